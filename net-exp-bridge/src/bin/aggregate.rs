@@ -0,0 +1,195 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use clap::{CommandFactory, Parser, ValueEnum};
+use clap::error::ErrorKind;
+use log::info;
+use serde_pickle::SerOptions;
+use net_exp_bridge::{Address, Frame};
+use net_exp_bridge::lacp::{Aggregator, HashPolicy};
+
+/// Duration frames are sent over, in milliseconds
+const ELAPSE_MS: u64 = 10_000;
+/// Time LACP runs before frames are sent, in milliseconds
+const WARMUP_MS: u64 = 5000;
+/// Step of simulated time, in milliseconds
+const STEP_MS: u64 = 10;
+/// Window of utilisation statistics, in milliseconds
+const WINDOW_MS: u64 = 100;
+/// Key of aggregators on both bridges
+const KEY: u16 = 1;
+
+/// Simulate two bridges connected by parallel links bundled with LACP.
+#[derive(Parser)]
+struct Args {
+    /// Count of parallel links
+    #[arg(long, default_value_t = 4)]
+    member: usize,
+    /// Hash policies to simulate, all of them if not specified
+    #[arg(long, value_enum)]
+    policy: Vec<HashPolicy>,
+    /// Member whose link drops, below the count of links
+    #[arg(long, default_value_t = 0)]
+    fail_member: usize,
+    /// Time the link drops, in milliseconds since frames begin
+    #[arg(long, value_name = "MILLIS")]
+    fail_at: Option<u64>,
+    /// Time the link recovers, in milliseconds since frames begin
+    #[arg(long, value_name = "MILLIS", requires = "fail_at")]
+    recover_at: Option<u64>,
+    /// Keep the failed link physically up while carrying nothing, leaving detection to LACP
+    #[arg(long)]
+    silent: bool,
+}
+
+/// Utilisation of members of an aggregation.
+struct Utilisation {
+    /// Frames through every member in every window.
+    windows: Vec<Vec<usize>>,
+    /// Frames lost for no member distributing or the member chosen being broken.
+    lost: usize,
+}
+
+impl Utilisation {
+    fn new(member: usize) -> Self {
+        let windows = vec![vec![0; member]; (ELAPSE_MS / WINDOW_MS) as usize + 1];
+        Utilisation { windows, lost: 0 }
+    }
+
+    fn rec(&mut self, t: u64, member: usize) {
+        self.windows[(t / WINDOW_MS) as usize][member] += 1;
+    }
+
+    /// Frames through every member in total.
+    fn total(&self) -> Vec<usize> {
+        let mut total = vec![0; self.windows[0].len()];
+        for window in &self.windows {
+            total.iter_mut().zip(window).for_each(|(x, y)| *x += y);
+        }
+        total
+    }
+
+    /// Export scatter of frames through every member in every window.
+    fn export_utilisation_scatter(&self, policy: &str) {
+        let sc_util = self.windows.iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let mut row = vec![(i as u64 * WINDOW_MS * 1000) as i64];
+                row.extend(x.iter().map(|x| *x as i64));
+                row
+            })
+            .collect::<Vec<_>>();
+        let path = format!("sc_lacp_{policy}.pkl");
+        serde_pickle::to_writer(&mut BufWriter::new(File::create(path).unwrap()),
+                                &sc_util, SerOptions::default()).unwrap();
+    }
+}
+
+/// Parallel link between two bridges.
+struct Link {
+    up: bool,
+    silent: bool,
+}
+
+impl Link {
+    /// Check if the link carries anything.
+    fn carries(&self) -> bool {
+        self.up && !self.silent
+    }
+}
+
+/// Simulate the aggregation with a hash policy, sending frames over it from one bridge to the
+/// other.
+fn simulate(args: &Args, frames: &[Frame], policy: HashPolicy) -> Utilisation {
+    let sys_a = Address { data: [0x02, 0x00, 0x00, 0x0a] };
+    let sys_b = Address { data: [0x02, 0x00, 0x00, 0x0b] };
+    let mut agg_a = Aggregator::new(sys_a, KEY, args.member, policy);
+    let mut agg_b = Aggregator::new(sys_b, KEY, args.member, policy);
+    let mut links = (0..args.member).map(|_| Link { up: true, silent: false }).collect::<Vec<_>>();
+    let mut util = Utilisation::new(args.member);
+    let mut last_pos = 0;
+    let mut distributing = Vec::new();
+    for now in (0..WARMUP_MS + ELAPSE_MS).step_by(STEP_MS as usize) {
+        let t = now.saturating_sub(WARMUP_MS);
+        // break or recover the link
+        let link = &mut links[args.fail_member];
+        let failed = now >= WARMUP_MS && args.fail_at.is_some_and(|x| t >= x)
+            && args.recover_at.is_none_or(|x| t < x);
+        if failed != (!link.up || link.silent) {
+            info!(target: "aggregate", "At {} ms, link of member {} {}.",
+                t, args.fail_member, if failed { "drops" } else { "recovers" });
+            if args.silent {
+                link.silent = failed;
+            } else {
+                link.up = !failed;
+                agg_a.set_link(args.fail_member, !failed);
+                agg_b.set_link(args.fail_member, !failed);
+            }
+        }
+        // exchange LACPDUs
+        for (member, pdu) in agg_a.tick(now) {
+            if links[member].carries() {
+                agg_b.receive(member, &pdu, now);
+            }
+        }
+        for (member, pdu) in agg_b.tick(now) {
+            if links[member].carries() {
+                agg_a.receive(member, &pdu, now);
+            }
+        }
+        if agg_a.distributing() != distributing {
+            distributing = agg_a.distributing();
+            info!(target: "aggregate", "At {} ms, distributing on members {:?}.",
+                now as i64 - WARMUP_MS as i64, distributing);
+        }
+        // send frames of this step
+        if now < WARMUP_MS {
+            continue;
+        }
+        let pos = ((t + STEP_MS) as usize * frames.len() / ELAPSE_MS as usize).min(frames.len());
+        for frame in &frames[last_pos..pos] {
+            match agg_a.select(frame) {
+                Some(member) if links[member].carries() => util.rec(t, member),
+                _ => util.lost += 1,
+            }
+        }
+        last_pos = pos;
+    }
+    util
+}
+
+/// Load generated frames from disk.
+fn load_frames() -> Vec<Frame> {
+    let frame = BufReader::new(File::open("frame.rmp").unwrap());
+    rmp_serde::from_read(frame).unwrap()
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+    if args.fail_member >= args.member {
+        Args::command()
+            .error(ErrorKind::ValueValidation,
+                   format!("--fail-member {} is not below --member {}", args.fail_member, args.member))
+            .exit();
+    }
+    let policies = if args.policy.is_empty() {
+        HashPolicy::value_variants().to_vec()
+    } else {
+        args.policy.clone()
+    };
+    let frames = load_frames();
+
+    let mut w = BufWriter::new(File::create("lacp_utilisation.txt").unwrap());
+    for policy in policies {
+        let name = policy.to_possible_value().unwrap().get_name().to_string();
+        info!(target: "aggregate", "Simulating with policy {}...", name);
+        let util = simulate(&args, &frames, policy);
+        let total = util.total();
+        let sum = total.iter().sum::<usize>().max(1);
+        for (member, cnt) in total.iter().enumerate() {
+            writeln!(w, "{} {} {} {:.4}", name, member, cnt, *cnt as f64 / sum as f64).unwrap();
+        }
+        info!(target: "aggregate", "Policy {} spread frames as {:?}, losing {}.", name, total, util.lost);
+        util.export_utilisation_scatter(&name);
+    }
+}
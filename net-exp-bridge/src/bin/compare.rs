@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use clap::{Parser, ValueEnum};
+use log::{error, info, warn};
+use serde::Deserialize;
+use net_exp_bridge::histogram::Percentiles;
+use net_exp_bridge::plot;
+use net_exp_bridge::stats::{mean, variance, welch_t_test};
+
+/// Compare results of two sets of runs, such as aging on against off.
+///
+/// A directory holding `summary.json` is taken as one run. Otherwise runs are taken from its
+/// subdirectories, for example those of repeated seeds put in one directory by `sweep --out`.
+///
+/// Throughput is only compared when every run has `latency.json`, giving the seconds it lasted.
+///
+/// Exits with 1 if a checked metric regresses, and with 2 if runs cannot be loaded, a checked metric
+/// cannot be compared or charts cannot be rendered.
+#[derive(Parser)]
+struct Args {
+    /// Directory of runs taken as the baseline
+    base: PathBuf,
+    /// Directory of runs compared against the baseline
+    candidate: PathBuf,
+    /// Metrics checked for regression
+    #[arg(long, value_delimiter = ',')]
+    fail_on: Vec<Metric>,
+    /// Change for the worse of a checked metric tolerated, in percent
+    #[arg(long, default_value_t = 5.0)]
+    threshold: f64,
+    /// Significance level a regression must reach when both sides have repeated runs
+    #[arg(long, default_value_t = 0.05)]
+    alpha: f64,
+    /// Directory overlaid charts are rendered to
+    #[arg(long, default_value = ".")]
+    out: PathBuf,
+}
+
+/// Metric of a run compared.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum Metric {
+    /// Frames dispatched or discarded per second
+    Throughput,
+    /// Ratio of frames discarded to frames dispatched or discarded
+    DiscardRate,
+    P50,
+    P90,
+    P99,
+    P999,
+    Max,
+}
+
+impl Metric {
+    const ALL: [Metric; 7] = [
+        Metric::Throughput, Metric::DiscardRate,
+        Metric::P50, Metric::P90, Metric::P99, Metric::P999, Metric::Max,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Metric::Throughput => "throughput/s",
+            Metric::DiscardRate => "discard rate",
+            Metric::P50 => "p50/us",
+            Metric::P90 => "p90/us",
+            Metric::P99 => "p99/us",
+            Metric::P999 => "p99.9/us",
+            Metric::Max => "max/us",
+        }
+    }
+
+    fn higher_is_better(&self) -> bool {
+        matches!(self, Metric::Throughput)
+    }
+}
+
+/// Part of `summary.json` compared.
+#[derive(Deserialize)]
+struct Summary {
+    dispatch: usize,
+    discard: usize,
+    latency: Percentiles,
+}
+
+/// Part of a window of `latency.json` compared.
+#[derive(Deserialize)]
+struct LatencyWindow {
+    second: usize,
+    all: Percentiles,
+}
+
+#[derive(Deserialize)]
+struct LatencyFile {
+    windows: Vec<LatencyWindow>,
+}
+
+/// Figure of a window of latencies plotted over time.
+type Figure = fn(&Percentiles) -> f64;
+
+/// Results of one run.
+struct Run {
+    summary: Summary,
+    windows: Vec<LatencyWindow>,
+}
+
+impl Run {
+    fn load(dir: &Path) -> Result<Run, String> {
+        let path = dir.join("summary.json");
+        let file = File::open(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let summary = serde_json::from_reader(BufReader::new(file)).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let path = dir.join("latency.json");
+        let windows = match File::open(&path) {
+            Ok(file) => serde_json::from_reader::<_, LatencyFile>(BufReader::new(file))
+                .map_err(|err| format!("{}: {}", path.display(), err))?
+                .windows,
+            Err(_) => {
+                warn!(target: "compare", "No latency.json in {}, time series left empty.", dir.display());
+                Vec::new()
+            }
+        };
+        Ok(Run { summary, windows })
+    }
+
+    /// Value of a metric, none for throughput if the run has no windows of latencies.
+    fn value(&self, metric: Metric) -> Option<f64> {
+        let done = self.summary.dispatch + self.summary.discard;
+        let x = &self.summary.latency;
+        Some(match metric {
+            Metric::Throughput if self.windows.is_empty() => return None,
+            Metric::Throughput => done as f64 / self.windows.len() as f64,
+            Metric::DiscardRate => self.summary.discard as f64 / done.max(1) as f64,
+            Metric::P50 => x.p50,
+            Metric::P90 => x.p90,
+            Metric::P99 => x.p99,
+            Metric::P999 => x.p999,
+            Metric::Max => x.max,
+        })
+    }
+}
+
+/// Load runs in a directory, or the directory itself as one run.
+fn load_runs(dir: &Path) -> Result<Vec<Run>, String> {
+    if dir.join("summary.json").exists() {
+        return Ok(vec![Run::load(dir)?]);
+    }
+    let mut dirs = Vec::new();
+    for x in fs::read_dir(dir).map_err(|err| format!("{}: {}", dir.display(), err))? {
+        let path = x.map_err(|err| format!("{}: {}", dir.display(), err))?.path();
+        if path.join("summary.json").exists() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    if dirs.is_empty() {
+        return Err(format!("{}: no runs found", dir.display()));
+    }
+    dirs.iter().map(|x| Run::load(x)).collect()
+}
+
+/// Series of a figure of every second, averaged over runs and aligned on the second.
+fn align(runs: &[Run], figure: impl Fn(&Percentiles) -> f64) -> Vec<(f64, f64)> {
+    let mut seconds = BTreeMap::<usize, Vec<f64>>::new();
+    for run in runs {
+        for x in run.windows.iter() {
+            seconds.entry(x.second).or_default().push(figure(&x.all));
+        }
+    }
+    seconds.into_iter()
+        .map(|(second, x)| (second as f64, x.iter().sum::<f64>() / x.len() as f64))
+        .collect()
+}
+
+/// Comparison of a metric between two sets of runs.
+struct Comparison {
+    metric: Metric,
+    base: f64,
+    candidate: f64,
+    /// Relative change, in percent.
+    change: f64,
+    /// P-value of the change, if both sides have repeated runs.
+    p: Option<f64>,
+}
+
+impl Comparison {
+    /// Comparison of a metric, none if a run lacks it.
+    fn new(metric: Metric, base: &[Run], candidate: &[Run]) -> Option<Self> {
+        let a = base.iter().map(|x| x.value(metric)).collect::<Option<Vec<_>>>()?;
+        let b = candidate.iter().map(|x| x.value(metric)).collect::<Option<Vec<_>>>()?;
+        let (base, candidate) = (mean(&a), mean(&b));
+        let change = if base == 0.0 {
+            if candidate == 0.0 { 0.0 } else { f64::INFINITY.copysign(candidate) }
+        } else {
+            (candidate - base) / base.abs() * 100.0
+        };
+        let p = welch_t_test(&a, &b).map(|x| x.p);
+        Some(Comparison { metric, base, candidate, change, p })
+    }
+
+    /// Change for the worse, in percent.
+    fn worsening(&self) -> f64 {
+        if self.metric.higher_is_better() { -self.change } else { self.change }
+    }
+}
+
+/// Standard deviation of a metric over runs, as shown next to its mean.
+fn spread(runs: &[Run], metric: Metric) -> f64 {
+    variance(&runs.iter().filter_map(|x| x.value(metric)).collect::<Vec<_>>()).sqrt()
+}
+
+/// Render charts of the series of both sides overlaid.
+fn export_charts(out: &Path, base: &[Run], candidate: &[Run]) -> Result<(), String> {
+    fs::create_dir_all(out).map_err(|err| format!("{}: {}", out.display(), err))?;
+    let charts: [(&str, &str, Figure); 3] = [
+        ("compare_throughput", "frames/s", |x| x.count as f64),
+        ("compare_p50", "p50 latency/us", |x| x.p50),
+        ("compare_p99", "p99 latency/us", |x| x.p99),
+    ];
+    for (name, y_label, figure) in charts {
+        let a = align(base, figure);
+        let b = align(candidate, figure);
+        let path = out.join(name);
+        plot::lines(Some(&path), "time/s", y_label, &[("base", &a), ("candidate", &b)])
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        info!(target: "compare", "Rendered {}.svg and {}.png.", path.display(), path.display());
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    env_logger::init();
+    let args = Args::parse();
+    let (base, candidate) = match (load_runs(&args.base), load_runs(&args.candidate)) {
+        (Ok(base), Ok(candidate)) => (base, candidate),
+        (Err(err), _) | (_, Err(err)) => {
+            error!(target: "compare", "Cannot load runs, {}.", err);
+            return ExitCode::from(2);
+        }
+    };
+    info!(target: "compare", "Comparing {} runs in {} against {} runs in {}.",
+        candidate.len(), args.candidate.display(), base.len(), args.base.display());
+    if base.len() < 2 || candidate.len() < 2 {
+        warn!(target: "compare", "Significance needs at least 2 runs on each side, not tested.");
+    }
+
+    println!("{:<14}{:>14}{:>10}{:>14}{:>10}{:>10}{:>10}",
+             "metric", "base", "±", "candidate", "±", "change", "p");
+    let comparisons = Metric::ALL.iter()
+        .filter_map(|x| Comparison::new(*x, &base, &candidate))
+        .collect::<Vec<_>>();
+    if comparisons.iter().all(|x| x.metric != Metric::Throughput) {
+        warn!(target: "compare", "Runs without latency.json, throughput not compared.");
+    }
+    for x in comparisons.iter() {
+        let p = x.p.map_or("-".to_string(), |p| format!("{:.4}", p));
+        println!("{:<14}{:>14.4}{:>10.4}{:>14.4}{:>10.4}{:>+9.2}%{:>10}",
+                 x.metric.name(), x.base, spread(&base, x.metric),
+                 x.candidate, spread(&candidate, x.metric), x.change, p);
+    }
+    if let Err(err) = export_charts(&args.out, &base, &candidate) {
+        error!(target: "compare", "Cannot render charts, {}.", err);
+        return ExitCode::from(2);
+    }
+
+    let mut regressed = false;
+    for metric in args.fail_on.iter() {
+        let Some(x) = comparisons.iter().find(|x| x.metric == *metric) else {
+            error!(target: "compare", "Cannot check {}, not compared.", metric.name());
+            return ExitCode::from(2);
+        };
+        let significant = x.p.is_none_or(|p| p < args.alpha);
+        if x.worsening() > args.threshold && significant {
+            println!("regression: {} worse by {:.2}% (threshold {}%)", metric.name(), x.worsening(), args.threshold);
+            regressed = true;
+        }
+    }
+    if regressed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
@@ -1,9 +1,15 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use rand::prelude::*;
+use rand::distributions::WeightedIndex;
 use net_exp_bridge::{Address, Frame, FrameData, Segment};
+use net_exp_bridge::igmp::{self, IgmpMessage};
 use std::io::{BufWriter, Write};
+use clap::{CommandFactory, Parser};
+use clap::error::ErrorKind;
 use log::info;
+use rand::rngs::StdRng;
+use serde::Serialize;
 
 /// Count of valid addresses
 const VALID_ADDR_CNT: usize = 5000;
@@ -15,6 +21,95 @@ const SEG_CNT: usize = 100;
 const VALID_FRAME_CNT: usize = 1000_0000;
 /// Count of invalid frames
 const INVALID_FRAME_CNT: usize = 10_0000;
+/// Count of frames with spoofed source addresses
+const SPOOFED_FRAME_CNT: usize = 0;
+/// Count of segments spoofed frames come from
+const SPOOF_SEG_CNT: usize = 1;
+/// Count of frames flooded by an attacker with random addresses
+const FLOOD_FRAME_CNT: usize = 0;
+/// Position in frame sequence where the flooding begins, as a fraction
+const FLOOD_BEGIN: f64 = 0.5;
+/// Count of multicast groups
+const GROUP_CNT: usize = 0;
+/// Count of members of every multicast group
+const GROUP_MEMBER_CNT: usize = 20;
+/// Count of frames sent to multicast groups
+const GROUP_FRAME_CNT: usize = 10_0000;
+/// Count of IGMP queriers
+const QUERIER_CNT: usize = 2;
+/// Count of IGMP rounds of queries and reports, spread evenly over the frame sequence
+const IGMP_ROUND_CNT: usize = 20;
+/// Probability that a member leaves its group in a round
+const LEAVE_PROB: f64 = 0.1;
+
+/// Generate addresses, segments and frames for the simulation.
+#[derive(Parser, Serialize)]
+struct Args {
+    /// Seed of random generation, picked randomly if not specified
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Count of valid addresses, at least 2 for frames to go from one to another
+    #[arg(long, default_value_t = VALID_ADDR_CNT, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(2..))]
+    valid_addr: usize,
+    /// Count of invalid addresses
+    #[arg(long, default_value_t = INVALID_ADDR_CNT)]
+    invalid_addr: usize,
+    /// Count of segments, at least 1
+    #[arg(long, default_value_t = SEG_CNT, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    seg: usize,
+    /// Count of valid frames
+    #[arg(long, default_value_t = VALID_FRAME_CNT)]
+    valid_frame: usize,
+    /// Count of frames destined to invalid addresses
+    #[arg(long, default_value_t = INVALID_FRAME_CNT)]
+    invalid_frame: usize,
+    /// Count of frames with source spoofed from invalid addresses
+    #[arg(long, default_value_t = SPOOFED_FRAME_CNT)]
+    spoofed_frame: usize,
+    /// Count of segments spoofed frames come from, at least 1 if any spoofed frame is generated
+    #[arg(long, default_value_t = SPOOF_SEG_CNT)]
+    spoof_seg: usize,
+    /// Count of frames flooded by an attacker with random source and destination addresses
+    #[arg(long, default_value_t = FLOOD_FRAME_CNT)]
+    flood_frame: usize,
+    /// Position in frame sequence where the flooding begins, from 0 to 1
+    #[arg(long, default_value_t = FLOOD_BEGIN)]
+    flood_begin: f64,
+    /// Count of multicast groups, with IGMP traffic generated if not zero, at most the 65280
+    /// groups left by the reserved ones
+    #[arg(long, default_value_t = GROUP_CNT, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(..=0xff00))]
+    group: usize,
+    /// Count of members of every multicast group, at least 1
+    #[arg(long, default_value_t = GROUP_MEMBER_CNT, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    group_member: usize,
+    /// Count of frames sent to multicast groups
+    #[arg(long, default_value_t = GROUP_FRAME_CNT)]
+    group_frame: usize,
+    /// Count of IGMP queriers
+    #[arg(long, default_value_t = QUERIER_CNT)]
+    querier: usize,
+    /// Count of IGMP rounds of queries and reports
+    #[arg(long, default_value_t = IGMP_ROUND_CNT)]
+    igmp_round: usize,
+    /// Skew addresses of valid frames by Zipf's law with the specified exponent, finite and not
+    /// negative, instead of picking them uniformly
+    #[arg(long, value_name = "EXPONENT", value_parser = parse_exponent)]
+    zipf: Option<f64>,
+}
+
+/// Parse an exponent of Zipf's law, for which every weight is finite and the first one positive.
+fn parse_exponent(s: &str) -> Result<f64, String> {
+    let x = s.parse::<f64>().map_err(|err| err.to_string())?;
+    if !x.is_finite() || x < 0.0 {
+        return Err(format!("{} is not a finite number of at least 0", s));
+    }
+    Ok(x)
+}
+
+/// Random number generator of `rand` API, seeded from `fastrand` so that one seed decides all.
+fn rng() -> StdRng {
+    StdRng::seed_from_u64(fastrand::u64(..))
+}
 
 /// Generate random byte array of specified size with `fastrand` API.
 fn gen_byte_arr<const N: usize>() -> [u8; N] {
@@ -23,30 +118,37 @@ fn gen_byte_arr<const N: usize>() -> [u8; N] {
     data
 }
 
-/// Generate a physical address.
+/// Generate a physical address, individual rather than group.
 fn gen_addr() -> Address {
-    Address { data: gen_byte_arr() }
+    let mut data: [u8; 4] = gen_byte_arr();
+    data[0] &= !1;
+    Address { data }
 }
 
-/// Generate a pool of physical addresses, unique.
-fn gen_addr_pool(count: usize) -> HashSet<Address> {
+/// Generate a pool of physical addresses, unique, in the order they are generated.
+fn gen_addr_pool(count: usize) -> Vec<Address> {
     let mut unique_set: HashSet<Address> = HashSet::with_capacity(count);
-    while unique_set.len() < count {
-        unique_set.insert(gen_addr());
+    let mut pool = Vec::with_capacity(count);
+    while pool.len() < count {
+        let addr = gen_addr();
+        if unique_set.insert(addr) {
+            pool.push(addr);
+        }
     }
-    unique_set
+    pool
 }
 
 /// Generate a pool of invalid addresses, unique and not clashing with valid ones.
-fn gen_invalid_addr_pool(addr_pool: &HashSet<Address>, count: usize) -> HashSet<Address> {
-    let mut unique_set: HashSet<Address> = HashSet::with_capacity(count);
-    while unique_set.len() < count {
+fn gen_invalid_addr_pool(addr_pool: &[Address], count: usize) -> Vec<Address> {
+    let mut unique_set: HashSet<Address> = HashSet::from_iter(addr_pool.iter().copied());
+    let mut pool = Vec::with_capacity(count);
+    while pool.len() < count {
         let addr = gen_addr();
-        if !addr_pool.contains(&addr) {
-            unique_set.insert(addr);
+        if unique_set.insert(addr) {
+            pool.push(addr);
         }
     }
-    unique_set
+    pool
 }
 
 /// Generate a segment.
@@ -54,13 +156,17 @@ fn gen_seg() -> Segment {
     Segment { data: gen_byte_arr() }
 }
 
-/// Generate a pool of segments.
-fn gen_seg_pool(count: usize) -> HashSet<Segment> {
+/// Generate a pool of segments, in the order they are generated.
+fn gen_seg_pool(count: usize) -> Vec<Segment> {
     let mut unique_set: HashSet<Segment> = HashSet::with_capacity(count);
-    while unique_set.len() < count {
-        unique_set.insert(gen_seg());
+    let mut pool = Vec::with_capacity(count);
+    while pool.len() < count {
+        let seg = gen_seg();
+        if unique_set.insert(seg) {
+            pool.push(seg);
+        }
     }
-    unique_set
+    pool
 }
 
 /// Generate frame data.
@@ -68,29 +174,139 @@ fn gen_data() -> FrameData {
     gen_byte_arr()
 }
 
-/// Generate frame with specified pools for source and destination addresses.
-fn gen_frame(src_pool: &[Address], src_seg_pool: &[Segment], dst_pool: &[Address]) -> Frame {
-    let src = src_pool[fastrand::usize(0..src_pool.len())];
-    let src_seg = src_seg_pool[fastrand::usize(0..src_seg_pool.len())];
+/// Generate frame with specified pools for source (with its segment) and destination addresses.
+fn gen_frame(src_pool: &[(Address, Segment)], dst_pool: &[Address]) -> Frame {
+    let (src, src_seg) = src_pool[fastrand::usize(0..src_pool.len())];
     let mut dst = src;
     while dst == src {
         dst = dst_pool[fastrand::usize(0..dst_pool.len())];
     }
     let data = gen_data();
-    Frame { src, src_seg, dst, data }
+    Frame { id: 0, src, src_seg, dst, data }
 }
 
 /// Generate a sequence of frames with `gen_frame` function.
-fn gen_frame_seq(src_pool: &[Address], src_seg_pool: &[Segment], dst_pool: &[Address], count: usize) -> Vec<Frame> {
+fn gen_frame_seq(src_pool: &[(Address, Segment)], dst_pool: &[Address], count: usize) -> Vec<Frame> {
+    let mut seq = Vec::with_capacity(count);
+    for _ in 0..count {
+        seq.push(gen_frame(src_pool, dst_pool));
+    }
+    seq
+}
+
+/// Generate a sequence of frames with addresses picked by Zipf's law, the k-th address of a pool
+/// being picked with weight `1 / k^exponent`.
+///
+/// A destination equal to the source is picked again among the other addresses by their weights,
+/// rather than retried, as retries would hardly ever end for a large exponent. The others are
+/// picked uniformly if their weights all vanish in floating point.
+fn gen_zipf_frame_seq(src_pool: &[(Address, Segment)], dst_pool: &[Address], count: usize, exponent: f64) -> Vec<Frame> {
+    let weight = |k: usize| 1.0 / (k as f64).powf(exponent);
+    let zipf = |len: usize| WeightedIndex::new((1..=len).map(weight)).unwrap();
+    let src_dist = zipf(src_pool.len());
+    let dst_dist = zipf(dst_pool.len());
+    // distributions of destinations other than the one at an index, by index
+    let mut others = HashMap::new();
+    let mut rng = rng();
+    let mut seq = Vec::with_capacity(count);
+    while seq.len() < count {
+        let (src, src_seg) = src_pool[src_dist.sample(&mut rng)];
+        let mut i = dst_dist.sample(&mut rng);
+        if dst_pool[i] == src {
+            let dist = others.entry(i).or_insert_with(|| {
+                let weights = (1..=dst_pool.len()).map(|k| if k == i + 1 { 0.0 } else { weight(k) });
+                WeightedIndex::new(weights).unwrap_or_else(|_| {
+                    WeightedIndex::new((0..dst_pool.len()).map(|j| if j == i { 0.0 } else { 1.0 })).unwrap()
+                })
+            });
+            i = dist.sample(&mut rng);
+        }
+        seq.push(Frame { id: 0, src, src_seg, dst: dst_pool[i], data: gen_data() });
+    }
+    seq
+}
+
+/// Generate a sequence of frames flooded from a segment, like `macof` does, with every address
+/// random.
+fn gen_flood_frame_seq(seg: Segment, count: usize) -> Vec<Frame> {
     let mut seq = Vec::with_capacity(count);
     for _ in 0..count {
-        seq.push(gen_frame(src_pool, src_seg_pool, dst_pool));
+        seq.push(Frame { id: 0, src: gen_addr(), src_seg: seg, dst: gen_addr(), data: gen_data() });
+    }
+    seq
+}
+
+/// Mix frames of an attacker into the sequence, from the specified position on.
+fn mix_attack(frame_seq: &mut Vec<Frame>, attack_seq: &[Frame], begin: f64) {
+    let begin = (frame_seq.len() as f64 * begin.clamp(0.0, 1.0)) as usize;
+    let mut tail = frame_seq.split_off(begin);
+    tail.extend_from_slice(attack_seq);
+    tail.shuffle(&mut rng());
+    frame_seq.extend(tail);
+}
+
+/// Generate a pool of multicast group addresses, avoiding the reserved ones.
+fn gen_group_pool(count: usize) -> Vec<Address> {
+    (0..count).map(|i| igmp::group(0x100 + i as u16)).collect()
+}
+
+/// Generate rounds of IGMP messages, in each of which queriers query and members report, with some
+/// members leaving and others joining to keep the count of members.
+fn gen_igmp_rounds(
+    addr_seg_seq: &[(Address, Segment)],
+    group_pool: &[Address],
+    member_count: usize,
+    querier_count: usize,
+    round_count: usize,
+) -> Vec<Vec<Frame>> {
+    let mut rng = rng();
+    let queriers = addr_seg_seq.choose_multiple(&mut rng, querier_count).copied().collect::<Vec<_>>();
+    let mut members = vec![Vec::<(Address, Segment)>::new(); group_pool.len()];
+    let mut rounds = Vec::with_capacity(round_count);
+    for _ in 0..round_count {
+        let mut round = Vec::new();
+        for (addr, seg) in &queriers {
+            round.push(IgmpMessage::Query.frame(*addr, *seg));
+        }
+        for (group, members) in group_pool.iter().zip(members.iter_mut()) {
+            members.retain(|(addr, seg)| {
+                let leave = rng.gen_bool(LEAVE_PROB);
+                let msg = if leave { IgmpMessage::Leave(*group) } else { IgmpMessage::Report(*group) };
+                round.push(msg.frame(*addr, *seg));
+                !leave
+            });
+            while members.len() < member_count.min(addr_seg_seq.len()) {
+                let (addr, seg) = addr_seg_seq[fastrand::usize(0..addr_seg_seq.len())];
+                if members.iter().all(|(x, _)| *x != addr) {
+                    members.push((addr, seg));
+                    round.push(IgmpMessage::Report(*group).frame(addr, seg));
+                }
+            }
+        }
+        round.shuffle(&mut rng);
+        rounds.push(round);
     }
+    rounds
+}
+
+/// Insert rounds of frames into the sequence, spread evenly from its beginning.
+fn mix_rounds(frame_seq: Vec<Frame>, rounds: Vec<Vec<Frame>>) -> Vec<Frame> {
+    let len = frame_seq.len();
+    let mut seq = Vec::with_capacity(len + rounds.iter().map(Vec::len).sum::<usize>());
+    let mut frames = frame_seq.into_iter();
+    let round_count = rounds.len();
+    for (i, round) in rounds.into_iter().enumerate() {
+        let begin = i * len / round_count;
+        let end = (i + 1) * len / round_count;
+        seq.extend(round);
+        seq.extend(frames.by_ref().take(end - begin));
+    }
+    seq.extend(frames);
     seq
 }
 
 /// Generate a mapping from address to segment from their pools.
-fn gen_addr_seg(addr_pool: Vec<Address>, seg_pool: &[Segment]) -> Vec<(Address, Segment)> {
+fn gen_addr_seg(addr_pool: &[Address], seg_pool: &[Segment]) -> Vec<(Address, Segment)> {
     let mut seq = Vec::with_capacity(addr_pool.len() * seg_pool.len());
     let least = addr_pool.len() / seg_pool.len();
     // assign segment for addresses
@@ -103,8 +319,21 @@ fn gen_addr_seg(addr_pool: Vec<Address>, seg_pool: &[Segment]) -> Vec<(Address,
     // treat remaining ones
     if seq.len() < addr_pool.len() {
         let begin = seq.len();
-        for i in begin..addr_pool.len() {
-            seq.push((addr_pool[i], seg_pool[fastrand::usize(0..seg_pool.len())]));
+        for addr in &addr_pool[begin..] {
+            seq.push((*addr, seg_pool[fastrand::usize(0..seg_pool.len())]));
+        }
+    }
+    seq
+}
+
+/// Generate source pool of spoofed frames, pairing invalid addresses with the segments they are
+/// injected from.
+fn gen_spoof_src(inv_addr_pool: &[Address], seg_pool: &[Segment], seg_count: usize) -> Vec<(Address, Segment)> {
+    let segs = seg_pool.choose_multiple(&mut rng(), seg_count).collect::<Vec<_>>();
+    let mut seq = Vec::with_capacity(inv_addr_pool.len() * segs.len());
+    for addr in inv_addr_pool {
+        for seg in &segs {
+            seq.push((*addr, **seg));
         }
     }
     seq
@@ -136,33 +365,79 @@ fn serialize(addr_seg_seq: &[(Address, Segment)], inv_addr_pool: &[Address], fra
 
 fn main() {
     env_logger::init();
+    let mut args = Args::parse();
+    if args.spoofed_frame > 0 && (args.spoof_seg == 0 || args.invalid_addr == 0) {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict,
+                   "--spoofed-frame needs --spoof-seg and --invalid-addr of at least 1 to spoof from")
+            .exit();
+    }
+    if args.invalid_frame > 0 && args.invalid_addr == 0 {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, "--invalid-frame needs --invalid-addr of at least 1 to send to")
+            .exit();
+    }
+    let seed = *args.seed.get_or_insert_with(|| fastrand::u64(..));
+    info!("Seed {}...", seed);
+    fastrand::seed(seed);
 
     // create pools
     info!("Address pool...");
-    let addr_pool = gen_addr_pool(VALID_ADDR_CNT);
+    let addr_pool = gen_addr_pool(args.valid_addr);
     info!("Invalid address pool...");
-    let inv_addr_pool = gen_addr_pool(INVALID_ADDR_CNT);
+    let inv_addr_pool = gen_invalid_addr_pool(&addr_pool, args.invalid_addr);
     info!("Segment pool...");
-    let seg_pool = gen_seg_pool(SEG_CNT);
+    let seg_pool = gen_seg_pool(args.seg);
 
-    let addr_pool = addr_pool.into_iter().collect::<Vec<_>>();
-    let inv_addr_pool = inv_addr_pool.into_iter().collect::<Vec<_>>();
-    let seg_pool = seg_pool.into_iter().collect::<Vec<_>>();
+    // generate segment mapping
+    let addr_seg_seq = gen_addr_seg(&addr_pool, &seg_pool);
 
     // fabricate frames
     info!("Frame sequence...");
     let frame_seq = {
-        let mut frame_seq = gen_frame_seq(
-            &addr_pool, &seg_pool, &addr_pool, VALID_FRAME_CNT);
+        let mut frame_seq = match args.zipf {
+            Some(exponent) => gen_zipf_frame_seq(
+                &addr_seg_seq, &addr_pool, args.valid_frame, exponent),
+            None => gen_frame_seq(
+                &addr_seg_seq, &addr_pool, args.valid_frame),
+        };
         let inv_frame_seq = gen_frame_seq(
-            &addr_pool, &seg_pool, &inv_addr_pool, INVALID_FRAME_CNT);
+            &addr_seg_seq, &inv_addr_pool, args.invalid_frame);
         frame_seq.extend_from_slice(&inv_frame_seq);
-        frame_seq.shuffle(&mut thread_rng());
+        if args.spoofed_frame > 0 {
+            let spoof_src = gen_spoof_src(&inv_addr_pool, &seg_pool, args.spoof_seg);
+            let spoof_frame_seq = gen_frame_seq(
+                &spoof_src, &addr_pool, args.spoofed_frame);
+            frame_seq.extend_from_slice(&spoof_frame_seq);
+        }
+        let group_pool = gen_group_pool(args.group);
+        if args.group > 0 {
+            let group_frame_seq = gen_frame_seq(
+                &addr_seg_seq, &group_pool, args.group_frame);
+            frame_seq.extend_from_slice(&group_frame_seq);
+        }
+        frame_seq.shuffle(&mut rng());
+        if args.group > 0 {
+            info!("IGMP rounds...");
+            let rounds = gen_igmp_rounds(
+                &addr_seg_seq, &group_pool, args.group_member, args.querier, args.igmp_round);
+            frame_seq = mix_rounds(frame_seq, rounds);
+        }
+        if args.flood_frame > 0 {
+            let seg = seg_pool[fastrand::usize(0..seg_pool.len())];
+            info!("Flooding from segment {}...", seg);
+            let flood_frame_seq = gen_flood_frame_seq(seg, args.flood_frame);
+            mix_attack(&mut frame_seq, &flood_frame_seq, args.flood_begin);
+        }
+        // number frames in the order they are sent
+        for (id, frame) in frame_seq.iter_mut().enumerate() {
+            frame.id = id as u64;
+        }
         frame_seq
     };
 
-    // generate segment mapping
-    let addr_seg_seq = gen_addr_seg(addr_pool, &seg_pool);
     info!("Serialization...");
     serialize(&addr_seg_seq, &inv_addr_pool, &frame_seq);
-}
\ No newline at end of file
+    let params = File::create("generate.json").unwrap();
+    serde_json::to_writer_pretty(BufWriter::new(params), &args).unwrap();
+}
@@ -1,20 +1,107 @@
-use std::collections::{BTreeMap, HashMap};
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::sync::mpsc::{Receiver, Sender};
-use std::{fs, thread};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread;
 use std::f64::consts::PI;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
-use log::info;
+use clap::{Parser, ValueEnum};
+use log::{error, info, trace, warn};
 use serde_pickle::SerOptions;
-use net_exp_bridge::{Address, Frame, Segment};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use net_exp_bridge::{openmetrics, plot, Address, Frame, Segment};
+use net_exp_bridge::histogram::{Histogram, Percentiles};
+use net_exp_bridge::igmp::{IgmpMessage, SnoopingTable};
+use net_exp_bridge::live::{Dashboard, HistogramCounts, LiveHistogram, LiveMetrics};
+use net_exp_bridge::report::HtmlReport;
+use net_exp_bridge::trace::{FrameState, Tracer};
+use net_exp_bridge::sink::{self, Column, StatsFormat};
+use net_exp_bridge::security::{PortSecurity, PortSecurityConfig, Verdict, ViolationAction};
 
 const ELAPSE_SEC: usize = 10;
 
+/// Set once the simulation is interrupted, so that it stops early and exports what it has.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Simulate the bridge with generated frames.
+#[derive(Parser, Serialize)]
+struct Args {
+    /// Enable port security, limiting the count of secure addresses per segment
+    #[arg(long, value_name = "COUNT")]
+    max_addr: Option<usize>,
+    /// Keep secure addresses learned across runs
+    #[arg(long, requires = "max_addr")]
+    sticky: bool,
+    /// File sticky addresses are loaded from and saved to
+    #[arg(long, default_value = "sticky.rmp")]
+    sticky_file: PathBuf,
+    /// Action taken when a source violates port security
+    #[arg(long, value_enum, default_value_t = ViolationAction::Shutdown)]
+    violation: ViolationAction,
+    /// Limit the count of addresses the bridge table holds
+    #[arg(long, value_name = "COUNT")]
+    table_size: Option<usize>,
+    /// Forget addresses not seen for the specified milliseconds
+    #[arg(long, value_name = "MILLIS")]
+    aging: Option<u64>,
+    /// Forward frames of multicast groups only to segments with members, learned by IGMP snooping
+    #[arg(long)]
+    igmp_snooping: bool,
+    /// Forget group members and queriers not heard for the specified milliseconds
+    #[arg(long, value_name = "MILLIS", default_value_t = 2000)]
+    membership: u64,
+    /// Pattern of frame arrivals over the duration of simulation
+    #[arg(long, value_enum, default_value_t = Arrival::HalfCircle)]
+    arrival: Arrival,
+    /// Simulate in virtual time on one thread, so that the same frames always give the same results
+    #[arg(long)]
+    deterministic: bool,
+    /// Formats statistics are written in
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "pickle")]
+    stats_format: Vec<StatsFormat>,
+    /// Trace every transition of frames to a file, to be queried with `trace`
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "trace.rmp")]
+    trace: Option<PathBuf>,
+    /// Show a live dashboard in the terminal
+    #[arg(long)]
+    dashboard: bool,
+    /// Serve metrics in OpenMetrics text format at http://127.0.0.1:<PORT>/metrics
+    #[arg(long, value_name = "PORT")]
+    metrics_port: Option<u16>,
+    /// Skip writing charts to SVG and PNG files, leaving them embedded in `report.html` only
+    #[arg(long)]
+    no_plot: bool,
+    /// Save the state of deterministic simulation to a file periodically, to be resumed from
+    #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "checkpoint.rmp", requires = "deterministic")]
+    checkpoint: Option<PathBuf>,
+    /// Virtual milliseconds between checkpoints
+    #[arg(long, value_name = "MILLIS", default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+    checkpoint_every: u64,
+    /// Resume deterministic simulation from a checkpoint, taken with the same frames and options
+    #[arg(long, value_name = "FILE", requires = "deterministic")]
+    resume: Option<PathBuf>,
+}
+
+impl Args {
+    /// Port security configuration, if enabled.
+    fn port_security(&self) -> Option<PortSecurityConfig> {
+        self.max_addr.map(|max_addr| PortSecurityConfig {
+            max_addr,
+            sticky: self.sticky,
+            action: self.violation,
+        })
+    }
+}
+
 /// Event that bridge receives.
 enum Event {
-    /// Incoming request of routing a frame.
-    Request(Frame),
+    /// Incoming request of routing a frame, along with the time it arrives.
+    Request(Frame, Duration),
     /// Found segment that accept an address.
     Success(Address, Segment),
     /// No segment accepts an address.
@@ -31,11 +118,53 @@ enum Command {
     Dispatch(Frame, Segment),
     /// Discard a frame
     Discard(Frame),
+    /// Forward a frame of a group to segments
+    Multicast(Frame, BTreeSet<Segment>),
+}
+
+/// Clock telling the time since the simulation starts.
+#[derive(Clone)]
+enum Clock {
+    /// Wall clock, for simulation in real time.
+    Real(Instant),
+    /// Virtual clock in nanoseconds, advanced by the simulation itself for deterministic runs.
+    Virtual(Arc<AtomicU64>),
 }
 
-/// Waiting list of frames.
+impl Clock {
+    fn start_real() -> Self {
+        Clock::Real(Instant::now())
+    }
+
+    fn start_virtual() -> Self {
+        Clock::Virtual(Arc::new(AtomicU64::new(0)))
+    }
+
+    fn now(&self) -> Duration {
+        match self {
+            Clock::Real(init) => init.elapsed(),
+            Clock::Virtual(t) => Duration::from_nanos(t.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Advance the virtual clock by some time.
+    fn advance(&self, dur: Duration) {
+        if let Clock::Virtual(t) = self {
+            t.fetch_add(dur.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Advance the virtual clock to some time, if it is not there yet.
+    fn advance_to(&self, time: Duration) {
+        if let Clock::Virtual(t) = self {
+            t.fetch_max(time.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Waiting list of frames, along with the times they arrive.
 struct Holder {
-    map: BTreeMap<Address, Vec<Frame>>
+    map: BTreeMap<Address, Vec<(Frame, Duration)>>
 }
 
 impl Holder {
@@ -49,14 +178,14 @@ impl Holder {
     }
 
     /// Hold a frame.
-    fn hold(&mut self, frame: Frame) {
+    fn hold(&mut self, frame: Frame, arrival: Duration) {
         let frames = self.map.entry(frame.dst)
-            .or_insert_with(Vec::new);
-        frames.push(frame);
+            .or_default();
+        frames.push((frame, arrival));
     }
 
     /// Release frames of the same address.
-    fn release(&mut self, addr: Address) -> Vec<Frame> {
+    fn release(&mut self, addr: Address) -> Vec<(Frame, Duration)> {
         self.map.remove(&addr).unwrap_or_default()
     }
 
@@ -65,7 +194,74 @@ impl Holder {
     }
 }
 
+/// Mapping from addresses to segments, optionally with limited capacity and aging.
+struct Table {
+    map: BTreeMap<Address, (Segment, Duration)>,
+    capacity: Option<usize>,
+    aging: Option<Duration>,
+    refused: usize,
+    last_purge: Duration,
+    clock: Clock,
+}
+
+impl Table {
+    fn new(capacity: Option<usize>, aging: Option<Duration>, clock: Clock) -> Self {
+        Table { map: BTreeMap::new(), capacity, aging, refused: 0, last_purge: clock.now(), clock }
+    }
+
+    fn expired(&self, t: &Duration) -> bool {
+        self.aging.is_some_and(|x| self.clock.now().saturating_sub(*t) > x)
+    }
+
+    fn get(&self, addr: &Address) -> Option<&Segment> {
+        self.map.get(addr)
+            .filter(|(_, t)| !self.expired(t))
+            .map(|(seg, _)| seg)
+    }
+
+    fn contains(&self, addr: &Address) -> bool {
+        self.get(addr).is_some()
+    }
+
+    /// Refresh the age of an address seen again.
+    fn refresh(&mut self, addr: &Address) {
+        if let Some((_, t)) = self.map.get_mut(addr) {
+            *t = self.clock.now();
+        }
+    }
+
+    /// Remove aged addresses, at most once every 10 milliseconds.
+    fn purge(&mut self) {
+        let aging = if let Some(val) = self.aging { val } else { return };
+        let now = self.clock.now();
+        if now.saturating_sub(self.last_purge) < Duration::from_millis(10) {
+            return;
+        }
+        self.map.retain(|_, (_, t)| now.saturating_sub(*t) <= aging);
+        self.last_purge = now;
+    }
+
+    /// Learn the segment of an address, refused if the address is new and the table is full.
+    fn learn(&mut self, addr: Address, seg: Segment) {
+        let full = |table: &Table| !table.map.contains_key(&addr)
+            && table.capacity.is_some_and(|x| table.map.len() >= x);
+        if full(self) {
+            self.purge();
+        }
+        if full(self) {
+            self.refused += 1;
+            return;
+        }
+        self.map.insert(addr, (seg, self.clock.now()));
+    }
+
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+}
+
 /// Statistics of bridge
+#[derive(Serialize, Deserialize)]
 pub enum BridgeStatRecord {
     Broadcast(Frame),
     Dispatch(Frame),
@@ -85,39 +281,39 @@ impl BridgeStatRecord {
 /// Record of bridge statistics.
 pub struct BridgeStat {
     pub records: Vec<BridgeStatRecord>,
-    pub times: Vec<Instant>,
-    pub init: Instant,
+    pub times: Vec<Duration>,
+    clock: Clock,
 }
 
 impl BridgeStat {
-    fn new() -> Self {
-        BridgeStat { records: Vec::new(), times: Vec::new(), init: Instant::now() }
+    fn new(clock: Clock) -> Self {
+        BridgeStat { records: Vec::new(), times: Vec::new(), clock }
     }
 
     fn broadcast(&mut self, frame: Frame) {
         self.records.push(BridgeStatRecord::Broadcast(frame));
-        self.times.push(Instant::now());
+        self.times.push(self.clock.now());
     }
 
     fn dispatch(&mut self, frame: Frame) {
         self.records.push(BridgeStatRecord::Dispatch(frame));
-        self.times.push(Instant::now());
+        self.times.push(self.clock.now());
     }
 
     fn discard(&mut self, frame: Frame) {
         self.records.push(BridgeStatRecord::Discard(frame));
-        self.times.push(Instant::now());
+        self.times.push(self.clock.now());
     }
 
     fn len(&self) -> usize {
         self.records.len()
     }
 
-    /// Export scatter of different types of activities.
-    fn export_activity_scatter(&self) {
+    /// Times of different types of activities, in microseconds.
+    fn activities(&self) -> [Vec<i64>; 3] {
         let sc_src = self.records.iter()
             .zip(self.times.iter())
-            .map(|(x, y)| (x, y.duration_since(self.init).as_micros()));
+            .map(|(x, y)| (x, y.as_micros()));
 
         let mut sc_broadcast = Vec::with_capacity(self.records.len());
         let mut sc_dispatch = Vec::with_capacity(self.records.len());
@@ -130,6 +326,12 @@ impl BridgeStat {
                 BridgeStatRecord::Discard(_) => sc_discard.push(y as i64),
             }
         }
+        [sc_broadcast, sc_dispatch, sc_discard]
+    }
+
+    /// Export scatter of different types of activities.
+    fn export_activity_scatter(&self) {
+        let [sc_broadcast, sc_dispatch, sc_discard] = self.activities();
 
         let mut w_broadcast = BufWriter::new(File::create("sc_broadcast_activity.pkl").unwrap());
         let mut w_dispatch = BufWriter::new(File::create("sc_dispatch_activity.pkl").unwrap());
@@ -140,141 +342,817 @@ impl BridgeStat {
         serde_pickle::to_writer(&mut w_discard, &sc_discard, SerOptions::default()).unwrap();
     }
 
-    /// Export scatter of latencies of frames broadcast.
-    fn export_latency_scatter(&self) {
-        let mut hold_map = HashMap::<Frame, u128>::new();
+    /// Plot density histogram of different types of activities, to files if specified.
+    fn plot_activity(&self, to_file: bool) -> String {
+        let [sc_broadcast, sc_dispatch, sc_discard] = self.activities()
+            .map(|x| x.into_iter().map(|x| x as f64 / 1e6).collect::<Vec<_>>());
+        plot::histogram(to_file.then_some(Path::new("activity")), "time (s)", &[
+            ("broadcast activity", &sc_broadcast),
+            ("dispatch activity", &sc_dispatch),
+            ("discard activity", &sc_discard),
+        ], 400).unwrap()
+    }
+
+    /// Latencies of frames broadcast along with the frames and the times they are broadcast, in
+    /// microseconds.
+    fn latencies(&self) -> Vec<(u64, i64, i64)> {
+        let mut hold_map = HashMap::<u64, u128>::new();
         let mut latencies = Vec::with_capacity(self.records.len());
         for (rec, t) in self.records.iter().zip(self.times.iter()) {
-            let t = t.duration_since(self.init).as_micros();
+            let t = t.as_micros();
             match rec {
                 BridgeStatRecord::Broadcast(frame) => {
-                    hold_map.insert(frame.clone(), t);
+                    hold_map.insert(frame.id, t);
                 }
                 BridgeStatRecord::Dispatch(frame) | BridgeStatRecord::Discard(frame) => {
-                    let begin = if let Some(val) = hold_map.remove(&frame) { val } else {
+                    let begin = if let Some(val) = hold_map.remove(&frame.id) { val } else {
                         continue
                     };
                     let lat = t - begin;
-                    latencies.push(vec![begin as i64, lat as i64]);
+                    latencies.push((frame.id, begin as i64, lat as i64));
                 }
             }
         }
+        latencies
+    }
+
+    /// Table of activities, for sinks of statistics.
+    fn activity_table(&self) -> sink::Table {
+        let kinds = self.records.iter()
+            .map(|x| match x {
+                BridgeStatRecord::Broadcast(_) => "broadcast",
+                BridgeStatRecord::Dispatch(_) => "dispatch",
+                BridgeStatRecord::Discard(_) => "discard",
+            })
+            .collect();
+        sink::Table::new("activity")
+            .column("time_us", Column::Int(self.times.iter().map(|x| x.as_micros() as i64).collect()))
+            .column("kind", Column::Str(kinds))
+            .column("frame_id", Column::UInt(self.records.iter().map(|x| x.frame().id).collect()))
+    }
+
+    /// Table of latencies of frames broadcast, for sinks of statistics.
+    fn latency_table(&self) -> sink::Table {
+        let latencies = self.latencies();
+        sink::Table::new("latency")
+            .column("frame_id", Column::UInt(latencies.iter().map(|x| x.0).collect()))
+            .column("time_us", Column::Int(latencies.iter().map(|x| x.1).collect()))
+            .column("latency_us", Column::Int(latencies.iter().map(|x| x.2).collect()))
+    }
+
+    /// Export scatter of latencies of frames broadcast.
+    fn export_latency_scatter(&self) {
+        let latencies = self.latencies().into_iter()
+            .map(|(_, x, y)| vec![x, y])
+            .collect::<Vec<_>>();
         serde_pickle::to_writer(&mut BufWriter::new(File::create("sc_latency.pkl").unwrap()),
                                 &latencies, SerOptions::default()).unwrap();
     }
+
+    /// Plot scatter of latencies of frames broadcast, to files if specified.
+    fn plot_latency(&self, to_file: bool) -> String {
+        let latencies = self.latencies().into_iter()
+            .map(|(_, x, y)| (x as f64 / 1e6, y as f64 / 1e3))
+            .collect::<Vec<_>>();
+        plot::scatter(to_file.then_some(Path::new("latency")), "time (s)", "latency (ms)",
+                      &[("latency", &latencies)]).unwrap()
+    }
 }
 
 /// Statistics of pending frames of bridge.
 pub struct BridgePendingStat {
     pub records: Vec<usize>,
-    pub times: Vec<Instant>,
-    pub init: Instant,
+    pub times: Vec<Duration>,
+    clock: Clock,
 }
 
 impl BridgePendingStat {
-    fn new() -> Self {
-        BridgePendingStat { records: Vec::new(), times: Vec::new(), init: Instant::now() }
+    fn new(clock: Clock) -> Self {
+        BridgePendingStat { records: Vec::new(), times: Vec::new(), clock }
     }
 
     fn rec(&mut self, count: usize) {
         self.records.push(count);
-        self.times.push(Instant::now());
+        self.times.push(self.clock.now());
     }
 
     fn len(&self) -> usize {
         self.records.len()
     }
 
+    /// Congestion along with the times it is recorded, in microseconds.
+    fn congestion(&self) -> Vec<(i64, i64)> {
+        self.records.iter()
+            .zip(self.times.iter())
+            .map(|(x, y)| (x, y.as_micros()))
+            .map(|(x, y)| (y as i64, *x as i64))
+            .collect()
+    }
+
+    /// Table of congestion, for sinks of statistics.
+    fn congestion_table(&self) -> sink::Table {
+        sink::Table::new("congestion")
+            .column("time_us", Column::Int(self.times.iter().map(|x| x.as_micros() as i64).collect()))
+            .column("pending", Column::UInt(self.records.iter().map(|x| *x as u64).collect()))
+    }
+
     /// Export scatter of congestion, the changing pressure of waiting list.
     fn export_congestion_scatter(&self) {
-        let sc_congestion = self.records.iter()
-            .zip(self.times.iter())
-            .map(|(x, y)| (x, y.duration_since(self.init).as_micros()))
-            .map(|(x, y)| vec![y as i64, *x as i64])
+        let sc_congestion = self.congestion().into_iter()
+            .map(|(x, y)| vec![x, y])
             .collect::<Vec<_>>();
         serde_pickle::to_writer(&mut BufWriter::new(File::create("sc_congestion.pkl").unwrap()),
                                 &sc_congestion, SerOptions::default()).unwrap();
     }
+
+    /// Plot scatter of congestion, to files if specified.
+    fn plot_congestion(&self, to_file: bool) -> String {
+        let congestion = self.congestion().into_iter()
+            .map(|(x, y)| (x as f64 / 1e6, y as f64))
+            .collect::<Vec<_>>();
+        plot::scatter(to_file.then_some(Path::new("congestion")), "time (s)", "congestion",
+                      &[("congestion", &congestion)]).unwrap()
+    }
 }
 
-/// Launch network bridge
-fn bridge(tc: Sender<Command>, re: Receiver<Event>) {
-    info!(target: "bridge", "Bridge started.");
-    let mut mapping = BTreeMap::new();
-    let mut pending = Holder::new();
-    let mut stat = BridgeStat::new();
-    let mut pending_stat = BridgePendingStat::new();
-    let mut req_cnt = 0;
-    let mut b_cnt = 0;
-    let mut dp_cnt = 0;
-    let mut dc_cnt = 0;
-    let mut last_t = Instant::now();
-    while let Ok(event) = re.recv() { // receive an event
+/// Export per-segment port security counters and sticky addresses.
+fn export_port_security(security: &PortSecurity, sticky_file: &Path) {
+    let mut w = BufWriter::new(File::create("port_security.txt").unwrap());
+    for (seg, port) in security.ports() {
+        let state = if port.shutdown { "shutdown" } else { "up" };
+        writeln!(w, "{} {} {} {} {}", seg, port.secure.len(), port.dropped, port.violations, state).unwrap();
+    }
+    if security.config().sticky {
+        let mut w = BufWriter::new(File::create(sticky_file).unwrap());
+        rmp_serde::encode::write(&mut w, &security.sticky()).unwrap();
+    }
+}
+
+/// Statistics of legitimate frames flooded for their destinations being unknown.
+pub struct BridgeFloodStat {
+    pub records: Vec<(bool, usize)>,
+    pub times: Vec<Duration>,
+    clock: Clock,
+}
+
+impl BridgeFloodStat {
+    fn new(clock: Clock) -> Self {
+        BridgeFloodStat { records: Vec::new(), times: Vec::new(), clock }
+    }
+
+    /// Record a legitimate frame, along with the size of table at its arrival.
+    fn rec(&mut self, flooded: bool, table_len: usize) {
+        self.records.push((flooded, table_len));
+        self.times.push(self.clock.now());
+    }
+
+    /// Export scatter of the fraction of legitimate frames flooded and the table size, in windows
+    /// of 100 milliseconds.
+    fn export_flooded_scatter(&self) {
+        let mut windows = BTreeMap::<u128, (usize, usize, usize)>::new();
+        for ((flooded, table_len), t) in self.records.iter().zip(self.times.iter()) {
+            let window = t.as_millis() / 100;
+            let (total, cnt, len) = windows.entry(window).or_default();
+            *total += 1;
+            if *flooded { *cnt += 1; }
+            *len = *table_len;
+        }
+        let sc_flooded = windows.into_iter()
+            .map(|(w, (total, cnt, len))| vec![(w * 100_000) as f64, cnt as f64 / total as f64, len as f64])
+            .collect::<Vec<_>>();
+        serde_pickle::to_writer(&mut BufWriter::new(File::create("sc_flooded.pkl").unwrap()),
+                                &sc_flooded, SerOptions::default()).unwrap();
+    }
+}
+
+/// Statistics of copies of frames sent to groups.
+pub struct BridgeMulticastStat {
+    pub records: Vec<(usize, usize)>,
+    pub times: Vec<Duration>,
+    clock: Clock,
+}
+
+impl BridgeMulticastStat {
+    fn new(clock: Clock) -> Self {
+        BridgeMulticastStat { records: Vec::new(), times: Vec::new(), clock }
+    }
+
+    /// Record copies of a frame sent, along with the copies plain flooding would send.
+    fn rec(&mut self, copies: usize, flood_copies: usize) {
+        self.records.push((copies, flood_copies));
+        self.times.push(self.clock.now());
+    }
+
+    /// Total copies sent and those plain flooding would send.
+    fn total(&self) -> (usize, usize) {
+        self.records.iter()
+            .fold((0, 0), |(x, y), (copies, flood_copies)| (x + copies, y + flood_copies))
+    }
+
+    /// Export scatter of copies of frames sent against those of plain flooding.
+    fn export_multicast_scatter(&self) {
+        let sc_multicast = self.records.iter()
+            .zip(self.times.iter())
+            .map(|((x, y), t)| vec![t.as_micros() as i64, *x as i64, *y as i64])
+            .collect::<Vec<_>>();
+        serde_pickle::to_writer(&mut BufWriter::new(File::create("sc_multicast.pkl").unwrap()),
+                                &sc_multicast, SerOptions::default()).unwrap();
+    }
+}
+
+/// Counters of activities of bridge.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+struct BridgeCounter {
+    req: usize,
+    broadcast: usize,
+    dispatch: usize,
+    discard: usize,
+}
+
+impl BridgeCounter {
+    fn add(&mut self, other: &BridgeCounter) {
+        self.req += other.req;
+        self.broadcast += other.broadcast;
+        self.dispatch += other.dispatch;
+        self.discard += other.discard;
+    }
+}
+
+/// Counters of frames of one segment.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+struct SegmentCounter {
+    /// Frames coming from the segment.
+    received: usize,
+    /// Frames dispatched to the segment.
+    dispatched: usize,
+    /// Copies of frames of groups forwarded to the segment.
+    multicast: usize,
+    /// Frames coming from the segment and discarded.
+    discarded: usize,
+}
+
+/// Statistics of frames of every segment.
+struct SegmentStat {
+    map: BTreeMap<Segment, SegmentCounter>,
+}
+
+impl SegmentStat {
+    fn new() -> Self {
+        SegmentStat { map: BTreeMap::new() }
+    }
+
+    fn receive(&mut self, seg: Segment) {
+        self.map.entry(seg).or_default().received += 1;
+    }
+
+    fn dispatch(&mut self, seg: Segment) {
+        self.map.entry(seg).or_default().dispatched += 1;
+    }
+
+    fn multicast(&mut self, segs: &BTreeSet<Segment>) {
+        for seg in segs {
+            self.map.entry(*seg).or_default().multicast += 1;
+        }
+    }
+
+    fn discard(&mut self, seg: Segment) {
+        self.map.entry(seg).or_default().discarded += 1;
+    }
+}
+
+/// Parameters of the scenario simulated, shown in the report.
+struct Scenario {
+    simulate: serde_json::Value,
+    /// Parameters the frames are generated with, if recorded by the generator.
+    generate: Option<serde_json::Value>,
+}
+
+/// Outcome of a frame, telling apart latencies of frames taking different paths through the bridge.
+#[derive(Debug, Copy, Clone)]
+enum Outcome {
+    /// Dispatched or forwarded at once, its destination known.
+    Direct,
+    /// Held until its destination is found, then dispatched.
+    Held,
+    /// Discarded, at once or after held.
+    Discarded,
+}
+
+/// Percentiles of latencies of all frames and of every outcome, in microseconds.
+#[derive(Debug, Copy, Clone, Serialize)]
+struct OutcomePercentiles {
+    all: Percentiles,
+    direct: Percentiles,
+    held: Percentiles,
+    discarded: Percentiles,
+}
+
+/// Percentiles of latencies of frames completed within a second.
+#[derive(Debug, Serialize)]
+struct LatencyWindow {
+    second: usize,
+    #[serde(flatten)]
+    percentiles: OutcomePercentiles,
+}
+
+/// Streaming statistics of latencies of frames from arrival to dispatch or discard, by outcome
+/// and in windows of a second.
+struct LatencyStat {
+    outcomes: [Histogram; 3],
+    windows: Vec<[Histogram; 3]>,
+    live: Arc<LiveMetrics>,
+}
+
+impl LatencyStat {
+    fn new(live: Arc<LiveMetrics>) -> Self {
+        LatencyStat { outcomes: Default::default(), windows: Vec::new(), live }
+    }
+
+    /// Record a frame completed with an outcome, along with the time it arrives.
+    fn rec(&mut self, outcome: Outcome, arrival: Duration, now: Duration) {
+        self.live.latency[outcome as usize].observe(now.saturating_sub(arrival));
+        let latency = now.saturating_sub(arrival).as_nanos() as u64;
+        let window = now.as_secs() as usize;
+        if window >= self.windows.len() {
+            self.windows.resize_with(window + 1, Default::default);
+        }
+        self.outcomes[outcome as usize].record(latency);
+        self.windows[window][outcome as usize].record(latency);
+    }
+
+    fn percentiles(outcomes: &[Histogram; 3]) -> OutcomePercentiles {
+        let mut all = Histogram::new();
+        outcomes.iter().for_each(|x| all.merge(x));
+        let [direct, held, discarded] = outcomes.each_ref().map(|x| x.percentiles(1e3));
+        OutcomePercentiles { all: all.percentiles(1e3), direct, held, discarded }
+    }
+
+    /// Percentiles of latencies of the whole run.
+    fn total(&self) -> OutcomePercentiles {
+        LatencyStat::percentiles(&self.outcomes)
+    }
+
+    /// Percentiles of latencies of every second.
+    fn windows(&self) -> Vec<LatencyWindow> {
+        self.windows.iter()
+            .enumerate()
+            .map(|(second, x)| LatencyWindow { second, percentiles: LatencyStat::percentiles(x) })
+            .collect()
+    }
+
+    /// Export percentiles of latencies, of the whole run and of every second, as JSON.
+    fn export_latency_percentiles(&self) {
+        let json = serde_json::json!({
+            "unit": "us",
+            "total": self.total(),
+            "windows": self.windows(),
+        });
+        serde_json::to_writer_pretty(BufWriter::new(File::create("latency.json").unwrap()), &json).unwrap();
+    }
+
+    /// Print percentiles of latencies of the whole run as a table.
+    fn print(&self) {
+        let total = self.total();
+        println!("{:<12}{:>10}{:>12}{:>12}{:>12}{:>12}{:>12}", "latency/us", "count", "p50", "p90", "p99", "p99.9", "max");
+        for (name, x) in [("all", total.all), ("direct", total.direct), ("held", total.held), ("discarded", total.discarded)] {
+            println!("{:<12}{:>10}{:>12.3}{:>12.3}{:>12.3}{:>12.3}{:>12.3}", name, x.count, x.p50, x.p90, x.p99, x.p999, x.max);
+        }
+    }
+}
+
+/// Summary of a run, for tools collecting results of many runs.
+#[derive(Serialize)]
+struct Summary {
+    #[serde(flatten)]
+    total: BridgeCounter,
+    table_len: usize,
+    refused: usize,
+    latency: Percentiles,
+}
+
+/// Export the summary of the run as JSON.
+fn export_summary(summary: &Summary) {
+    serde_json::to_writer_pretty(BufWriter::new(File::create("summary.json").unwrap()), summary).unwrap();
+}
+
+/// Rows of parameters in a JSON object.
+fn param_rows(params: &serde_json::Value) -> Vec<Vec<String>> {
+    params.as_object()
+        .into_iter()
+        .flatten()
+        .map(|(k, v)| vec![k.clone(), v.to_string()])
+        .collect()
+}
+
+/// Everything the report of a run shows.
+struct ReportData<'a> {
+    scenario: &'a Scenario,
+    total: &'a BridgeCounter,
+    latency: &'a OutcomePercentiles,
+    seg_stat: &'a SegmentStat,
+    mapping: &'a Table,
+    security: Option<&'a PortSecurity>,
+    charts: &'a [(&'a str, String)],
+}
+
+/// Export the report of the run as a self-contained HTML file.
+fn export_report(data: &ReportData) {
+    let mut report = HtmlReport::new("Bridge simulation report");
+
+    report.heading("Scenario");
+    let seed = data.scenario.generate.as_ref()
+        .and_then(|x| x.get("seed"))
+        .map_or("unknown".to_string(), |x| x.to_string());
+    report.paragraph(&format!("Frames generated with seed {}.", seed));
+    report.table(&["simulation parameter", "value"], &param_rows(&data.scenario.simulate));
+    if let Some(generate) = &data.scenario.generate {
+        report.table(&["generation parameter", "value"], &param_rows(generate));
+    }
+
+    report.heading("Summary");
+    let total = data.total;
+    report.table(&["counter", "total"], &[
+        vec!["requests".to_string(), total.req.to_string()],
+        vec!["broadcasts".to_string(), total.broadcast.to_string()],
+        vec!["dispatches".to_string(), total.dispatch.to_string()],
+        vec!["discards".to_string(), total.discard.to_string()],
+        vec!["table size".to_string(), data.mapping.len().to_string()],
+        vec!["table refusals".to_string(), data.mapping.refused.to_string()],
+    ]);
+
+    report.heading("Latency");
+    let latency = data.latency;
+    report.paragraph("Latencies of frames from arrival to dispatch or discard, in microseconds.");
+    let rows = [("all", latency.all), ("direct", latency.direct), ("held", latency.held), ("discarded", latency.discarded)]
+        .into_iter()
+        .map(|(name, x)| {
+            let mut row = vec![name.to_string(), x.count.to_string()];
+            row.extend([x.p50, x.p90, x.p99, x.p999, x.max].map(|x| format!("{:.3}", x)));
+            row
+        })
+        .collect::<Vec<_>>();
+    report.table(&["outcome", "count", "p50", "p90", "p99", "p99.9", "max"], &rows);
+
+    report.heading("Charts");
+    for (caption, svg) in data.charts {
+        report.figure(svg, caption);
+    }
+
+    report.heading("Segments");
+    let mut entries = BTreeMap::<Segment, usize>::new();
+    for seg in data.mapping.map.values().map(|(seg, _)| seg) {
+        *entries.entry(*seg).or_default() += 1;
+    }
+    let rows = data.seg_stat.map.iter()
+        .map(|(seg, cnt)| {
+            let port = data.security.and_then(|x| x.ports().get(seg));
+            vec![
+                seg.to_string(),
+                cnt.received.to_string(),
+                cnt.dispatched.to_string(),
+                cnt.multicast.to_string(),
+                cnt.discarded.to_string(),
+                entries.get(seg).copied().unwrap_or_default().to_string(),
+                port.map_or("-".to_string(), |x| x.violations.to_string()),
+                port.map_or("-", |x| if x.shutdown { "shutdown" } else { "up" }).to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    report.table(&["segment", "received", "dispatched", "multicast", "discarded", "table entries",
+        "violations", "state"], &rows);
+
+    report.write("report.html").unwrap();
+}
+
+/// Options of network bridge.
+struct BridgeOptions {
+    security: Option<PortSecurity>,
+    sticky_file: PathBuf,
+    table_size: Option<usize>,
+    aging: Option<Duration>,
+    /// Addresses of legitimate hosts, telling frames of attackers apart in statistics.
+    legit: BTreeSet<Address>,
+    /// Segments attached to the bridge, where frames are flooded to.
+    segments: BTreeSet<Segment>,
+    snooping: Option<SnoopingTable>,
+    /// Whether to write charts of statistics to SVG and PNG files, besides the report.
+    plot: bool,
+    /// Formats statistics are written in.
+    stats_format: Vec<StatsFormat>,
+    /// Figures shown to watchers while running.
+    live: Arc<LiveMetrics>,
+    scenario: Scenario,
+}
+
+/// Network bridge, handling events and emitting commands.
+struct Bridge {
+    options: BridgeOptions,
+    clock: Clock,
+    mapping: Table,
+    pending: Holder,
+    stat: BridgeStat,
+    latency_stat: LatencyStat,
+    tracer: Tracer,
+    pending_stat: BridgePendingStat,
+    flood_stat: BridgeFloodStat,
+    multicast_stat: BridgeMulticastStat,
+    seg_stat: SegmentStat,
+    cnt: BridgeCounter,
+    total: BridgeCounter,
+    last_t: Duration,
+}
+
+impl Bridge {
+    fn new(options: BridgeOptions, clock: Clock, tracer: Tracer) -> Self {
+        info!(target: "bridge", "Bridge started.");
+        Bridge {
+            mapping: Table::new(options.table_size, options.aging, clock.clone()),
+            pending: Holder::new(),
+            stat: BridgeStat::new(clock.clone()),
+            latency_stat: LatencyStat::new(options.live.clone()),
+            tracer,
+            pending_stat: BridgePendingStat::new(clock.clone()),
+            flood_stat: BridgeFloodStat::new(clock.clone()),
+            multicast_stat: BridgeMulticastStat::new(clock.clone()),
+            seg_stat: SegmentStat::new(),
+            cnt: BridgeCounter::default(),
+            total: BridgeCounter::default(),
+            last_t: clock.now(),
+            options,
+            clock,
+        }
+    }
+
+    /// Handle an event, emitting commands. Returns whether the bridge keeps running.
+    fn handle(&mut self, event: Event, emit: &mut dyn FnMut(Command)) -> bool {
+        let Bridge { options, clock, mapping, pending, stat, latency_stat, tracer, pending_stat, flood_stat, multicast_stat, seg_stat, cnt, .. } = &mut *self;
+        let BridgeOptions { security, legit, segments, snooping, .. } = options;
         match event {
-            Event::Request(frame) => {
-                if mapping.get(&frame.src).is_none() {
+            Event::Request(frame, arrival) => {
+                tracer.rec(frame.id, FrameState::Arrived, arrival);
+                let verdict = security.as_mut()
+                    .map_or(Verdict::Admit, |x| x.admit(frame.src, frame.src_seg));
+                if verdict == Verdict::Shutdown {
+                    warn!(target: "bridge", "Segment {} shut down by source {}.", frame.src_seg, frame.src);
+                }
+                if verdict == Verdict::Admit && !mapping.contains(&frame.src) {
                     // correlate the source address with incoming segment
-                    mapping.insert(frame.src, frame.src_seg);
+                    mapping.learn(frame.src, frame.src_seg);
+                } else if verdict == Verdict::Admit {
+                    mapping.refresh(&frame.src);
+                }
+                if verdict == Verdict::Admit && legit.contains(&frame.src) && !frame.dst.is_group() {
+                    flood_stat.rec(!mapping.contains(&frame.dst), mapping.len());
                 }
-                if let Some(segment) = mapping.get(&frame.dst) {
+                seg_stat.receive(frame.src_seg);
+                if verdict != Verdict::Admit {
+                    // drop the frame violating port security
+                    stat.discard(frame.clone());
+                    latency_stat.rec(Outcome::Discarded, arrival, clock.now());
+                    tracer.rec(frame.id, FrameState::Discarded, clock.now());
+                    seg_stat.discard(frame.src_seg);
+                    emit(Command::Discard(frame));
+                    cnt.req += 1;
+                    cnt.discard += 1;
+                } else if frame.dst.is_group() {
+                    // forward to members if snooping, otherwise flood
+                    let msg = IgmpMessage::parse(&frame);
+                    let mut segs = match (snooping.as_mut(), msg) {
+                        (Some(snooping), Some(msg)) => {
+                            snooping.handle(msg, frame.src, frame.src_seg, clock.now());
+                            match msg {
+                                IgmpMessage::Query => segments.clone(),
+                                _ => snooping.router_segments(),
+                            }
+                        }
+                        (Some(snooping), None) => snooping.group_segments(&frame.dst),
+                        (None, _) => segments.clone(),
+                    };
+                    segs.remove(&frame.src_seg);
+                    let flood_copies = segments.len() - segments.contains(&frame.src_seg) as usize;
+                    multicast_stat.rec(segs.len(), flood_copies);
+                    seg_stat.multicast(&segs);
+                    stat.dispatch(frame.clone());
+                    latency_stat.rec(Outcome::Direct, arrival, clock.now());
+                    tracer.rec(frame.id, FrameState::Dispatched, clock.now());
+                    emit(Command::Multicast(frame, segs));
+                    cnt.req += 1;
+                    cnt.dispatch += 1;
+                } else if let Some(segment) = mapping.get(&frame.dst) {
                     // dispatch if source found in mapping
                     stat.dispatch(frame.clone());
-                    tc.send(Command::Dispatch(frame, *segment)).unwrap();
-                    req_cnt += 1;
-                    dp_cnt += 1;
+                    latency_stat.rec(Outcome::Direct, arrival, clock.now());
+                    tracer.rec(frame.id, FrameState::Dispatched, clock.now());
+                    seg_stat.dispatch(*segment);
+                    emit(Command::Dispatch(frame, *segment));
+                    cnt.req += 1;
+                    cnt.dispatch += 1;
                 } else if !pending.exist_addr(&frame.dst) {
                     // broadcast if no frames of same source are waiting
                     stat.broadcast(frame.clone());
-                    tc.send(Command::Broadcast(frame.dst)).unwrap(); // <- actual command
+                    emit(Command::Broadcast(frame.dst)); // <- actual command
+                    tracer.rec(frame.id, FrameState::Held, clock.now());
+                    tracer.rec(frame.id, FrameState::Queried, clock.now());
                     pending_stat.rec(pending.len());
-                    pending.hold(frame);
-                    b_cnt += 1;
+                    pending.hold(frame, arrival);
+                    cnt.broadcast += 1;
                 } else {
                     stat.broadcast(frame.clone());
+                    tracer.rec(frame.id, FrameState::Held, clock.now());
                     pending_stat.rec(pending.len());
-                    pending.hold(frame);
+                    pending.hold(frame, arrival);
                 }
             }
             Event::Success(address, segment) => {
                 // update the mapping
-                mapping.insert(address, segment);
-                for frame in pending.release(address) {
+                mapping.learn(address, segment);
+                for (frame, arrival) in pending.release(address) {
                     // dispatch all frames with the same segment
                     stat.dispatch(frame.clone());
-                    tc.send(Command::Dispatch(frame, segment)).unwrap();
-                    dp_cnt += 1;
+                    latency_stat.rec(Outcome::Held, arrival, clock.now());
+                    tracer.rec(frame.id, FrameState::Released, clock.now());
+                    tracer.rec(frame.id, FrameState::Dispatched, clock.now());
+                    seg_stat.dispatch(segment);
+                    emit(Command::Dispatch(frame, segment));
+                    cnt.dispatch += 1;
                 }
                 pending_stat.rec(pending.len());
             }
             Event::Failure(address) => {
-                for frame in pending.release(address) {
+                for (frame, arrival) in pending.release(address) {
                     // discard them all
                     stat.discard(frame.clone());
-                    tc.send(Command::Discard(frame)).unwrap();
-                    dc_cnt += 1;
+                    latency_stat.rec(Outcome::Discarded, arrival, clock.now());
+                    tracer.rec(frame.id, FrameState::Released, clock.now());
+                    tracer.rec(frame.id, FrameState::Discarded, clock.now());
+                    seg_stat.discard(frame.src_seg);
+                    emit(Command::Discard(frame));
+                    cnt.discard += 1;
                 }
                 pending_stat.rec(pending.len());
             }
             Event::Shutdown => {
                 info!(target: "bridge", "Received shutdown signal.");
-                // export statistics
-                stat.export_activity_scatter();
-                stat.export_latency_scatter();
-                pending_stat.export_congestion_scatter();
-                break;
+                self.shutdown();
+                return false;
             }
         }
-        if last_t.elapsed() > Duration::from_millis(50) {
+        self.update_live();
+        let now = self.clock.now();
+        if now.saturating_sub(self.last_t) > Duration::from_millis(50) {
+            if let Some(snooping) = &mut self.options.snooping {
+                snooping.purge(now);
+                trace!(target: "bridge", "Snooping {} groups, with querier {:?}.", snooping.len(), snooping.querier());
+            }
+            let cnt = &self.cnt;
             info!(target: "bridge", "Received {} requests. Done {} broadcasts, {} dispatches and {} discards.",
-                    req_cnt, b_cnt, dp_cnt, dc_cnt);
-            req_cnt = 0;
-            b_cnt = 0;
-            dp_cnt = 0;
-            dc_cnt = 0;
-            last_t = Instant::now();
+                    cnt.req, cnt.broadcast, cnt.dispatch, cnt.discard);
+            self.total.add(cnt);
+            self.cnt = BridgeCounter::default();
+            self.last_t = now;
+        }
+        true
+    }
+
+    /// Update figures shown to watchers.
+    fn update_live(&self) {
+        let live = &self.options.live;
+        let (total, cnt) = (&self.total, &self.cnt);
+        live.requests.store((total.req + cnt.req) as u64, Ordering::Relaxed);
+        live.broadcasts.store((total.broadcast + cnt.broadcast) as u64, Ordering::Relaxed);
+        live.dispatches.store((total.dispatch + cnt.dispatch) as u64, Ordering::Relaxed);
+        live.discards.store((total.discard + cnt.discard) as u64, Ordering::Relaxed);
+        live.holder.store(self.pending.len() as u64, Ordering::Relaxed);
+        live.table.store(self.mapping.len() as u64, Ordering::Relaxed);
+    }
+
+    /// Save the state of the bridge along with statistics so far, in the order `load` reads.
+    fn save(&self, w: &mut impl Write) -> io::Result<()> {
+        put(w, &self.mapping.map)?;
+        put(w, &(self.mapping.refused, self.mapping.last_purge))?;
+        put(w, &self.pending.map)?;
+        put(w, &(&self.stat.records, &self.stat.times))?;
+        put(w, &(&self.pending_stat.records, &self.pending_stat.times))?;
+        put(w, &(&self.flood_stat.records, &self.flood_stat.times))?;
+        put(w, &(&self.multicast_stat.records, &self.multicast_stat.times))?;
+        put(w, &(&self.latency_stat.outcomes, &self.latency_stat.windows))?;
+        put(w, &self.options.live.latency.each_ref().map(LiveHistogram::counts))?;
+        put(w, &self.seg_stat.map)?;
+        put(w, &(self.cnt, self.total, self.last_t))?;
+        put(w, &(&self.options.snooping, &self.options.security))
+    }
+
+    /// Load the state of the bridge saved by `save`.
+    fn load(&mut self, r: &mut impl Read) -> io::Result<()> {
+        self.mapping.map = take(r)?;
+        (self.mapping.refused, self.mapping.last_purge) = take(r)?;
+        self.pending.map = take(r)?;
+        (self.stat.records, self.stat.times) = take(r)?;
+        (self.pending_stat.records, self.pending_stat.times) = take(r)?;
+        (self.flood_stat.records, self.flood_stat.times) = take(r)?;
+        (self.multicast_stat.records, self.multicast_stat.times) = take(r)?;
+        (self.latency_stat.outcomes, self.latency_stat.windows) = take(r)?;
+        for (x, counts) in self.options.live.latency.iter().zip(take::<[HistogramCounts; 3]>(r)?) {
+            x.restore(counts);
+        }
+        self.seg_stat.map = take(r)?;
+        (self.cnt, self.total, self.last_t) = take(r)?;
+        (self.options.snooping, self.options.security) = take(r)?;
+        self.update_live();
+        Ok(())
+    }
+
+    /// Log totals and export statistics, charts and the report.
+    fn shutdown(&mut self) {
+        let Bridge { options, stat, latency_stat, tracer, pending_stat, flood_stat, multicast_stat, seg_stat, mapping, cnt, total, .. } = self;
+        total.add(cnt);
+        info!(target: "bridge", "Received {} requests in total. Done {} broadcasts, {} dispatches and {} discards.",
+            total.req, total.broadcast, total.dispatch, total.discard);
+        info!(target: "bridge", "Recorded {} activities and {} congestion samples.",
+            stat.len(), pending_stat.len());
+        info!(target: "bridge", "Table holds {} addresses, refused to learn {} times.",
+            mapping.len(), mapping.refused);
+        // export statistics
+        let (copies, flood_copies) = multicast_stat.total();
+        if flood_copies > 0 {
+            info!(target: "bridge", "Sent {} copies of frames to groups, where flooding would send {}, saving {:.1}%.",
+                copies, flood_copies, (1.0 - copies as f64 / flood_copies as f64) * 100.0);
+        }
+        if let Some(security) = &options.security {
+            info!(target: "bridge", "Port security dropped {} frames, with {} violations and {} segments shut down.",
+                security.dropped(), security.violations(), security.shutdowns());
+            export_port_security(security, &options.sticky_file);
+        }
+        let trace = tracer.finish();
+        if let Err(err) = &trace {
+            error!(target: "bridge", "Cannot write trace: {}.", err);
+        }
+        for format in &options.stats_format {
+            match format.sink(Path::new(".")) {
+                Some(mut sink) => {
+                    sink.write(&stat.activity_table()).unwrap();
+                    sink.write(&stat.latency_table()).unwrap();
+                    sink.write(&pending_stat.congestion_table()).unwrap();
+                }
+                None => {
+                    stat.export_activity_scatter();
+                    stat.export_latency_scatter();
+                    pending_stat.export_congestion_scatter();
+                    flood_stat.export_flooded_scatter();
+                    multicast_stat.export_multicast_scatter();
+                }
+            }
+        }
+        latency_stat.export_latency_percentiles();
+        latency_stat.print();
+        let latency = latency_stat.total();
+        export_summary(&Summary { total: *total, table_len: mapping.len(), refused: mapping.refused, latency: latency.all });
+        info!(target: "bridge", "Rendering charts and report...");
+        let charts = [
+            ("activities density histogram", stat.plot_activity(options.plot)),
+            ("latency", stat.plot_latency(options.plot)),
+            ("congestion", pending_stat.plot_congestion(options.plot)),
+        ];
+        export_report(&ReportData {
+            scenario: &options.scenario,
+            total,
+            latency: &latency,
+            seg_stat,
+            mapping,
+            security: options.security.as_ref(),
+            charts: &charts,
+        });
+        info!(target: "bridge", "Bridge exiting.");
+        if trace.is_err() {
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Pattern of frame arrivals over the duration of simulation.
+#[derive(Debug, Copy, Clone, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Arrival {
+    /// Rising and falling smoothly, peaking in the middle.
+    HalfCircle,
+    /// Steady throughout.
+    Uniform,
+    /// Rising linearly from nothing.
+    Ramp,
+    /// Bursting in the first tenth of every second, idle for the rest.
+    Burst,
+}
+
+impl Arrival {
+    /// Cumulative distribution function of arrivals, over the duration scaled to 0 to 1.
+    fn cdf(&self) -> fn(f64) -> f64 {
+        match self {
+            Arrival::HalfCircle => half_circle_dist_cdf,
+            Arrival::Uniform => |x| x,
+            Arrival::Ramp => |x| x * x,
+            Arrival::Burst => burst_dist_cdf,
         }
     }
-    info!(target: "bridge", "Bridge exiting.");
 }
 
 /// Cumulative distribution function of the distribution of "half circle".
@@ -286,6 +1164,12 @@ fn half_circle_dist_cdf(x: f64) -> f64 {
     (x.sin() + 1.0) / 2.0
 }
 
+/// Cumulative distribution function of bursts in the first tenth of every second.
+fn burst_dist_cdf(x: f64) -> f64 {
+    let x = x * ELAPSE_SEC as f64;
+    (x.floor() + (x.fract() * 10.0).min(1.0)) / ELAPSE_SEC as f64
+}
+
 /// Distribute the frames per milliseconds in specified duration with a distribution function.
 fn distribute(frame_seq: Vec<Frame>, dur_sec: usize, dist: fn(f64) -> f64) -> Vec<Vec<Frame>> {
     let mut buckets = vec![Vec::new(); dur_sec * 1000];
@@ -304,9 +1188,8 @@ fn distribute(frame_seq: Vec<Frame>, dur_sec: usize, dist: fn(f64) -> f64) -> Ve
 }
 
 /// Orchestration service that send frames to the bridge with distributed frame sequence.
-fn orchestrator(frame_seq: Vec<Frame>, te: Sender<Event>) {
+fn orchestrator(frame_seq: Vec<Vec<Frame>>, te: Sender<Event>, clock: Clock, live: Arc<LiveMetrics>) {
     info!(target: "orchestrator", "Orchestrator started.");
-    let frame_seq = distribute(frame_seq, ELAPSE_SEC, half_circle_dist_cdf);
     let begin = Instant::now();
     let mut last = 0;
     let mut last_t = Instant::now();
@@ -318,7 +1201,9 @@ fn orchestrator(frame_seq: Vec<Frame>, te: Sender<Event>) {
         if cur >= frame_seq.len() as u64 {
             for buckets in frame_seq[last..].iter() {
                 for frame in buckets {
-                    te.send(Event::Request(frame.clone())).unwrap();
+                    live.event_depth.fetch_add(1, Ordering::Relaxed);
+                    te.send(Event::Request(frame.clone(), clock.now())).unwrap();
+                    live.sent.fetch_add(1, Ordering::Relaxed);
                 }
             }
             break;
@@ -326,12 +1211,20 @@ fn orchestrator(frame_seq: Vec<Frame>, te: Sender<Event>) {
         if cur > last as u64 {
             for buckets in frame_seq[last..cur as usize].iter() {
                 for frame in buckets {
-                    te.send(Event::Request(frame.clone())).unwrap();
+                    live.event_depth.fetch_add(1, Ordering::Relaxed);
+                    te.send(Event::Request(frame.clone(), clock.now())).unwrap();
+                    live.sent.fetch_add(1, Ordering::Relaxed);
                     count += 1;
                 }
             }
             last = cur as usize;
         }
+        if INTERRUPTED.load(Ordering::Relaxed) {
+            // frames already sent are handled before the bridge shuts down
+            warn!(target: "orchestrator", "Interrupted, statistics are partial.");
+            te.send(Event::Shutdown).unwrap();
+            break;
+        }
         if now.duration_since(last_t) > Duration::from_millis(250) {
             info!(target: "orchestrator", "Sent {} frames.", count);
             count = 0;
@@ -348,11 +1241,12 @@ struct FacilityMeter {
     f_cnt: usize,
     dp_cnt: usize,
     dc_cnt: usize,
+    mc_cnt: usize,
 }
 
 impl FacilityMeter {
     fn new() -> Self {
-        FacilityMeter { s_cnt: 0, f_cnt: 0, dp_cnt: 0, dc_cnt: 0 }
+        FacilityMeter { s_cnt: 0, f_cnt: 0, dp_cnt: 0, dc_cnt: 0, mc_cnt: 0 }
     }
 
     fn inc_success(&mut self) {
@@ -371,52 +1265,266 @@ impl FacilityMeter {
         self.dc_cnt += 1;
     }
 
+    fn inc_multicast(&mut self) {
+        self.mc_cnt += 1;
+    }
+
     fn report(&mut self) {
-        info!(target: "facility", "Handled {} successes, {} failures, {} dispatches and {} discards.",
-            self.s_cnt, self.f_cnt, self.dp_cnt, self.dc_cnt);
+        info!(target: "facility", "Handled {} successes, {} failures, {} dispatches, {} multicasts and {} discards.",
+            self.s_cnt, self.f_cnt, self.dp_cnt, self.mc_cnt, self.dc_cnt);
         self.s_cnt = 0;
         self.f_cnt = 0;
         self.dp_cnt = 0;
         self.dc_cnt = 0;
+        self.mc_cnt = 0;
     }
 }
 
-/// Facilitation service that handle commands from the bridge.
-fn facility(count: usize, mapping: BTreeMap<Address, Segment>, te: Sender<Event>, rc: Receiver<Command>) {
-    info!(target: "facility", "Facility started.");
-    let mut cur_n = 0;
-    let mut meter = FacilityMeter::new();
-    let mut last_t = Instant::now();
-    while let Ok(command) = rc.recv() {
+/// Facility of segments, handling commands from the bridge and answering with events.
+struct Facility {
+    count: usize,
+    mapping: BTreeMap<Address, Segment>,
+    clock: Clock,
+    live: Arc<LiveMetrics>,
+    cur_n: usize,
+    meter: FacilityMeter,
+    last_t: Duration,
+}
+
+impl Facility {
+    fn new(count: usize, mapping: BTreeMap<Address, Segment>, clock: Clock, live: Arc<LiveMetrics>) -> Self {
+        info!(target: "facility", "Facility started.");
+        let last_t = clock.now();
+        Facility { count, mapping, clock, live, cur_n: 0, meter: FacilityMeter::new(), last_t }
+    }
+
+    /// Handle a command, emitting events. Returns whether the facility keeps running.
+    fn handle(&mut self, command: Command, emit: &mut dyn FnMut(Event)) -> bool {
         match command {
             Command::Broadcast(addr) => {
-                if let Some(segment) = mapping.get(&addr) {
-                    te.send(Event::Success(addr, *segment)).unwrap();
-                    meter.inc_success();
+                if let Some(segment) = self.mapping.get(&addr) {
+                    emit(Event::Success(addr, *segment));
+                    self.meter.inc_success();
+                    self.live.successes.fetch_add(1, Ordering::Relaxed);
                 } else {
-                    te.send(Event::Failure(addr)).unwrap();
-                    meter.inc_failure();
+                    emit(Event::Failure(addr));
+                    self.meter.inc_failure();
+                    self.live.failures.fetch_add(1, Ordering::Relaxed);
                 }
             }
-            Command::Dispatch(_, _) => {
-                meter.inc_dispatch();
-                cur_n += 1;
+            Command::Dispatch(frame, segment) => {
+                trace!(target: "facility", "Dispatched {} to {}.", frame, segment);
+                self.meter.inc_dispatch();
+                self.cur_n += 1;
+            }
+            Command::Discard(frame) => {
+                trace!(target: "facility", "Discarded {}.", frame);
+                self.meter.inc_discard();
+                self.cur_n += 1;
             }
-            Command::Discard(_) => {
-                meter.inc_discard();
-                cur_n += 1;
+            Command::Multicast(frame, segments) => {
+                trace!(target: "facility", "Forwarded {} to {} segments.", frame, segments.len());
+                self.meter.inc_multicast();
+                self.cur_n += 1;
             }
         }
-        if last_t.elapsed() > Duration::from_millis(250) {
-            meter.report();
-            last_t = Instant::now();
+        let now = self.clock.now();
+        if now.saturating_sub(self.last_t) > Duration::from_millis(250) {
+            self.meter.report();
+            self.last_t = now;
         }
-        if cur_n == count {
-            te.send(Event::Shutdown).unwrap();
+        if self.cur_n == self.count {
+            emit(Event::Shutdown);
+            info!(target: "facility", "Facility exiting.");
+            return false;
+        }
+        true
+    }
+
+    /// Save the state of the facility, in the order `load` reads.
+    fn save(&self, w: &mut impl Write) -> io::Result<()> {
+        put(w, &(self.cur_n, self.last_t))
+    }
+
+    /// Load the state of the facility saved by `save`.
+    fn load(&mut self, r: &mut impl Read) -> io::Result<()> {
+        (self.cur_n, self.last_t) = take(r)?;
+        Ok(())
+    }
+}
+
+/// Run the orchestrator, the bridge and the facility on their own threads, in real time.
+fn run_threaded(frame_seq: Vec<Vec<Frame>>, mut bridge: Bridge, mut facility: Facility, clock: Clock) {
+    let live = bridge.options.live.clone();
+    let (tc, rc) = std::sync::mpsc::channel();
+    let (te, re) = std::sync::mpsc::channel();
+
+    // depths of channels are counted before sending, so that they never go below zero
+    let facility = {
+        let te = te.clone();
+        let live = live.clone();
+        thread::spawn(move || {
+            while let Ok(command) = rc.recv() {
+                live.command_depth.fetch_sub(1, Ordering::Relaxed);
+                let emit = &mut |x| {
+                    live.event_depth.fetch_add(1, Ordering::Relaxed);
+                    // the bridge may have shut down early if interrupted
+                    te.send(x).ok();
+                };
+                if !facility.handle(command, emit) {
+                    break;
+                }
+            }
+        })
+    };
+
+    let bridge = {
+        let live = live.clone();
+        thread::spawn(move || {
+            while let Ok(event) = re.recv() {
+                live.event_depth.fetch_sub(1, Ordering::Relaxed);
+                let emit = &mut |x| {
+                    live.command_depth.fetch_add(1, Ordering::Relaxed);
+                    tc.send(x).unwrap()
+                };
+                if !bridge.handle(event, emit) {
+                    break;
+                }
+            }
+        })
+    };
+
+    let orchestrator = thread::spawn(move || orchestrator(frame_seq, te, clock, live));
+
+    orchestrator.join().unwrap();
+    facility.join().unwrap();
+    bridge.join().unwrap();
+}
+
+/// Virtual time the bridge takes to handle an event, in deterministic simulation.
+const EVENT_COST: Duration = Duration::from_nanos(250);
+
+/// Write a value to a checkpoint.
+fn put<T: Serialize + ?Sized>(w: &mut impl Write, x: &T) -> io::Result<()> {
+    rmp_serde::encode::write(w, x).map_err(io::Error::other)
+}
+
+/// Read a value from a checkpoint.
+fn take<T: DeserializeOwned>(r: &mut impl Read) -> io::Result<T> {
+    rmp_serde::decode::from_read(r).map_err(io::Error::other)
+}
+
+/// Position of deterministic simulation in a checkpoint, followed by the states of the bridge and
+/// the facility.
+///
+/// Checkpoints are taken between milliseconds, when no events are in flight. The simulator draws
+/// no random numbers, so there is no generator state to keep; the frames are generated ahead.
+#[derive(Serialize, Deserialize)]
+struct CheckpointHeader {
+    /// Count of frames simulated, telling checkpoints of other frames apart.
+    frames: usize,
+    /// Millisecond to go on from.
+    bucket: usize,
+    /// Virtual time the checkpoint is taken at.
+    time: Duration,
+    /// Length of the trace written so far.
+    trace_len: u64,
+    sent: u64,
+    successes: u64,
+    failures: u64,
+}
+
+/// Where and how often deterministic simulation is checkpointed.
+struct CheckpointOptions {
+    path: PathBuf,
+    /// Milliseconds between checkpoints.
+    every: usize,
+}
+
+/// Save a checkpoint before a millisecond, replacing the previous one only once written in full.
+fn save_checkpoint(path: &Path, bucket: usize, clock: &Clock, bridge: &mut Bridge, facility: &Facility) -> io::Result<()> {
+    let live = &bridge.options.live;
+    let header = CheckpointHeader {
+        frames: facility.count,
+        bucket,
+        time: clock.now(),
+        trace_len: bridge.tracer.written()?,
+        sent: live.sent.load(Ordering::Relaxed),
+        successes: live.successes.load(Ordering::Relaxed),
+        failures: live.failures.load(Ordering::Relaxed),
+    };
+    let tmp = path.with_extension("tmp");
+    let mut w = BufWriter::new(File::create(&tmp)?);
+    put(&mut w, &header)?;
+    bridge.save(&mut w)?;
+    facility.save(&mut w)?;
+    w.flush()?;
+    drop(w);
+    fs::rename(&tmp, path)?;
+    info!(target: "orchestrator", "Saved checkpoint at {} ms to {}.", bucket, path.display());
+    Ok(())
+}
+
+/// Open a checkpoint of the count of frames, reading its header and leaving the rest to the
+/// bridge and the facility.
+fn open_checkpoint(path: &Path, frames: usize) -> io::Result<(CheckpointHeader, BufReader<File>)> {
+    let mut r = BufReader::new(File::open(path)?);
+    let header: CheckpointHeader = take(&mut r)?;
+    if header.frames != frames {
+        let msg = format!("checkpoint taken of {} frames, not of the {} frames loaded", header.frames, frames);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+    }
+    Ok((header, r))
+}
+
+/// Log the reason a checkpoint cannot be resumed from and quit.
+fn resume_failed(path: &Path, err: io::Error) -> ! {
+    error!(target: "orchestrator", "Cannot resume from {}: {}.", path.display(), err);
+    std::process::exit(1);
+}
+
+/// Run the orchestrator, the bridge and the facility in turn on one thread, in virtual time,
+/// from some millisecond on.
+///
+/// Frames of every millisecond are queued at its beginning, and events are handled in the order
+/// they are queued, each taking the same virtual time. Answers of the facility are queued
+/// behind frames already waiting, as they would be in the channel to the bridge.
+fn run_deterministic(frame_seq: Vec<Vec<Frame>>, mut bridge: Bridge, mut facility: Facility, clock: Clock,
+                     start: usize, checkpoint: Option<&CheckpointOptions>) {
+    info!(target: "orchestrator", "Simulating in virtual time.");
+    let mut events = VecDeque::new();
+    let mut commands = Vec::new();
+    let mut running = true;
+    for (i, bucket) in frame_seq.into_iter().enumerate().skip(start) {
+        let interrupted = INTERRUPTED.load(Ordering::Relaxed);
+        if let Some(x) = checkpoint.filter(|x| interrupted || (i > start && i % x.every == 0)) {
+            save_checkpoint(&x.path, i, &clock, &mut bridge, &facility).unwrap();
+        }
+        if interrupted {
+            warn!(target: "orchestrator", "Interrupted at {} ms, statistics are partial.", i);
+            bridge.shutdown();
+            return;
+        }
+        clock.advance_to(Duration::from_millis(i as u64));
+        bridge.options.live.sent.fetch_add(bucket.len() as u64, Ordering::Relaxed);
+        events.extend(bucket.into_iter().map(|x| Event::Request(x, clock.now())));
+        while let Some(event) = events.pop_front() {
+            clock.advance(EVENT_COST);
+            running = bridge.handle(event, &mut |x| commands.push(x));
+            for command in commands.drain(..) {
+                facility.handle(command, &mut |x| events.push_back(x));
+            }
+            bridge.options.live.event_depth.store(events.len() as u64, Ordering::Relaxed);
+        }
+        if !running {
+            // every frame is done and the bridge has shut down
             break;
         }
     }
-    info!(target: "facility", "Facility exiting.");
+    if running {
+        warn!(target: "orchestrator", "Frames left unresolved at the end of simulation.");
+        bridge.shutdown();
+    }
 }
 
 /// Load segment mapping from disk.
@@ -426,6 +1534,20 @@ fn load_mapping() -> BTreeMap<Address, Segment> {
     BTreeMap::from_iter(addr_seg)
 }
 
+/// Load sticky addresses saved by a previous run, if any.
+fn load_sticky(path: &Path) -> Vec<(Address, Segment)> {
+    match File::open(path) {
+        Ok(file) => rmp_serde::from_read(BufReader::new(file)).unwrap(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Load parameters recorded by the generator, if any.
+fn load_generate_params() -> Option<serde_json::Value> {
+    let file = File::open("generate.json").ok()?;
+    serde_json::from_reader(BufReader::new(file)).ok()
+}
+
 /// Load generated frames from disk.
 fn load_frames() -> Vec<Frame> {
     let frame = BufReader::new(File::open("frame.rmp").unwrap());
@@ -434,28 +1556,92 @@ fn load_frames() -> Vec<Frame> {
 
 fn main() {
     env_logger::init();
-    let (tc, rc) = std::sync::mpsc::channel();
-    let (te, re) = std::sync::mpsc::channel();
+    let args = Args::parse();
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+        warn!(target: "orchestrator", "Interrupted, exporting statistics so far. Interrupt again to quit at once.");
+    }).unwrap();
     let frames = load_frames();
+    let count = frames.len();
+    let frame_seq = distribute(frames, ELAPSE_SEC, args.arrival.cdf());
 
-    let facility = {
-        let mapping = load_mapping();
-        let te = te.clone();
-        let len = frames.len();
-        thread::spawn(move || facility(len, mapping, te, rc))
-    };
+    let mapping = load_mapping();
+    let legit = mapping.keys().copied().collect();
+    let segments = mapping.values().copied().collect();
 
-    let bridge = {
-        let tc = tc.clone();
-        thread::spawn(move || bridge(tc, re))
-    };
+    let live = Arc::new(LiveMetrics::new());
+    if let Some(port) = args.metrics_port {
+        if let Err(err) = openmetrics::serve(live.clone(), port) {
+            error!(target: "metrics", "Cannot serve metrics on port {}: {}.", port, err);
+            std::process::exit(1);
+        }
+    }
+    let dashboard = args.dashboard.then(|| Dashboard::spawn(live.clone(), count as u64));
 
-    let orchestrator = {
-        let te = te.clone();
-        thread::spawn(move || orchestrator(frames, te))
+    let clock = if args.deterministic { Clock::start_virtual() } else { Clock::start_real() };
+    let mut facility = Facility::new(count, mapping, clock.clone(), live.clone());
+
+    let security = args.port_security().map(|config| {
+        let mut security = PortSecurity::new(config);
+        if config.sticky {
+            security.load_sticky(&load_sticky(&args.sticky_file));
+        }
+        security
+    });
+    let options = BridgeOptions {
+        security,
+        sticky_file: args.sticky_file.clone(),
+        table_size: args.table_size,
+        aging: args.aging.map(Duration::from_millis),
+        legit,
+        segments,
+        snooping: args.igmp_snooping
+            .then(|| SnoopingTable::new(Duration::from_millis(args.membership))),
+        plot: !args.no_plot,
+        stats_format: args.stats_format.clone(),
+        live: live.clone(),
+        scenario: Scenario {
+            simulate: serde_json::to_value(&args).unwrap(),
+            generate: load_generate_params(),
+        },
+    };
+    let resume = args.resume.as_deref()
+        .map(|x| open_checkpoint(x, count).unwrap_or_else(|err| resume_failed(x, err)));
+    let tracer = match &resume {
+        Some((header, _)) => Tracer::resume(args.trace.as_deref(), header.trace_len)
+            .unwrap_or_else(|err| resume_failed(args.resume.as_deref().unwrap(), err)),
+        None => Tracer::new(args.trace.as_deref()).unwrap(),
+    };
+    let mut bridge = Bridge::new(options, clock.clone(), tracer);
+    let start = match resume {
+        Some((header, mut r)) => {
+            let path = args.resume.as_deref().unwrap();
+            bridge.load(&mut r).unwrap_or_else(|err| resume_failed(path, err));
+            facility.load(&mut r).unwrap_or_else(|err| resume_failed(path, err));
+            clock.advance_to(header.time);
+            live.sent.store(header.sent, Ordering::Relaxed);
+            live.successes.store(header.successes, Ordering::Relaxed);
+            live.failures.store(header.failures, Ordering::Relaxed);
+            info!(target: "orchestrator", "Resumed from checkpoint at {} ms.", header.bucket);
+            header.bucket
+        }
+        None => 0,
     };
 
-    orchestrator.join().unwrap();
-    facility.join().unwrap();
-    bridge.join().unwrap();
-}
\ No newline at end of file
+    if args.deterministic {
+        let checkpoint = args.checkpoint.clone()
+            .map(|path| CheckpointOptions { path, every: args.checkpoint_every as usize });
+        run_deterministic(frame_seq, bridge, facility, clock, start, checkpoint.as_ref());
+    } else {
+        run_threaded(frame_seq, bridge, facility, clock);
+    }
+    live.done.store(true, Ordering::Relaxed);
+    if let Some(dashboard) = dashboard {
+        dashboard.join();
+    }
+    if INTERRUPTED.load(Ordering::Relaxed) {
+        std::process::exit(130);
+    }
+}
@@ -0,0 +1,231 @@
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use chrono::Local;
+use clap::Parser;
+use log::{info, warn};
+
+/// Format of timestamps naming directories of runs
+const TIME_FORMAT: &str = "%Y-%m-%d-%H-%M-%S";
+/// Metrics read from the summary of every run, as CSV columns and JSON pointers
+const METRICS: [(&str, &str); 12] = [
+    ("req", "/req"),
+    ("broadcast", "/broadcast"),
+    ("dispatch", "/dispatch"),
+    ("discard", "/discard"),
+    ("table_len", "/table_len"),
+    ("refused", "/refused"),
+    ("latency_count", "/latency/count"),
+    ("latency_p50_us", "/latency/p50"),
+    ("latency_p90_us", "/latency/p90"),
+    ("latency_p99_us", "/latency/p99"),
+    ("latency_p999_us", "/latency/p999"),
+    ("latency_max_us", "/latency/max"),
+];
+
+/// Generate and simulate over a grid of parameters, each run in its own directory, collecting
+/// results in one CSV table.
+#[derive(Parser)]
+struct Args {
+    /// Counts of valid frames
+    #[arg(long, value_delimiter = ',', default_value = "10000000")]
+    valid_frame: Vec<usize>,
+    /// Counts of segments
+    #[arg(long, value_delimiter = ',', default_value = "100")]
+    seg: Vec<usize>,
+    /// Ratios of frames destined to invalid addresses to valid frames
+    #[arg(long, value_delimiter = ',', default_value = "0.01")]
+    invalid_ratio: Vec<f64>,
+    /// Patterns of frame arrivals, as taken by the simulator
+    #[arg(long, value_delimiter = ',', default_value = "half-circle")]
+    arrival: Vec<String>,
+    /// Limits of the count of addresses the bridge table holds, 0 for unlimited
+    #[arg(long, value_delimiter = ',', default_value = "0")]
+    table_size: Vec<usize>,
+    /// Seeds frames of every combination are generated with
+    #[arg(long, value_delimiter = ',', default_value = "0")]
+    seed: Vec<u64>,
+    /// Simulate in virtual time, so that runs are reproducible and may go in parallel
+    #[arg(long)]
+    deterministic: bool,
+    /// Count of runs going in parallel in deterministic mode, at least 1, the count of CPUs if not
+    /// specified
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    jobs: Option<usize>,
+    /// Directory runs are put in
+    #[arg(long, default_value = "out")]
+    out: PathBuf,
+}
+
+/// One combination of parameters.
+#[derive(Debug, Clone)]
+struct Run {
+    index: usize,
+    valid_frame: usize,
+    seg: usize,
+    invalid_ratio: f64,
+    arrival: String,
+    table_size: usize,
+    seed: u64,
+}
+
+impl Run {
+    fn invalid_frame(&self) -> usize {
+        (self.valid_frame as f64 * self.invalid_ratio).round() as usize
+    }
+
+    fn generate_args(&self) -> Vec<String> {
+        vec![
+            "--seed".to_string(), self.seed.to_string(),
+            "--valid-frame".to_string(), self.valid_frame.to_string(),
+            "--invalid-frame".to_string(), self.invalid_frame().to_string(),
+            "--seg".to_string(), self.seg.to_string(),
+        ]
+    }
+
+    fn simulate_args(&self, deterministic: bool) -> Vec<String> {
+        let mut args = vec!["--arrival".to_string(), self.arrival.clone()];
+        if self.table_size > 0 {
+            args.extend(["--table-size".to_string(), self.table_size.to_string()]);
+        }
+        if deterministic {
+            args.push("--deterministic".to_string());
+        }
+        args
+    }
+}
+
+/// Every combination of parameters in the grid.
+fn grid(args: &Args) -> Vec<Run> {
+    let mut runs = Vec::new();
+    for &valid_frame in &args.valid_frame {
+        for &seg in &args.seg {
+            for &invalid_ratio in &args.invalid_ratio {
+                for arrival in &args.arrival {
+                    for &table_size in &args.table_size {
+                        for &seed in &args.seed {
+                            runs.push(Run {
+                                index: runs.len(),
+                                valid_frame,
+                                seg,
+                                invalid_ratio,
+                                arrival: arrival.clone(),
+                                table_size,
+                                seed,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    runs
+}
+
+/// Path of a binary built along with this one.
+fn sibling_bin(name: &str) -> PathBuf {
+    env::current_exe().unwrap().with_file_name(format!("{}{}", name, env::consts::EXE_SUFFIX))
+}
+
+/// Run a binary in a directory, with its output logged to `<name>.log` there.
+fn exec(name: &str, args: &[String], dir: &Path) -> io::Result<()> {
+    let log = File::create(dir.join(format!("{}.log", name)))?;
+    let status = Command::new(sibling_bin(name))
+        .args(args)
+        .current_dir(dir)
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .stdin(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} exited with {}", name, status)));
+    }
+    Ok(())
+}
+
+/// Generate and simulate a run in a new directory, returning the summary of the simulation.
+fn execute(run: &Run, dir: &Path, deterministic: bool) -> io::Result<serde_json::Value> {
+    fs::create_dir_all(dir)?;
+    exec("generate", &run.generate_args(), dir)?;
+    exec("simulate", &run.simulate_args(deterministic), dir)?;
+    let summary = File::open(dir.join("summary.json"))?;
+    Ok(serde_json::from_reader(io::BufReader::new(summary))?)
+}
+
+/// Write results of all runs as CSV, one row per run.
+fn export_csv(path: &Path, results: &[(Run, PathBuf, Option<serde_json::Value>)]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    write!(w, "run,dir,valid_frame,seg,invalid_ratio,invalid_frame,arrival,table_size,seed,status")?;
+    for (name, _) in METRICS {
+        write!(w, ",{}", name)?;
+    }
+    writeln!(w)?;
+    for (run, dir, summary) in results {
+        write!(w, "{},{},{},{},{},{},{},{},{},{}", run.index, dir.display(), run.valid_frame, run.seg,
+               run.invalid_ratio, run.invalid_frame(), run.arrival, run.table_size, run.seed,
+               if summary.is_some() { "ok" } else { "failed" })?;
+        for (_, pointer) in METRICS {
+            let value = summary.as_ref().and_then(|x| x.pointer(pointer));
+            match value {
+                Some(value) => write!(w, ",{}", value)?,
+                None => write!(w, ",")?,
+            }
+        }
+        writeln!(w)?;
+    }
+    w.flush()
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+    let runs = grid(&args);
+    let jobs = if args.deterministic {
+        args.jobs.unwrap_or_else(|| thread::available_parallelism().map_or(1, |x| x.get()))
+    } else {
+        if args.jobs.is_some_and(|x| x > 1) {
+            warn!(target: "sweep", "Runs in real time go one at a time, for parallel runs would disturb their timing.");
+        }
+        1
+    };
+    fs::create_dir_all(&args.out).unwrap();
+    let begin = Local::now().format(TIME_FORMAT).to_string();
+    info!(target: "sweep", "Sweeping {} runs, {} at a time.", runs.len(), jobs);
+
+    let total = runs.len();
+    let queue = Mutex::new(runs.into_iter());
+    let results = Mutex::new(Vec::with_capacity(total));
+    thread::scope(|s| {
+        for _ in 0..jobs.min(total) {
+            s.spawn(|| loop {
+                let run = match queue.lock().unwrap().next() {
+                    Some(run) => run,
+                    None => break,
+                };
+                let time = Local::now().format(TIME_FORMAT);
+                let dir = args.out.join(format!("{}#{}", time, run.index));
+                info!(target: "sweep", "Run {} in {}: {:?}", run.index, dir.display(), run);
+                let summary = match execute(&run, &dir, args.deterministic) {
+                    Ok(summary) => Some(summary),
+                    Err(err) => {
+                        warn!(target: "sweep", "Run {} failed: {}", run.index, err);
+                        None
+                    }
+                };
+                let mut results = results.lock().unwrap();
+                results.push((run, dir, summary));
+                info!(target: "sweep", "Finished {} of {} runs.", results.len(), total);
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(run, _, _)| run.index);
+    let path = args.out.join(format!("sweep-{}.csv", begin));
+    export_csv(&path, &results).unwrap();
+    info!(target: "sweep", "Results written to {}.", path.display());
+}
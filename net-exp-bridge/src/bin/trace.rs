@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use clap::Parser;
+use log::error;
+use net_exp_bridge::Frame;
+use net_exp_bridge::trace::{read_trace, TraceRecord};
+
+/// Print the history of frames traced by `simulate --trace`.
+#[derive(Parser)]
+struct Args {
+    /// Sequence numbers of frames to print
+    #[arg(required = true)]
+    id: Vec<u64>,
+    /// File transitions of frames are traced to
+    #[arg(long, default_value = "trace.rmp")]
+    file: PathBuf,
+}
+
+/// Load generated frames from disk.
+fn load_frames() -> Vec<Frame> {
+    let frame = BufReader::new(File::open("frame.rmp").unwrap());
+    rmp_serde::from_read(frame).unwrap()
+}
+
+fn main() {
+    env_logger::init();
+    let args = Args::parse();
+    let frames = load_frames();
+
+    let mut history = args.id.iter()
+        .map(|x| (*x, Vec::<TraceRecord>::new()))
+        .collect::<BTreeMap<_, _>>();
+    for (count, rec) in read_trace(&args.file).unwrap().enumerate() {
+        let rec = match rec {
+            Ok(rec) => rec,
+            Err(err) => {
+                error!("Trace {} is corrupt after {} records: {}", args.file.display(), count, err);
+                std::process::exit(1);
+            }
+        };
+        if let Some(records) = history.get_mut(&rec.id) {
+            records.push(rec);
+        }
+    }
+
+    for (id, records) in history {
+        match frames.get(id as usize) {
+            Some(frame) => println!("{}", frame),
+            None => println!("#{} not generated", id),
+        }
+        if records.is_empty() {
+            println!("  never traced");
+        }
+        let begin = records.first().map(|x| x.time).unwrap_or_default();
+        for rec in records {
+            println!("  {:>12.3} ms  {:>+10.3} ms  {}", rec.time.as_secs_f64() * 1e3,
+                     (rec.time - begin).as_secs_f64() * 1e3, rec.state);
+        }
+    }
+}
@@ -0,0 +1,728 @@
+//! Dissector of Ethernet II frames carrying ARP or IPv4 with ICMP, TCP or UDP, as captured in the
+//! Wireshark labs.
+//!
+//! Headers are parsed into views borrowing the frame, checked for length once when parsed so
+//! their accessors never fail. Options of IPv4 and TCP are checked as well, so iterating them
+//! never fails either.
+
+use std::fmt::{Display, Formatter};
+use std::net::Ipv4Addr;
+use net_exp_checksum::packet::{self, field, need, Truncated};
+
+pub const ETHER_TYPE_IPV4: u16 = 0x0800;
+pub const ETHER_TYPE_ARP: u16 = 0x0806;
+pub const ETHER_TYPE_VLAN: u16 = 0x8100;
+pub const ETHER_TYPE_QINQ: u16 = 0x88a8;
+
+/// Layer of a header dissected.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Layer {
+    Ethernet,
+    Vlan,
+    Arp,
+    Ipv4,
+    Icmp,
+    Tcp,
+    Udp,
+}
+
+impl Display for Layer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Layer::Ethernet => "Ethernet",
+            Layer::Vlan => "802.1Q",
+            Layer::Arp => "ARP",
+            Layer::Ipv4 => "IPv4",
+            Layer::Icmp => "ICMP",
+            Layer::Tcp => "TCP",
+            Layer::Udp => "UDP",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Error of a header that cannot be dissected.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DissectError {
+    /// Header or the data a length field covers longer than the bytes left.
+    Truncated { layer: Layer, needed: usize, len: usize },
+    /// Header with a field out of range.
+    Malformed { layer: Layer, reason: &'static str },
+}
+
+impl Display for DissectError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DissectError::Truncated { layer, needed, len } =>
+                write!(f, "{} truncated, {} bytes needed but {} left", layer, needed, len),
+            DissectError::Malformed { layer, reason } => write!(f, "{} malformed, {}", layer, reason),
+        }
+    }
+}
+
+impl std::error::Error for DissectError {}
+
+impl From<Truncated<Layer>> for DissectError {
+    fn from(x: Truncated<Layer>) -> Self {
+        DissectError::Truncated { layer: x.layer, needed: x.needed, len: x.len }
+    }
+}
+
+pub type DissectResult<T> = Result<T, DissectError>;
+
+fn malformed<T>(layer: Layer, reason: &'static str) -> DissectResult<T> {
+    Err(DissectError::Malformed { layer, reason })
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn ipv4_at(data: &[u8], offset: usize) -> Ipv4Addr {
+    Ipv4Addr::from(u32_at(data, offset))
+}
+
+/// MAC address of 48 bits.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl MacAddress {
+    fn at(data: &[u8], offset: usize) -> Self {
+        MacAddress(data[offset..offset + 6].try_into().unwrap())
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.0 == [0xff; 6]
+    }
+
+    /// Check if the address is a group address, i.e. its I/G bit is set.
+    pub fn is_group(&self) -> bool {
+        self.0[0] & 1 == 1
+    }
+}
+
+impl Display for MacAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let [a1, a2, a3, a4, a5, a6] = self.0;
+        write!(f, "{a1:02x}:{a2:02x}:{a3:02x}:{a4:02x}:{a5:02x}:{a6:02x}")
+    }
+}
+
+/// Tag of 802.1Q.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Vlan {
+    /// EtherType of the tag, `0x8100`, or `0x88a8` for the outer tag of 802.1ad.
+    pub tpid: u16,
+    /// Priority code point.
+    pub pcp: u8,
+    /// Drop eligible indicator.
+    pub dei: bool,
+    pub id: u16,
+}
+
+/// Ethernet II frame, without the frame check sequence, with any count of VLAN tags.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Ethernet<'a> {
+    data: &'a [u8],
+    /// Length of the header including tags.
+    header_len: usize,
+}
+
+impl<'a> Ethernet<'a> {
+    pub fn parse(data: &'a [u8]) -> DissectResult<Self> {
+        need(Layer::Ethernet, data, 14)?;
+        let mut header_len = 14;
+        while matches!(field(data, header_len - 2), ETHER_TYPE_VLAN | ETHER_TYPE_QINQ) {
+            need(Layer::Vlan, data, header_len + 4)?;
+            header_len += 4;
+        }
+        if field(data, header_len - 2) < 0x0600 {
+            return malformed(Layer::Ethernet, "length instead of EtherType, not Ethernet II");
+        }
+        Ok(Ethernet { data, header_len })
+    }
+
+    pub fn dst(&self) -> MacAddress {
+        MacAddress::at(self.data, 0)
+    }
+
+    pub fn src(&self) -> MacAddress {
+        MacAddress::at(self.data, 6)
+    }
+
+    /// VLAN tags, the outermost first.
+    pub fn vlans(&self) -> impl Iterator<Item = Vlan> + 'a {
+        let data = self.data;
+        (12..self.header_len - 2).step_by(4).map(move |i| {
+            let tci = field(data, i + 2);
+            Vlan { tpid: field(data, i), pcp: (tci >> 13) as u8, dei: tci & 0x1000 != 0, id: tci & 0x0fff }
+        })
+    }
+
+    /// EtherType of the payload, after any VLAN tags.
+    pub fn ether_type(&self) -> u16 {
+        field(self.data, self.header_len - 2)
+    }
+
+    pub fn header_len(&self) -> usize {
+        self.header_len
+    }
+
+    /// Payload, including any padding up to the minimum frame size.
+    pub fn payload(&self) -> &'a [u8] {
+        &self.data[self.header_len..]
+    }
+}
+
+/// Operation of ARP.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ArpOperation {
+    Request,
+    Reply,
+    Other(u16),
+}
+
+/// ARP packet resolving IPv4 addresses to MAC addresses.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Arp<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Arp<'a> {
+    pub fn parse(data: &'a [u8]) -> DissectResult<Self> {
+        need(Layer::Arp, data, 8)?;
+        if field(data, 0) != 1 || field(data, 2) != ETHER_TYPE_IPV4 {
+            return malformed(Layer::Arp, "not resolving IPv4 over Ethernet");
+        }
+        if data[4] != 6 || data[5] != 4 {
+            return malformed(Layer::Arp, "address lengths not 6 and 4");
+        }
+        need(Layer::Arp, data, 28)?;
+        Ok(Arp { data: &data[..28] })
+    }
+
+    pub fn operation(&self) -> ArpOperation {
+        match field(self.data, 6) {
+            1 => ArpOperation::Request,
+            2 => ArpOperation::Reply,
+            x => ArpOperation::Other(x),
+        }
+    }
+
+    pub fn sender_mac(&self) -> MacAddress {
+        MacAddress::at(self.data, 8)
+    }
+
+    pub fn sender_ip(&self) -> Ipv4Addr {
+        ipv4_at(self.data, 14)
+    }
+
+    pub fn target_mac(&self) -> MacAddress {
+        MacAddress::at(self.data, 18)
+    }
+
+    pub fn target_ip(&self) -> Ipv4Addr {
+        ipv4_at(self.data, 24)
+    }
+}
+
+/// Option of an IPv4 or TCP header, other than end of list and no operation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RawOption<'a> {
+    pub kind: u8,
+    /// Data of the option, after its kind and length.
+    pub data: &'a [u8],
+}
+
+/// Check options of the type-length-value form shared by IPv4 and TCP.
+fn check_options(layer: Layer, mut data: &[u8]) -> DissectResult<()> {
+    while let [kind, rest @ ..] = data {
+        match kind {
+            0 => break,
+            1 => data = rest,
+            _ => {
+                let [len, ..] = rest else { return malformed(layer, "option without length") };
+                let len = *len as usize;
+                if len < 2 {
+                    return malformed(layer, "option length less than 2");
+                }
+                if len > data.len() {
+                    return malformed(layer, "option longer than the header");
+                }
+                data = &data[len..];
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Iterator of checked options, stopping at the end of list.
+#[derive(Debug, Clone)]
+pub struct Options<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Options<'a> {
+    type Item = RawOption<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match *self.data {
+                [] | [0, ..] => return None,
+                [1, ..] => self.data = &self.data[1..],
+                [kind, len, ..] => {
+                    let (option, rest) = self.data.split_at(len as usize);
+                    self.data = rest;
+                    return Some(RawOption { kind, data: &option[2..] });
+                }
+                // checked when parsed
+                [_] => unreachable!(),
+            }
+        }
+    }
+}
+
+/// IPv4 packet, with its options and fragmentation.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Ipv4<'a> {
+    /// Packet up to its total length, or less if quoted cut in an ICMP error.
+    data: &'a [u8],
+}
+
+impl<'a> Ipv4<'a> {
+    /// Parse a packet, which must be as long as its total length. Bytes after, such as padding
+    /// of a short Ethernet frame, are left out.
+    pub fn parse(data: &'a [u8]) -> DissectResult<Self> {
+        let ip = Ipv4::parse_quoted(data)?;
+        need(Layer::Ipv4, data, ip.total_len() as usize)?;
+        Ok(ip)
+    }
+
+    /// Parse a packet that may be cut after its header, as quoted in ICMP errors.
+    pub fn parse_quoted(data: &'a [u8]) -> DissectResult<Self> {
+        need(Layer::Ipv4, data, 20)?;
+        if data[0] >> 4 != 4 {
+            return malformed(Layer::Ipv4, "version not 4");
+        }
+        let header_len = (data[0] & 0x0f) as usize * 4;
+        if header_len < 20 {
+            return malformed(Layer::Ipv4, "header length less than 20");
+        }
+        let total_len = field(data, 2) as usize;
+        if total_len < header_len {
+            return malformed(Layer::Ipv4, "total length less than header length");
+        }
+        need(Layer::Ipv4, data, header_len)?;
+        check_options(Layer::Ipv4, &data[20..header_len])?;
+        Ok(Ipv4 { data: &data[..total_len.min(data.len())] })
+    }
+
+    pub fn header_len(&self) -> usize {
+        (self.data[0] & 0x0f) as usize * 4
+    }
+
+    /// Differentiated services code point.
+    pub fn dscp(&self) -> u8 {
+        self.data[1] >> 2
+    }
+
+    /// Explicit congestion notification.
+    pub fn ecn(&self) -> u8 {
+        self.data[1] & 0x03
+    }
+
+    pub fn total_len(&self) -> u16 {
+        field(self.data, 2)
+    }
+
+    pub fn identification(&self) -> u16 {
+        field(self.data, 4)
+    }
+
+    pub fn dont_fragment(&self) -> bool {
+        self.data[6] & 0x40 != 0
+    }
+
+    pub fn more_fragments(&self) -> bool {
+        self.data[6] & 0x20 != 0
+    }
+
+    /// Offset of the fragment in the datagram, in bytes.
+    pub fn fragment_offset(&self) -> usize {
+        (field(self.data, 6) & 0x1fff) as usize * 8
+    }
+
+    /// Whether the packet is only a fragment of a datagram.
+    pub fn is_fragment(&self) -> bool {
+        self.more_fragments() || self.fragment_offset() != 0
+    }
+
+    pub fn ttl(&self) -> u8 {
+        self.data[8]
+    }
+
+    pub fn protocol(&self) -> u8 {
+        self.data[9]
+    }
+
+    pub fn checksum(&self) -> u16 {
+        field(self.data, 10)
+    }
+
+    pub fn checksum_valid(&self) -> bool {
+        net_exp_checksum::verify(&self.data[..self.header_len()])
+    }
+
+    pub fn src(&self) -> Ipv4Addr {
+        ipv4_at(self.data, 12)
+    }
+
+    pub fn dst(&self) -> Ipv4Addr {
+        ipv4_at(self.data, 16)
+    }
+
+    pub fn options(&self) -> Options<'a> {
+        Options { data: &self.data[20..self.header_len()] }
+    }
+
+    /// Payload up to the total length, or less if the packet is quoted cut.
+    pub fn payload(&self) -> &'a [u8] {
+        &self.data[self.header_len()..]
+    }
+
+    /// Sum of the pseudo-header of TCP and UDP carried, for a payload of some length.
+    fn pseudo_header(&self, len: usize) -> net_exp_checksum::InternetChecksum {
+        packet::ipv4_pseudo_header(self.src().octets(), self.dst().octets(), self.protocol(), len as u16)
+    }
+}
+
+/// Message of ICMP, by its type.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IcmpMessage<'a> {
+    EchoReply { id: u16, seq: u16, data: &'a [u8] },
+    EchoRequest { id: u16, seq: u16, data: &'a [u8] },
+    DestinationUnreachable {
+        code: u8,
+        /// MTU of the next hop, when fragmentation is needed but not allowed.
+        next_hop_mtu: u16,
+        /// Header and leading bytes of the datagram that could not be delivered.
+        original: &'a [u8],
+    },
+    TimeExceeded {
+        /// 0 for the TTL exceeded in transit, 1 for fragment reassembly time exceeded.
+        code: u8,
+        original: &'a [u8],
+    },
+    Other { kind: u8, code: u8, rest: &'a [u8] },
+}
+
+/// ICMP message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Icmp<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Icmp<'a> {
+    pub fn parse(data: &'a [u8]) -> DissectResult<Self> {
+        need(Layer::Icmp, data, 8)?;
+        Ok(Icmp { data })
+    }
+
+    pub fn kind(&self) -> u8 {
+        self.data[0]
+    }
+
+    pub fn code(&self) -> u8 {
+        self.data[1]
+    }
+
+    pub fn checksum(&self) -> u16 {
+        field(self.data, 2)
+    }
+
+    /// Check the checksum over the message, or `None` if the packet carrying it is only the first
+    /// fragment, the rest of the message being needed.
+    pub fn checksum_valid(&self, ip: &Ipv4) -> Option<bool> {
+        (!ip.is_fragment()).then(|| net_exp_checksum::verify(self.data))
+    }
+
+    pub fn message(&self) -> IcmpMessage<'a> {
+        let data = self.data;
+        match self.kind() {
+            0 => IcmpMessage::EchoReply { id: field(data, 4), seq: field(data, 6), data: &data[8..] },
+            8 => IcmpMessage::EchoRequest { id: field(data, 4), seq: field(data, 6), data: &data[8..] },
+            3 => IcmpMessage::DestinationUnreachable {
+                code: self.code(),
+                next_hop_mtu: field(data, 6),
+                original: &data[8..],
+            },
+            11 => IcmpMessage::TimeExceeded { code: self.code(), original: &data[8..] },
+            kind => IcmpMessage::Other { kind, code: self.code(), rest: &data[4..] },
+        }
+    }
+
+    /// Header of the datagram an error is about, parsed as quoted.
+    pub fn original(&self) -> Option<DissectResult<Ipv4<'a>>> {
+        match self.message() {
+            IcmpMessage::DestinationUnreachable { original, .. } | IcmpMessage::TimeExceeded { original, .. } =>
+                Some(Ipv4::parse_quoted(original)),
+            _ => None,
+        }
+    }
+}
+
+/// Flags of a TCP header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct TcpFlags(pub u16);
+
+impl TcpFlags {
+    pub const FIN: u16 = 0x001;
+    pub const SYN: u16 = 0x002;
+    pub const RST: u16 = 0x004;
+    pub const PSH: u16 = 0x008;
+    pub const ACK: u16 = 0x010;
+    pub const URG: u16 = 0x020;
+    pub const ECE: u16 = 0x040;
+    pub const CWR: u16 = 0x080;
+    pub const AE: u16 = 0x100;
+
+    const NAMES: [(u16, &'static str); 9] = [
+        (TcpFlags::AE, "AE"), (TcpFlags::CWR, "CWR"), (TcpFlags::ECE, "ECE"), (TcpFlags::URG, "URG"),
+        (TcpFlags::ACK, "ACK"), (TcpFlags::PSH, "PSH"), (TcpFlags::RST, "RST"), (TcpFlags::SYN, "SYN"),
+        (TcpFlags::FIN, "FIN"),
+    ];
+
+    pub fn contains(&self, flags: u16) -> bool {
+        self.0 & flags == flags
+    }
+}
+
+impl Display for TcpFlags {
+    /// Names of flags set, in the order of Wireshark, such as `SYN, ACK`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let names = TcpFlags::NAMES.iter()
+            .rev()
+            .filter(|(x, _)| self.contains(*x))
+            .map(|(_, x)| *x)
+            .collect::<Vec<_>>();
+        f.write_str(&names.join(", "))
+    }
+}
+
+/// Option of a TCP header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TcpOption<'a> {
+    MaxSegmentSize(u16),
+    WindowScale(u8),
+    SackPermitted,
+    /// Edges of blocks received, in pairs of left and right.
+    Sack(&'a [u8]),
+    Timestamps { value: u32, echo: u32 },
+    Other(RawOption<'a>),
+}
+
+impl<'a> TcpOption<'a> {
+    fn from_raw(x: RawOption<'a>) -> Self {
+        match (x.kind, x.data.len()) {
+            (2, 2) => TcpOption::MaxSegmentSize(field(x.data, 0)),
+            (3, 1) => TcpOption::WindowScale(x.data[0]),
+            (4, 0) => TcpOption::SackPermitted,
+            (5, len) if len % 8 == 0 => TcpOption::Sack(x.data),
+            (8, 8) => TcpOption::Timestamps { value: u32_at(x.data, 0), echo: u32_at(x.data, 4) },
+            _ => TcpOption::Other(x),
+        }
+    }
+}
+
+/// TCP segment.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Tcp<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Tcp<'a> {
+    pub fn parse(data: &'a [u8]) -> DissectResult<Self> {
+        need(Layer::Tcp, data, 20)?;
+        let header_len = (data[12] >> 4) as usize * 4;
+        if header_len < 20 {
+            return malformed(Layer::Tcp, "data offset less than 5");
+        }
+        need(Layer::Tcp, data, header_len)?;
+        check_options(Layer::Tcp, &data[20..header_len])?;
+        Ok(Tcp { data })
+    }
+
+    pub fn src_port(&self) -> u16 {
+        field(self.data, 0)
+    }
+
+    pub fn dst_port(&self) -> u16 {
+        field(self.data, 2)
+    }
+
+    pub fn seq(&self) -> u32 {
+        u32_at(self.data, 4)
+    }
+
+    pub fn ack(&self) -> u32 {
+        u32_at(self.data, 8)
+    }
+
+    pub fn header_len(&self) -> usize {
+        (self.data[12] >> 4) as usize * 4
+    }
+
+    pub fn flags(&self) -> TcpFlags {
+        TcpFlags(field(self.data, 12) & 0x01ff)
+    }
+
+    pub fn window(&self) -> u16 {
+        field(self.data, 14)
+    }
+
+    pub fn checksum(&self) -> u16 {
+        field(self.data, 16)
+    }
+
+    /// Check the checksum over the segment and the pseudo-header of the packet carrying it, or
+    /// `None` if the packet is only the first fragment.
+    pub fn checksum_valid(&self, ip: &Ipv4) -> Option<bool> {
+        if ip.is_fragment() {
+            return None;
+        }
+        let mut hasher = ip.pseudo_header(self.data.len());
+        hasher.update(self.data);
+        Some(hasher.sum() == 0xffff)
+    }
+
+    pub fn urgent_pointer(&self) -> u16 {
+        field(self.data, 18)
+    }
+
+    pub fn options(&self) -> impl Iterator<Item = TcpOption<'a>> {
+        Options { data: &self.data[20..self.header_len()] }.map(TcpOption::from_raw)
+    }
+
+    pub fn payload(&self) -> &'a [u8] {
+        &self.data[self.header_len()..]
+    }
+}
+
+/// UDP datagram.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Udp<'a> {
+    /// Datagram up to its length, or less if cut by fragmentation.
+    data: &'a [u8],
+}
+
+impl<'a> Udp<'a> {
+    pub fn parse(data: &'a [u8]) -> DissectResult<Self> {
+        need(Layer::Udp, data, 8)?;
+        let len = field(data, 4) as usize;
+        if len < 8 {
+            return malformed(Layer::Udp, "length less than 8");
+        }
+        need(Layer::Udp, data, len)?;
+        Ok(Udp { data: &data[..len] })
+    }
+
+    /// Parse a datagram that may be cut after its header, as carried by the first fragment.
+    pub fn parse_cut(data: &'a [u8]) -> DissectResult<Self> {
+        need(Layer::Udp, data, 8)?;
+        let len = field(data, 4) as usize;
+        if len < 8 {
+            return malformed(Layer::Udp, "length less than 8");
+        }
+        Ok(Udp { data: &data[..len.min(data.len())] })
+    }
+
+    pub fn src_port(&self) -> u16 {
+        field(self.data, 0)
+    }
+
+    pub fn dst_port(&self) -> u16 {
+        field(self.data, 2)
+    }
+
+    pub fn len(&self) -> u16 {
+        field(self.data, 4)
+    }
+
+    /// Whether the datagram is only a header, without payload.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 8
+    }
+
+    pub fn checksum(&self) -> u16 {
+        field(self.data, 6)
+    }
+
+    /// Check the checksum over the datagram and the pseudo-header of the packet carrying it, or
+    /// `None` if the datagram is sent without checksum or the packet is only the first fragment.
+    pub fn checksum_valid(&self, ip: &Ipv4) -> Option<bool> {
+        if self.checksum() == 0 || ip.is_fragment() {
+            return None;
+        }
+        let mut hasher = ip.pseudo_header(self.data.len());
+        hasher.update(self.data);
+        Some(hasher.sum() == 0xffff)
+    }
+
+    pub fn payload(&self) -> &'a [u8] {
+        &self.data[8..]
+    }
+}
+
+/// Header carried by Ethernet.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Network<'a> {
+    Arp(Arp<'a>),
+    Ipv4(Ipv4<'a>),
+    /// Payload of another EtherType.
+    Other(u16, &'a [u8]),
+}
+
+/// Header carried by IPv4.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Transport<'a> {
+    Icmp(Icmp<'a>),
+    Tcp(Tcp<'a>),
+    Udp(Udp<'a>),
+    /// Payload of a fragment other than the first, not dissected further before reassembly.
+    Fragment(&'a [u8]),
+    /// Payload of another protocol.
+    Other(u8, &'a [u8]),
+}
+
+/// Frame dissected down to the transport layer.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Dissection<'a> {
+    pub ethernet: Ethernet<'a>,
+    pub network: Network<'a>,
+    /// Header carried by IPv4, if the frame carries IPv4.
+    pub transport: Option<Transport<'a>>,
+}
+
+/// Dissect an Ethernet II frame, without its frame check sequence.
+pub fn dissect(frame: &[u8]) -> DissectResult<Dissection<'_>> {
+    let ethernet = Ethernet::parse(frame)?;
+    let (network, transport) = match ethernet.ether_type() {
+        ETHER_TYPE_ARP => (Network::Arp(Arp::parse(ethernet.payload())?), None),
+        ETHER_TYPE_IPV4 => {
+            let ip = Ipv4::parse(ethernet.payload())?;
+            let payload = ip.payload();
+            let transport = match ip.protocol() {
+                _ if ip.fragment_offset() != 0 => Transport::Fragment(payload),
+                packet::ICMP => Transport::Icmp(Icmp::parse(payload)?),
+                packet::TCP => Transport::Tcp(Tcp::parse(payload)?),
+                // the first fragment, holding the header of a datagram longer than itself
+                packet::UDP if ip.more_fragments() => Transport::Udp(Udp::parse_cut(payload)?),
+                packet::UDP => Transport::Udp(Udp::parse(payload)?),
+                x => Transport::Other(x, payload),
+            };
+            (Network::Ipv4(ip), Some(transport))
+        }
+        x => (Network::Other(x, ethernet.payload()), None),
+    };
+    Ok(Dissection { ethernet, network, transport })
+}
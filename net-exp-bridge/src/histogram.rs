@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+/// Bits of sub-buckets in every power of two, keeping relative error of values under 1%.
+const SUB_BITS: u32 = 8;
+const SUB_HALF: u64 = 1 << (SUB_BITS - 1);
+
+/// Index of the bucket a value falls into.
+///
+/// Values under `2^SUB_BITS` have buckets of their own, and each power of two above is split
+/// into `2^(SUB_BITS - 1)` buckets of equal width.
+fn index(value: u64) -> usize {
+    if value < 1 << SUB_BITS {
+        return value as usize;
+    }
+    let exp = 63 - value.leading_zeros();
+    let shift = exp - SUB_BITS + 1;
+    (shift as u64 * SUB_HALF + (value >> shift)) as usize
+}
+
+/// Highest value falling into a bucket.
+fn highest(index: usize) -> u64 {
+    let index = index as u64;
+    if index < 1 << SUB_BITS {
+        return index;
+    }
+    let shift = (index / SUB_HALF - 1) as u32;
+    let mantissa = index % SUB_HALF + SUB_HALF;
+    // the highest bucket ends at `u64::MAX`, one short of overflowing
+    (mantissa << shift) + ((1 << shift) - 1)
+}
+
+/// Percentiles and other figures of values recorded in a histogram.
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+pub struct Percentiles {
+    pub count: u64,
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub p999: f64,
+    pub max: f64,
+}
+
+/// Histogram of values in the style of HDR histograms, with buckets growing exponentially in
+/// width so that any value is told within a fixed relative error, in memory bounded however
+/// large the values are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Histogram {
+    counts: Vec<u64>,
+    count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Histogram::default()
+    }
+
+    pub fn record(&mut self, value: u64) {
+        let i = index(value);
+        if i >= self.counts.len() {
+            self.counts.resize(i + 1, 0);
+        }
+        self.counts[i] += 1;
+        self.min = if self.count == 0 { value } else { self.min.min(value) };
+        self.max = self.max.max(value);
+        self.count += 1;
+        self.sum += value as u128;
+    }
+
+    /// Add values recorded in another histogram.
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.count == 0 {
+            return;
+        }
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        self.counts.iter_mut().zip(other.counts.iter()).for_each(|(x, y)| *x += y);
+        self.min = if self.count == 0 { other.min } else { self.min.min(other.min) };
+        self.max = self.max.max(other.max);
+        self.count += other.count;
+        self.sum += other.sum;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn min(&self) -> u64 {
+        self.min
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum as f64 / self.count as f64
+    }
+
+    /// Value at a quantile from 0 to 1, as the highest value of the bucket it falls into.
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = ((q * self.count as f64).ceil() as u64).clamp(1, self.count);
+        let mut seen = 0;
+        for (i, cnt) in self.counts.iter().enumerate() {
+            seen += cnt;
+            if seen >= rank {
+                return highest(i).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Percentiles of values recorded, divided by a unit.
+    pub fn percentiles(&self, unit: f64) -> Percentiles {
+        Percentiles {
+            count: self.count,
+            min: self.min as f64 / unit,
+            mean: self.mean() / unit,
+            p50: self.quantile(0.5) as f64 / unit,
+            p90: self.quantile(0.9) as f64 / unit,
+            p99: self.quantile(0.99) as f64 / unit,
+            p999: self.quantile(0.999) as f64 / unit,
+            max: self.max as f64 / unit,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::{Address, Frame, Segment};
+
+/// Leading bytes of group addresses mapped from IGMP groups, like `01:00:5e` of Ethernet.
+pub const GROUP_PREFIX: [u8; 2] = [0x01, 0x5e];
+/// Group of all hosts, where queries are sent to.
+pub const ALL_HOSTS: Address = Address { data: [0x01, 0x5e, 0x00, 0x01] };
+/// Group of all routers, where reports and leaves are sent to.
+pub const ALL_ROUTERS: Address = Address { data: [0x01, 0x5e, 0x00, 0x02] };
+
+const TYPE_QUERY: u8 = 0x11;
+const TYPE_REPORT: u8 = 0x16;
+const TYPE_LEAVE: u8 = 0x17;
+
+/// Group address of the specified group number.
+pub fn group(id: u16) -> Address {
+    let [hi, lo] = id.to_be_bytes();
+    Address { data: [GROUP_PREFIX[0], GROUP_PREFIX[1], hi, lo] }
+}
+
+/// IGMP message carried by a frame sent to one of the reserved groups.
+///
+/// The frame data holds the message type in its first byte and the group number in its last two.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IgmpMessage {
+    /// General query of a querier.
+    Query,
+    /// Membership report of a host joining or staying in a group.
+    Report(Address),
+    /// Leave of a host from a group.
+    Leave(Address),
+}
+
+impl IgmpMessage {
+    /// Parse the message a frame carries, if it is one.
+    pub fn parse(frame: &Frame) -> Option<Self> {
+        let group = group(u16::from_be_bytes([frame.data[2], frame.data[3]]));
+        match (frame.dst, frame.data[0]) {
+            (ALL_HOSTS, TYPE_QUERY) => Some(IgmpMessage::Query),
+            (ALL_ROUTERS, TYPE_REPORT) => Some(IgmpMessage::Report(group)),
+            (ALL_ROUTERS, TYPE_LEAVE) => Some(IgmpMessage::Leave(group)),
+            _ => None,
+        }
+    }
+
+    /// Make a frame carrying the message.
+    pub fn frame(&self, src: Address, src_seg: Segment) -> Frame {
+        let (dst, ty, group) = match self {
+            IgmpMessage::Query => (ALL_HOSTS, TYPE_QUERY, [0, 0]),
+            IgmpMessage::Report(group) => (ALL_ROUTERS, TYPE_REPORT, [group.data[2], group.data[3]]),
+            IgmpMessage::Leave(group) => (ALL_ROUTERS, TYPE_LEAVE, [group.data[2], group.data[3]]),
+        };
+        Frame { id: 0, src, src_seg, dst, data: [ty, 0, group[0], group[1]] }
+    }
+}
+
+/// IGMP snooping table, tracking members of groups and queriers.
+///
+/// Times are given as durations since a common epoch, such as the start of a simulation.
+#[derive(Serialize, Deserialize)]
+pub struct SnoopingTable {
+    timeout: Duration,
+    groups: BTreeMap<Address, BTreeMap<Address, (Segment, Duration)>>,
+    queriers: BTreeMap<Address, (Segment, Duration)>,
+}
+
+impl SnoopingTable {
+    /// Create a table forgetting members and queriers not heard for the specified duration.
+    pub fn new(timeout: Duration) -> Self {
+        SnoopingTable { timeout, groups: BTreeMap::new(), queriers: BTreeMap::new() }
+    }
+
+    /// Learn from a message sent by a host.
+    pub fn handle(&mut self, msg: IgmpMessage, src: Address, seg: Segment, now: Duration) {
+        match msg {
+            IgmpMessage::Query => {
+                self.queriers.insert(src, (seg, now));
+            }
+            IgmpMessage::Report(group) => {
+                self.groups.entry(group).or_default().insert(src, (seg, now));
+            }
+            IgmpMessage::Leave(group) => {
+                if let Some(members) = self.groups.get_mut(&group) {
+                    members.remove(&src);
+                }
+            }
+        }
+    }
+
+    /// Remove members and queriers timed out.
+    pub fn purge(&mut self, now: Duration) {
+        let alive = |t: &Duration| now.saturating_sub(*t) <= self.timeout;
+        self.queriers.retain(|_, (_, t)| alive(t));
+        for members in self.groups.values_mut() {
+            members.retain(|_, (_, t)| alive(t));
+        }
+        self.groups.retain(|_, members| !members.is_empty());
+    }
+
+    /// Elected querier, the one with the lowest address among those present.
+    pub fn querier(&self) -> Option<(Address, Segment)> {
+        self.queriers.iter()
+            .next()
+            .map(|(addr, (seg, _))| (*addr, *seg))
+    }
+
+    /// Segments towards routers, i.e. the one of the elected querier.
+    pub fn router_segments(&self) -> BTreeSet<Segment> {
+        self.querier().map(|(_, seg)| seg).into_iter().collect()
+    }
+
+    /// Segments frames of a group are forwarded to, with members or towards routers.
+    pub fn group_segments(&self, group: &Address) -> BTreeSet<Segment> {
+        let mut segs = self.router_segments();
+        if let Some(members) = self.groups.get(group) {
+            segs.extend(members.values().map(|(seg, _)| *seg));
+        }
+        segs
+    }
+
+    /// Count of groups with members.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
@@ -0,0 +1,307 @@
+use clap::ValueEnum;
+use crate::{Address, Frame};
+
+/// Interval of periodic transmission of LACPDUs at fast rate, in milliseconds.
+pub const FAST_PERIODIC_TIME: u64 = 1000;
+/// Time partner information stays valid without LACPDUs at short timeout, in milliseconds.
+pub const SHORT_TIMEOUT_TIME: u64 = 3000;
+/// Time a port waits before attaching to the aggregator, in milliseconds.
+pub const AGGREGATE_WAIT_TIME: u64 = 2000;
+
+/// State flags of a port as carried in LACPDUs.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct PortState(pub u8);
+
+impl PortState {
+    pub const ACTIVITY: u8 = 0x01;
+    pub const TIMEOUT: u8 = 0x02;
+    pub const AGGREGATION: u8 = 0x04;
+    pub const SYNCHRONIZATION: u8 = 0x08;
+    pub const COLLECTING: u8 = 0x10;
+    pub const DISTRIBUTING: u8 = 0x20;
+    pub const DEFAULTED: u8 = 0x40;
+    pub const EXPIRED: u8 = 0x80;
+
+    pub fn has(&self, flag: u8) -> bool {
+        self.0 & flag == flag
+    }
+
+    pub fn set(&mut self, flag: u8, on: bool) {
+        if on { self.0 |= flag } else { self.0 &= !flag }
+    }
+}
+
+/// Information of one end of a link, as carried in LACPDUs.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PortInfo {
+    pub system: Address,
+    pub key: u16,
+    pub port: u16,
+    pub state: PortState,
+}
+
+/// LACPDU exchanged over a link, telling the sender's information and its view of the receiver.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Lacpdu {
+    pub actor: PortInfo,
+    pub partner: Option<PortInfo>,
+}
+
+/// State of the receive machine.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RxState {
+    /// Partner information is fresh.
+    Current,
+    /// Partner went silent for a timeout, waiting for another.
+    Expired,
+    /// Partner went silent for too long, no partner known.
+    Defaulted,
+    /// The link is down.
+    Disabled,
+}
+
+/// State of the mux machine.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MuxState {
+    Detached,
+    Waiting,
+    Attached,
+    CollectingDistributing,
+}
+
+/// One member port running LACP, with fast periodic rate and short timeout.
+pub struct LacpPort {
+    actor: PortInfo,
+    partner: Option<PortInfo>,
+    rx: RxState,
+    mux: MuxState,
+    link_up: bool,
+    need_tx: bool,
+    current_while: u64,
+    wait_while: u64,
+    next_tx: u64,
+}
+
+impl LacpPort {
+    pub fn new(system: Address, key: u16, port: u16) -> Self {
+        let mut state = PortState::default();
+        state.set(PortState::ACTIVITY | PortState::TIMEOUT | PortState::AGGREGATION | PortState::DEFAULTED, true);
+        LacpPort {
+            actor: PortInfo { system, key, port, state },
+            partner: None,
+            rx: RxState::Disabled,
+            mux: MuxState::Detached,
+            link_up: false,
+            need_tx: false,
+            current_while: 0,
+            wait_while: 0,
+            next_tx: 0,
+        }
+    }
+
+    pub fn actor(&self) -> &PortInfo {
+        &self.actor
+    }
+
+    pub fn partner(&self) -> Option<&PortInfo> {
+        self.partner.as_ref()
+    }
+
+    pub fn rx(&self) -> RxState {
+        self.rx
+    }
+
+    pub fn mux(&self) -> MuxState {
+        self.mux
+    }
+
+    /// Bring the link of the port up or down.
+    pub fn set_link(&mut self, up: bool) {
+        self.link_up = up;
+        if !up {
+            self.rx = RxState::Disabled;
+            self.partner = None;
+        } else if self.rx == RxState::Disabled {
+            self.rx = RxState::Defaulted;
+            self.need_tx = true;
+        }
+    }
+
+    /// Check if the port may join an aggregation, having a partner willing to aggregate.
+    pub fn aggregatable(&self) -> bool {
+        matches!(self.rx, RxState::Current | RxState::Expired)
+            && self.partner.is_some_and(|x| x.state.has(PortState::AGGREGATION))
+    }
+
+    /// Check if frames may be distributed to the port.
+    pub fn distributing(&self) -> bool {
+        self.mux == MuxState::CollectingDistributing
+    }
+
+    /// Take a LACPDU received from the partner.
+    pub fn receive(&mut self, pdu: &Lacpdu, now: u64) {
+        if !self.link_up {
+            return;
+        }
+        // answer at once if the partner has an outdated view of us
+        if pdu.partner != Some(self.actor) {
+            self.need_tx = true;
+        }
+        self.partner = Some(pdu.actor);
+        self.rx = RxState::Current;
+        self.current_while = now + SHORT_TIMEOUT_TIME;
+        self.actor.state.set(PortState::DEFAULTED | PortState::EXPIRED, false);
+    }
+
+    /// Run timers and the mux machine, yielding a LACPDU to send if one is due.
+    pub fn tick(&mut self, now: u64, selected: bool) -> Option<Lacpdu> {
+        if !self.link_up {
+            self.set_mux(MuxState::Detached);
+            return None;
+        }
+        // receive machine
+        match self.rx {
+            RxState::Current if now >= self.current_while => {
+                self.rx = RxState::Expired;
+                self.actor.state.set(PortState::EXPIRED, true);
+                if let Some(partner) = &mut self.partner {
+                    partner.state.set(PortState::SYNCHRONIZATION, false);
+                }
+                self.current_while = now + SHORT_TIMEOUT_TIME;
+            }
+            RxState::Expired if now >= self.current_while => {
+                self.rx = RxState::Defaulted;
+                self.actor.state.set(PortState::EXPIRED, false);
+                self.actor.state.set(PortState::DEFAULTED, true);
+                self.partner = None;
+            }
+            _ => {}
+        }
+        // mux machine
+        let partner_sync = self.partner.is_some_and(|x| x.state.has(PortState::SYNCHRONIZATION));
+        match self.mux {
+            MuxState::Detached if selected => {
+                self.set_mux(MuxState::Waiting);
+                self.wait_while = now + AGGREGATE_WAIT_TIME;
+            }
+            MuxState::Waiting if !selected => self.set_mux(MuxState::Detached),
+            MuxState::Waiting if now >= self.wait_while => self.set_mux(MuxState::Attached),
+            MuxState::Attached if !selected => self.set_mux(MuxState::Detached),
+            MuxState::Attached if partner_sync => self.set_mux(MuxState::CollectingDistributing),
+            MuxState::CollectingDistributing if !selected || !partner_sync => self.set_mux(MuxState::Attached),
+            _ => {}
+        }
+        // transmit machine
+        if self.need_tx || now >= self.next_tx {
+            self.need_tx = false;
+            self.next_tx = now + FAST_PERIODIC_TIME;
+            return Some(Lacpdu { actor: self.actor, partner: self.partner });
+        }
+        None
+    }
+
+    fn set_mux(&mut self, mux: MuxState) {
+        if self.mux == mux {
+            return;
+        }
+        let (sync, coll_dist) = match mux {
+            MuxState::Detached | MuxState::Waiting => (false, false),
+            MuxState::Attached => (true, false),
+            MuxState::CollectingDistributing => (true, true),
+        };
+        self.actor.state.set(PortState::SYNCHRONIZATION, sync);
+        self.actor.state.set(PortState::COLLECTING | PortState::DISTRIBUTING, coll_dist);
+        self.mux = mux;
+        self.need_tx = true;
+    }
+}
+
+/// Policy choosing the member a frame goes through.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, ValueEnum)]
+pub enum HashPolicy {
+    /// Hash of the source address.
+    SrcMac,
+    /// Hash of the destination address.
+    DstMac,
+    /// Hash of both source and destination addresses.
+    SrcDstHash,
+}
+
+impl HashPolicy {
+    pub fn hash(&self, frame: &Frame) -> u32 {
+        let fold = |addr: &Address| u32::from_be_bytes(addr.data);
+        let val = match self {
+            HashPolicy::SrcMac => fold(&frame.src),
+            HashPolicy::DstMac => fold(&frame.dst),
+            HashPolicy::SrcDstHash => fold(&frame.src) ^ fold(&frame.dst),
+        };
+        // fold the bytes down, as the layer 2 policy of bonding does
+        let val = val ^ (val >> 16);
+        val ^ (val >> 8)
+    }
+}
+
+/// Aggregator bundling member ports of a bridge into one logical port.
+pub struct Aggregator {
+    ports: Vec<LacpPort>,
+    policy: HashPolicy,
+}
+
+impl Aggregator {
+    /// Create an aggregator of the specified count of members, with links all up.
+    pub fn new(system: Address, key: u16, count: usize, policy: HashPolicy) -> Self {
+        let ports = (0..count)
+            .map(|i| {
+                let mut port = LacpPort::new(system, key, i as u16 + 1);
+                port.set_link(true);
+                port
+            })
+            .collect();
+        Aggregator { ports, policy }
+    }
+
+    pub fn ports(&self) -> &[LacpPort] {
+        &self.ports
+    }
+
+    pub fn set_link(&mut self, member: usize, up: bool) {
+        self.ports[member].set_link(up);
+    }
+
+    pub fn receive(&mut self, member: usize, pdu: &Lacpdu, now: u64) {
+        self.ports[member].receive(pdu, now);
+    }
+
+    /// Run all members, yielding LACPDUs to send along with the members they go through.
+    ///
+    /// Members are selected if they lead to the same partner system and key as the first
+    /// aggregatable member does.
+    pub fn tick(&mut self, now: u64) -> Vec<(usize, Lacpdu)> {
+        let target = self.ports.iter()
+            .filter(|x| x.aggregatable())
+            .find_map(|x| x.partner().map(|p| (p.system, p.key)));
+        let mut pdus = Vec::new();
+        for (i, port) in self.ports.iter_mut().enumerate() {
+            let selected = port.aggregatable()
+                && port.partner().map(|p| (p.system, p.key)) == target;
+            if let Some(pdu) = port.tick(now, selected) {
+                pdus.push((i, pdu));
+            }
+        }
+        pdus
+    }
+
+    /// Members frames are distributed to.
+    pub fn distributing(&self) -> Vec<usize> {
+        (0..self.ports.len()).filter(|x| self.ports[*x].distributing()).collect()
+    }
+
+    /// Choose the member a frame goes through, if any is distributing.
+    pub fn select(&self, frame: &Frame) -> Option<usize> {
+        let active = self.distributing();
+        if active.is_empty() {
+            return None;
+        }
+        Some(active[self.policy.hash(frame) as usize % active.len()])
+    }
+}
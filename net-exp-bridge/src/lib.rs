@@ -1,12 +1,32 @@
 use std::fmt::{Display, Formatter};
 use serde::{Serialize, Deserialize};
 
+pub mod dissect;
+pub mod histogram;
+pub mod igmp;
+pub mod lacp;
+pub mod live;
+pub mod openmetrics;
+pub mod plot;
+pub mod report;
+pub mod security;
+pub mod sink;
+pub mod stats;
+pub mod trace;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct Address {
     pub data: [u8; 4]
 }
 
+impl Address {
+    /// Check if the address is a group address, i.e. its I/G bit is set.
+    pub fn is_group(&self) -> bool {
+        self.data[0] & 1 == 1
+    }
+}
+
 impl Display for Address {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let a1 = self.data[0];
@@ -21,7 +41,7 @@ impl TryFrom<&str> for Address {
     type Error = ();
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if value.len() != 11 {
+        if value.len() != 11 || !value.is_ascii() {
             return Err(());
         }
         let mut data = [0; 4];
@@ -51,7 +71,7 @@ impl TryFrom<&str> for Segment {
     type Error = ();
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if value.len() != 5 {
+        if value.len() != 5 || !value.is_ascii() {
             return Err(());
         }
         let mut data = [0; 2];
@@ -65,6 +85,8 @@ pub type FrameData = [u8; 4];
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Frame {
+    /// Sequence number of the frame, unique among frames generated together.
+    pub id: u64,
     pub src: Address,
     pub src_seg: Segment,
     pub dst: Address,
@@ -73,24 +95,34 @@ pub struct Frame {
 
 impl Display for Frame {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {} {} {:02x?}", self.src, self.src_seg, self.dst, self.data)
+        write!(f, "#{} {} {} {} {:02x?}", self.id, self.src, self.src_seg, self.dst, self.data)
     }
 }
 
+/// Parse a frame as displayed.
 impl TryFrom<&str> for Frame {
     type Error = ();
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let mut seg = value.trim().split(' ');
+        let mut seg = value.trim().splitn(5, ' ');
+        let id = if let Some(val) = seg.next().and_then(|x| x.strip_prefix('#')) { val } else { return Err(()) };
         let src = if let Some(val) = seg.next() { val } else { return Err(()) };
         let src_seg = if let Some(val) = seg.next() { val } else { return Err(()) };
         let dst = if let Some(val) = seg.next() { val } else { return Err(()) };
         let data_s = if let Some(val) = seg.next() { val } else { return Err(()) };
+        // bytes as `{:02x?}` lists them, `[0a, 1b, 2c, 3d]`
+        let data_s = data_s.strip_prefix('[').and_then(|x| x.strip_suffix(']')).ok_or(())?;
+        let mut bytes = data_s.split(", ");
         let mut data = FrameData::default();
-        for i in 0..16 {
-            data[i] = u8::from_str_radix(&data_s[i * 2..i * 2 + 2], 16).map_err(|_| ())?;
+        for x in data.iter_mut() {
+            let byte = bytes.next().filter(|x| x.len() == 2 && x.bytes().all(|x| x.is_ascii_hexdigit())).ok_or(())?;
+            *x = u8::from_str_radix(byte, 16).map_err(|_| ())?;
+        }
+        if bytes.next().is_some() {
+            return Err(());
         }
         Ok(Frame {
+            id: id.parse().map_err(|_| ())?,
             src: src.try_into()?,
             src_seg: src_seg.try_into()?,
             dst: dst.try_into()?,
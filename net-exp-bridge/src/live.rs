@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+/// Upper bounds of buckets of live latency histograms, in seconds.
+pub const LATENCY_BOUNDS: [f64; 15] = [
+    1e-6, 2.5e-6, 5e-6, 1e-5, 2.5e-5, 5e-5, 1e-4, 2.5e-4, 5e-4, 1e-3, 2.5e-3, 5e-3, 1e-2, 2.5e-2, 5e-2,
+];
+
+/// Counts of every bucket of a live histogram, then the sum of latencies in nanoseconds and their
+/// count, as kept in checkpoints.
+pub type HistogramCounts = ([u64; LATENCY_BOUNDS.len() + 1], u64, u64);
+
+/// Histogram of latencies with fixed buckets, updated from any thread.
+#[derive(Debug, Default)]
+pub struct LiveHistogram {
+    /// Count of latencies in every bucket, the last one unbounded.
+    pub buckets: [AtomicU64; LATENCY_BOUNDS.len() + 1],
+    pub sum_ns: AtomicU64,
+    pub count: AtomicU64,
+}
+
+impl LiveHistogram {
+    pub fn observe(&self, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        let i = LATENCY_BOUNDS.iter().position(|x| secs <= *x).unwrap_or(LATENCY_BOUNDS.len());
+        self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        self.sum_ns.fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn counts(&self) -> HistogramCounts {
+        (self.buckets.each_ref().map(|x| x.load(Ordering::Relaxed)),
+         self.sum_ns.load(Ordering::Relaxed), self.count.load(Ordering::Relaxed))
+    }
+
+    /// Go on from counts taken before, such as those of a checkpoint.
+    pub fn restore(&self, (buckets, sum_ns, count): HistogramCounts) {
+        for (x, y) in self.buckets.iter().zip(buckets) {
+            x.store(y, Ordering::Relaxed);
+        }
+        self.sum_ns.store(sum_ns, Ordering::Relaxed);
+        self.count.store(count, Ordering::Relaxed);
+    }
+}
+
+/// Figures of a running simulation, updated as it goes and read by watchers like the dashboard.
+#[derive(Debug, Default)]
+pub struct LiveMetrics {
+    /// Frames sent to the bridge.
+    pub sent: AtomicU64,
+    pub requests: AtomicU64,
+    pub broadcasts: AtomicU64,
+    pub dispatches: AtomicU64,
+    pub discards: AtomicU64,
+    /// Destinations frames are held for.
+    pub holder: AtomicU64,
+    /// Addresses in the bridge table.
+    pub table: AtomicU64,
+    /// Queries answered by the facility with the segment found.
+    pub successes: AtomicU64,
+    /// Queries answered by the facility with no segment found.
+    pub failures: AtomicU64,
+    /// Events waiting for the bridge.
+    pub event_depth: AtomicU64,
+    /// Commands waiting for the facility.
+    pub command_depth: AtomicU64,
+    /// Latencies of frames dispatched at once, held then dispatched, and discarded, in order.
+    pub latency: [LiveHistogram; 3],
+    /// Whether the simulation is over.
+    pub done: AtomicBool,
+}
+
+/// Figures of a simulation at some moment.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Snapshot {
+    pub sent: u64,
+    pub requests: u64,
+    pub broadcasts: u64,
+    pub dispatches: u64,
+    pub discards: u64,
+    pub holder: u64,
+    pub table: u64,
+}
+
+impl LiveMetrics {
+    pub fn new() -> Self {
+        LiveMetrics::default()
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            sent: self.sent.load(Ordering::Relaxed),
+            requests: self.requests.load(Ordering::Relaxed),
+            broadcasts: self.broadcasts.load(Ordering::Relaxed),
+            dispatches: self.dispatches.load(Ordering::Relaxed),
+            discards: self.discards.load(Ordering::Relaxed),
+            holder: self.holder.load(Ordering::Relaxed),
+            table: self.table.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Interval the dashboard refreshes at.
+const REFRESH: Duration = Duration::from_millis(200);
+/// Count of samples shown in sparklines.
+const SPARK_LEN: usize = 50;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Recent samples of a rate, drawn as a sparkline.
+struct Sparkline {
+    samples: VecDeque<f64>,
+}
+
+impl Sparkline {
+    fn new() -> Self {
+        Sparkline { samples: VecDeque::with_capacity(SPARK_LEN) }
+    }
+
+    fn push(&mut self, value: f64) {
+        if self.samples.len() == SPARK_LEN {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value);
+    }
+
+    fn last(&self) -> f64 {
+        self.samples.back().copied().unwrap_or_default()
+    }
+
+    fn draw(&self) -> String {
+        let max = self.samples.iter().copied().fold(0.0, f64::max);
+        self.samples.iter()
+            .map(|x| if max > 0.0 { SPARKS[((x / max) * 7.0).round() as usize] } else { SPARKS[0] })
+            .collect()
+    }
+}
+
+/// Terminal dashboard showing a running simulation, refreshed on a thread of its own.
+pub struct Dashboard {
+    handle: JoinHandle<()>,
+}
+
+impl Dashboard {
+    /// Start showing a simulation of the specified count of frames, until it is done.
+    pub fn spawn(metrics: Arc<LiveMetrics>, total: u64) -> Self {
+        let handle = thread::spawn(move || Dashboard::run(&metrics, total));
+        Dashboard { handle }
+    }
+
+    pub fn join(self) {
+        self.handle.join().unwrap();
+    }
+
+    fn run(metrics: &LiveMetrics, total: u64) {
+        let multi = MultiProgress::new();
+        let progress = multi.add(ProgressBar::new(total));
+        progress.set_style(ProgressStyle::with_template(
+            "{elapsed_precise} [{bar:50.cyan/blue}] {pos}/{len} frames sent ({percent}%)")
+            .unwrap()
+            .progress_chars("=> "));
+        let lines = (0..5)
+            .map(|_| {
+                let line = multi.add(ProgressBar::new_spinner());
+                line.set_style(ProgressStyle::with_template("{msg}").unwrap());
+                line
+            })
+            .collect::<Vec<_>>();
+
+        let mut sparks = [Sparkline::new(), Sparkline::new(), Sparkline::new()];
+        let mut last = metrics.snapshot();
+        let mut last_t = Instant::now();
+        loop {
+            let done = metrics.done.load(Ordering::Relaxed);
+            let now = Instant::now();
+            let cur = metrics.snapshot();
+            let secs = now.duration_since(last_t).as_secs_f64().max(1e-3);
+            let rate = |x: u64, y: u64| (x - y) as f64 / secs;
+            sparks[0].push(rate(cur.dispatches, last.dispatches));
+            sparks[1].push(rate(cur.broadcasts, last.broadcasts));
+            sparks[2].push(rate(cur.discards, last.discards));
+            let throughput = rate(cur.dispatches + cur.discards, last.dispatches + last.discards);
+
+            progress.set_position(cur.sent);
+            lines[0].set_message(format!("throughput {:>10.0} frames/s   holder {:>6}   table {:>6}",
+                                         throughput, cur.holder, cur.table));
+            for (line, (name, spark)) in lines[1..4].iter().zip(["dispatch", "broadcast", "discard"].iter().zip(&sparks)) {
+                line.set_message(format!("{:<10}{:>10.0}/s {}", name, spark.last(), spark.draw()));
+            }
+            lines[4].set_message(format!("requests {}   broadcasts {}   dispatches {}   discards {}",
+                                         cur.requests, cur.broadcasts, cur.dispatches, cur.discards));
+
+            if done {
+                break;
+            }
+            last = cur;
+            last_t = now;
+            thread::sleep(REFRESH);
+        }
+        progress.finish();
+        lines.iter().for_each(|x| x.finish());
+    }
+}
@@ -0,0 +1,118 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+use log::{info, warn};
+use crate::live::{LiveMetrics, LATENCY_BOUNDS};
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+/// Outcomes labeling latency histograms, in the order of `LiveMetrics::latency`.
+const OUTCOMES: [&str; 3] = ["direct", "held", "discarded"];
+/// Time a client may take to send its request or to take the answer.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn load(x: &AtomicU64) -> u64 {
+    x.load(Ordering::Relaxed)
+}
+
+fn metric(body: &mut String, name: &str, ty: &str, help: &str) {
+    writeln!(body, "# TYPE {} {}", name, ty).unwrap();
+    writeln!(body, "# HELP {} {}", name, help).unwrap();
+}
+
+/// Render metrics in the OpenMetrics text format.
+pub fn render(metrics: &LiveMetrics) -> String {
+    let mut body = String::new();
+    let counters = [
+        ("orchestrator_frames", "Frames sent to the bridge.", &metrics.sent),
+        ("bridge_requests", "Requests of routing frames handled.", &metrics.requests),
+        ("bridge_broadcasts", "Queries broadcast for unknown destinations.", &metrics.broadcasts),
+        ("bridge_dispatches", "Frames dispatched or forwarded.", &metrics.dispatches),
+        ("bridge_discards", "Frames discarded.", &metrics.discards),
+        ("facility_successes", "Queries answered with the segment found.", &metrics.successes),
+        ("facility_failures", "Queries answered with no segment found.", &metrics.failures),
+    ];
+    for (name, help, value) in counters {
+        metric(&mut body, name, "counter", help);
+        writeln!(body, "{}_total {}", name, load(value)).unwrap();
+    }
+    let gauges = [
+        ("bridge_holder_length", "Destinations frames are held for.", &metrics.holder),
+        ("bridge_table_size", "Addresses in the bridge table.", &metrics.table),
+    ];
+    for (name, help, value) in gauges {
+        metric(&mut body, name, "gauge", help);
+        writeln!(body, "{} {}", name, load(value)).unwrap();
+    }
+    metric(&mut body, "channel_depth", "gauge", "Messages waiting in channels.");
+    writeln!(body, "channel_depth{{channel=\"event\"}} {}", load(&metrics.event_depth)).unwrap();
+    writeln!(body, "channel_depth{{channel=\"command\"}} {}", load(&metrics.command_depth)).unwrap();
+
+    metric(&mut body, "bridge_latency_seconds", "histogram",
+           "Latencies of frames from arrival to dispatch or discard.");
+    for (outcome, hist) in OUTCOMES.iter().zip(metrics.latency.iter()) {
+        let mut cumulative = 0;
+        for (i, cnt) in hist.buckets.iter().enumerate() {
+            cumulative += load(cnt);
+            let le = LATENCY_BOUNDS.get(i).map_or("+Inf".to_string(), |x| x.to_string());
+            writeln!(body, "bridge_latency_seconds_bucket{{outcome=\"{}\",le=\"{}\"}} {}", outcome, le, cumulative).unwrap();
+        }
+        writeln!(body, "bridge_latency_seconds_sum{{outcome=\"{}\"}} {}", outcome, load(&hist.sum_ns) as f64 / 1e9).unwrap();
+        writeln!(body, "bridge_latency_seconds_count{{outcome=\"{}\"}} {}", outcome, load(&hist.count)).unwrap();
+    }
+    body.push_str("# EOF\n");
+    body
+}
+
+/// Answer one HTTP request, with metrics at `/metrics` and nothing elsewhere.
+fn respond(stream: TcpStream, metrics: &LiveMetrics) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut r = BufReader::new(&stream);
+    let mut request = String::new();
+    r.read_line(&mut request)?;
+    // skip headers
+    let mut line = String::new();
+    while r.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    let path = request.split(' ').nth(1).unwrap_or_default();
+    let (status, body) = match path {
+        "/metrics" => ("200 OK", render(metrics)),
+        _ => ("404 Not Found", String::new()),
+    };
+    let mut w = &stream;
+    write!(w, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+           status, CONTENT_TYPE, body.len(), body)?;
+    w.flush()
+}
+
+/// Serve metrics at `http://127.0.0.1:<port>/metrics` on a thread of its own, for as long as the
+/// process runs, answering every connection on a thread of its own too so that a slow client
+/// holds up no other. Port 0 picks any free port, the address bound being returned.
+pub fn serve(metrics: Arc<LiveMetrics>, port: u16) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let addr = listener.local_addr()?;
+    info!(target: "metrics", "Serving metrics at http://{}/metrics.", addr);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(x) => x,
+                Err(err) => {
+                    warn!(target: "metrics", "Failed to accept connection: {}", err);
+                    continue;
+                }
+            };
+            let metrics = metrics.clone();
+            thread::spawn(move || {
+                if let Err(err) = respond(stream, &metrics) {
+                    warn!(target: "metrics", "Failed to serve metrics: {}", err);
+                }
+            });
+        }
+    });
+    Ok(addr)
+}
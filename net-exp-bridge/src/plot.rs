@@ -0,0 +1,179 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use net_exp_checksum::plot::{FONT, SIZE};
+pub use net_exp_checksum::plot::PlotResult;
+
+/// Render a chart to an SVG string, and also to `<path>.svg` and `<path>.png` if a path is given.
+macro_rules! render {
+    ($path:expr, $draw:ident($($arg:expr),*)) => {{
+        let mut svg = String::new();
+        $draw(SVGBackend::with_string(&mut svg, SIZE).into_drawing_area(), $($arg),*)?;
+        if let Some(path) = $path {
+            fs::write(with_extension(path, "svg"), &svg)?;
+            let png = with_extension(path, "png");
+            $draw(BitMapBackend::new(&png, SIZE).into_drawing_area(), $($arg),*)?;
+        }
+        Ok(svg)
+    }};
+}
+
+/// Path of a file of a chart, the extension appended to the path given even if it holds a dot.
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut x = OsString::from(path);
+    x.push(".");
+    x.push(extension);
+    x.into()
+}
+
+/// Range covering all values, never empty.
+fn range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values.fold((f64::MAX, f64::MIN), |(min, max), x| (min.min(x), max.max(x)));
+    if min > max {
+        (0.0, 1.0)
+    } else if min == max {
+        (min - 0.5, max + 0.5)
+    } else {
+        (min, max)
+    }
+}
+
+/// Render a density histogram of labeled series overlaid, to SVG and optionally PNG.
+pub fn histogram(path: Option<&Path>, x_label: &str, series: &[(&str, &[f64])], bins: usize) -> PlotResult<String> {
+    render!(path, draw_histogram(x_label, series, bins))
+}
+
+/// Render a scatter of labeled series of points, to SVG and optionally PNG.
+///
+/// Labels are shown in a legend unless there is only one series.
+pub fn scatter(path: Option<&Path>, x_label: &str, y_label: &str, series: &[(&str, &[(f64, f64)])]) -> PlotResult<String> {
+    render!(path, draw_scatter(x_label, y_label, series))
+}
+
+/// Render lines of labeled series of points overlaid, to SVG and optionally PNG.
+pub fn lines(path: Option<&Path>, x_label: &str, y_label: &str, series: &[(&str, &[(f64, f64)])]) -> PlotResult<String> {
+    render!(path, draw_lines(x_label, y_label, series))
+}
+
+fn draw_histogram<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    x_label: &str,
+    series: &[(&str, &[f64])],
+    bins: usize,
+) -> PlotResult where DB::ErrorType: 'static {
+    root.fill(&WHITE)?;
+    let (min, max) = range(series.iter().flat_map(|(_, x)| x.iter().copied()));
+    let width = (max - min) / bins as f64;
+    let densities = series.iter()
+        .map(|(_, values)| {
+            let mut counts = vec![0usize; bins];
+            for x in values.iter() {
+                counts[(((x - min) / width) as usize).min(bins - 1)] += 1;
+            }
+            let total = values.len().max(1) as f64;
+            counts.into_iter().map(|x| x as f64 / total / width).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let (_, y_max) = range(densities.iter().flatten().copied().chain([0.0]));
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .x_label_area_size(60)
+        .y_label_area_size(100)
+        .build_cartesian_2d(min..max, 0.0..y_max * 1.05)?;
+    chart.configure_mesh()
+        .x_desc(x_label)
+        .y_desc("density")
+        .label_style(FONT)
+        .draw()?;
+    for (i, ((label, _), density)) in series.iter().zip(densities.iter()).enumerate() {
+        let color = Palette99::pick(i).mix(0.5);
+        chart.draw_series(density.iter().enumerate().map(|(j, y)| {
+            let x = min + j as f64 * width;
+            Rectangle::new([(x, 0.0), (x + width, *y)], color.filled())
+        }))?
+            .label(*label)
+            .legend(move |(x, y)| Rectangle::new([(x, y - 8), (x + 16, y + 8)], color.filled()));
+    }
+    chart.configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .label_font(FONT)
+        .draw()?;
+    root.present()?;
+    Ok(())
+}
+
+fn draw_scatter<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    x_label: &str,
+    y_label: &str,
+    series: &[(&str, &[(f64, f64)])],
+) -> PlotResult where DB::ErrorType: 'static {
+    root.fill(&WHITE)?;
+    let (x_min, x_max) = range(series.iter().flat_map(|(_, x)| x.iter().map(|(x, _)| *x)));
+    let (y_min, y_max) = range(series.iter().flat_map(|(_, x)| x.iter().map(|(_, y)| *y)));
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .x_label_area_size(60)
+        .y_label_area_size(100)
+        .build_cartesian_2d(x_min..x_max, y_min..y_max)?;
+    chart.configure_mesh()
+        .x_desc(x_label)
+        .y_desc(y_label)
+        .label_style(FONT)
+        .draw()?;
+    for (i, (label, points)) in series.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        chart.draw_series(points.iter().map(|x| Pixel::new(*x, color)))?
+            .label(*label)
+            .legend(move |(x, y)| Circle::new((x + 8, y), 6, color.filled()));
+    }
+    if series.len() > 1 {
+        chart.configure_series_labels()
+            .position(SeriesLabelPosition::UpperRight)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .label_font(FONT)
+            .draw()?;
+    }
+    root.present()?;
+    Ok(())
+}
+
+fn draw_lines<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    x_label: &str,
+    y_label: &str,
+    series: &[(&str, &[(f64, f64)])],
+) -> PlotResult where DB::ErrorType: 'static {
+    root.fill(&WHITE)?;
+    let (x_min, x_max) = range(series.iter().flat_map(|(_, x)| x.iter().map(|(x, _)| *x)));
+    let (_, y_max) = range(series.iter().flat_map(|(_, x)| x.iter().map(|(_, y)| *y)).chain([0.0]));
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .x_label_area_size(60)
+        .y_label_area_size(100)
+        .build_cartesian_2d(x_min..x_max, 0.0..y_max * 1.05)?;
+    chart.configure_mesh()
+        .x_desc(x_label)
+        .y_desc(y_label)
+        .label_style(FONT)
+        .draw()?;
+    for (i, (label, points)) in series.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        chart.draw_series(LineSeries::new(points.iter().copied(), color.stroke_width(2)))?
+            .label(*label)
+            .legend(move |(x, y)| PathElement::new([(x, y), (x + 16, y)], color.stroke_width(2)));
+    }
+    chart.configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .label_font(FONT)
+        .draw()?;
+    root.present()?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; max-width: 72em; margin: 2em auto; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: right; }
+th { background: #f0f0f0; }
+td:first-child, th:first-child { text-align: left; }
+figure { margin: 1em 0; }
+figure svg { width: 100%; height: auto; }
+figcaption { text-align: center; color: #555; }
+";
+
+/// Escape text to be put in HTML.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Builder of a self-contained HTML report, with charts embedded inline.
+pub struct HtmlReport {
+    title: String,
+    body: String,
+}
+
+impl HtmlReport {
+    pub fn new(title: &str) -> Self {
+        HtmlReport { title: title.to_string(), body: String::new() }
+    }
+
+    pub fn heading(&mut self, text: &str) {
+        writeln!(self.body, "<h2>{}</h2>", escape(text)).unwrap();
+    }
+
+    pub fn paragraph(&mut self, text: &str) {
+        writeln!(self.body, "<p>{}</p>", escape(text)).unwrap();
+    }
+
+    /// Add a table with a header row.
+    pub fn table<S: AsRef<str>>(&mut self, header: &[&str], rows: &[Vec<S>]) {
+        self.body.push_str("<table>\n<tr>");
+        for cell in header {
+            write!(self.body, "<th>{}</th>", escape(cell)).unwrap();
+        }
+        self.body.push_str("</tr>\n");
+        for row in rows {
+            self.body.push_str("<tr>");
+            for cell in row {
+                write!(self.body, "<td>{}</td>", escape(cell.as_ref())).unwrap();
+            }
+            self.body.push_str("</tr>\n");
+        }
+        self.body.push_str("</table>\n");
+    }
+
+    /// Add a chart rendered as SVG, with a caption.
+    pub fn figure(&mut self, svg: &str, caption: &str) {
+        // drop the XML declaration, not allowed inside HTML
+        let svg = match svg.find("<svg") {
+            Some(begin) => &svg[begin..],
+            None => svg,
+        };
+        writeln!(self.body, "<figure>\n{}\n<figcaption>{}</figcaption>\n</figure>", svg, escape(caption)).unwrap();
+    }
+
+    pub fn finish(self) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n<style>\n{1}</style>\n</head>\n<body>\n<h1>{0}</h1>\n{2}</body>\n</html>\n",
+            escape(&self.title), STYLE, self.body)
+    }
+
+    pub fn write(self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.finish())
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, BTreeSet};
+use clap::ValueEnum;
+use serde::{Serialize, Deserialize};
+use crate::{Address, Segment};
+
+/// Action taken when a secure segment sees a violating source.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+pub enum ViolationAction {
+    /// Drop the frame silently.
+    Protect,
+    /// Drop the frame and count the violation.
+    Restrict,
+    /// Count the violation and disable the segment, dropping everything from it afterwards.
+    Shutdown,
+}
+
+/// Configuration of port security, shared by all segments.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct PortSecurityConfig {
+    /// Maximum count of secure addresses learned on one segment.
+    pub max_addr: usize,
+    /// Whether learned addresses are sticky, i.e. kept across runs.
+    pub sticky: bool,
+    /// Action taken on violation.
+    pub action: ViolationAction,
+}
+
+/// Decision made on an incoming source.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Verdict {
+    /// The source is secure on the segment, go on with it.
+    Admit,
+    /// The frame is dropped.
+    Drop,
+    /// The frame is dropped and the segment has just been shut down by it.
+    Shutdown,
+}
+
+/// State of port security of one segment.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecurePort {
+    /// Addresses secured on this segment.
+    pub secure: BTreeSet<Address>,
+    /// Whether the segment has been shut down by a violation.
+    pub shutdown: bool,
+    /// Count of violations as counted by `Restrict` and `Shutdown` actions.
+    pub violations: usize,
+    /// Count of frames dropped on this segment, regardless of action.
+    pub dropped: usize,
+}
+
+/// Port security of the bridge, tracking secure addresses of every segment.
+#[derive(Serialize, Deserialize)]
+pub struct PortSecurity {
+    config: PortSecurityConfig,
+    ports: BTreeMap<Segment, SecurePort>,
+    owner: BTreeMap<Address, Segment>,
+}
+
+impl PortSecurity {
+    pub fn new(config: PortSecurityConfig) -> Self {
+        PortSecurity { config, ports: BTreeMap::new(), owner: BTreeMap::new() }
+    }
+
+    /// Preload sticky addresses, as exported by `sticky` of a previous run.
+    pub fn load_sticky(&mut self, sticky: &[(Address, Segment)]) {
+        for (addr, seg) in sticky {
+            self.ports.entry(*seg).or_default().secure.insert(*addr);
+            self.owner.insert(*addr, *seg);
+        }
+    }
+
+    /// Check a source arriving on a segment, learning it if there is still room.
+    ///
+    /// A source violates the policy if its segment is already full of other secure addresses,
+    /// or if it is secured on another segment.
+    pub fn admit(&mut self, src: Address, seg: Segment) -> Verdict {
+        let port = self.ports.entry(seg).or_default();
+        if port.shutdown {
+            port.dropped += 1;
+            return Verdict::Drop;
+        }
+        let violating = match self.owner.get(&src) {
+            Some(owner) => *owner != seg,
+            None => port.secure.len() >= self.config.max_addr,
+        };
+        if !violating {
+            if port.secure.insert(src) {
+                self.owner.insert(src, seg);
+            }
+            return Verdict::Admit;
+        }
+        port.dropped += 1;
+        match self.config.action {
+            ViolationAction::Protect => Verdict::Drop,
+            ViolationAction::Restrict => {
+                port.violations += 1;
+                Verdict::Drop
+            }
+            ViolationAction::Shutdown => {
+                port.violations += 1;
+                port.shutdown = true;
+                Verdict::Shutdown
+            }
+        }
+    }
+
+    pub fn config(&self) -> &PortSecurityConfig {
+        &self.config
+    }
+
+    pub fn ports(&self) -> &BTreeMap<Segment, SecurePort> {
+        &self.ports
+    }
+
+    /// Secure addresses to be kept across runs, empty if learning is not sticky.
+    pub fn sticky(&self) -> Vec<(Address, Segment)> {
+        if !self.config.sticky {
+            return Vec::new();
+        }
+        self.owner.iter().map(|(addr, seg)| (*addr, *seg)).collect()
+    }
+
+    /// Total count of frames dropped.
+    pub fn dropped(&self) -> usize {
+        self.ports.values().map(|x| x.dropped).sum()
+    }
+
+    /// Total count of violations.
+    pub fn violations(&self) -> usize {
+        self.ports.values().map(|x| x.violations).sum()
+    }
+
+    /// Count of segments shut down.
+    pub fn shutdowns(&self) -> usize {
+        self.ports.values().filter(|x| x.shutdown).count()
+    }
+}
@@ -0,0 +1,206 @@
+//! Sinks writing series of statistics as tables, in formats read outside Python.
+//!
+//! Every series is written as one table to `<name>.<extension>`, with the columns below. Times
+//! are in microseconds since the simulation starts, and frames are told by their sequence numbers.
+//!
+//! | table        | column        | type   | meaning                                               |
+//! |--------------|---------------|--------|-------------------------------------------------------|
+//! | `activity`   | `time_us`     | int64  | time of the activity                                  |
+//! |              | `kind`        | string | `broadcast`, `dispatch` or `discard`                  |
+//! |              | `frame_id`    | uint64 | frame acted on                                        |
+//! | `latency`    | `frame_id`    | uint64 | frame held for its destination unknown                |
+//! |              | `time_us`     | int64  | time the frame is held                                |
+//! |              | `latency_us`  | int64  | time from being held to dispatch or discard           |
+//! | `congestion` | `time_us`     | int64  | time of the sample                                    |
+//! |              | `pending`     | uint64 | count of destinations frames are held for             |
+//!
+//! CSV files have a header row, JSON Lines files hold one object per row keyed by column names,
+//! and Parquet and Arrow IPC files carry the types above in their schemas.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use clap::ValueEnum;
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+
+/// Values of a column.
+pub enum Column {
+    Int(Vec<i64>),
+    UInt(Vec<u64>),
+    Str(Vec<&'static str>),
+}
+
+impl Column {
+    fn len(&self) -> usize {
+        match self {
+            Column::Int(x) => x.len(),
+            Column::UInt(x) => x.len(),
+            Column::Str(x) => x.len(),
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Column::Int(_) => DataType::Int64,
+            Column::UInt(_) => DataType::UInt64,
+            Column::Str(_) => DataType::Utf8,
+        }
+    }
+
+    fn to_json(&self, row: usize) -> serde_json::Value {
+        match self {
+            Column::Int(x) => x[row].into(),
+            Column::UInt(x) => x[row].into(),
+            Column::Str(x) => x[row].into(),
+        }
+    }
+
+    fn to_csv(&self, row: usize) -> String {
+        match self {
+            Column::Int(x) => x[row].to_string(),
+            Column::UInt(x) => x[row].to_string(),
+            Column::Str(x) => x[row].to_string(),
+        }
+    }
+
+    fn to_arrow(&self) -> ArrayRef {
+        match self {
+            Column::Int(x) => Arc::new(Int64Array::from(x.clone())),
+            Column::UInt(x) => Arc::new(UInt64Array::from(x.clone())),
+            Column::Str(x) => Arc::new(StringArray::from(x.clone())),
+        }
+    }
+}
+
+/// Series of statistics, as a table of named columns of the same length.
+pub struct Table {
+    pub name: &'static str,
+    pub columns: Vec<(&'static str, Column)>,
+}
+
+impl Table {
+    pub fn new(name: &'static str) -> Self {
+        Table { name, columns: Vec::new() }
+    }
+
+    pub fn column(mut self, name: &'static str, column: Column) -> Self {
+        self.columns.push((name, column));
+        self
+    }
+
+    pub fn rows(&self) -> usize {
+        self.columns.first().map_or(0, |(_, x)| x.len())
+    }
+
+    fn to_arrow(&self) -> io::Result<RecordBatch> {
+        let schema = Schema::new(self.columns.iter()
+            .map(|(name, x)| Field::new(*name, x.data_type(), false))
+            .collect::<Vec<_>>());
+        let arrays = self.columns.iter().map(|(_, x)| x.to_arrow()).collect();
+        RecordBatch::try_new(Arc::new(schema), arrays).map_err(io::Error::other)
+    }
+}
+
+/// Format statistics are written in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum StatsFormat {
+    /// Python pickle, read by the analysis notebook.
+    Pickle,
+    /// Comma separated values with a header row.
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+    /// Apache Parquet.
+    Parquet,
+    /// Apache Arrow IPC file.
+    Arrow,
+}
+
+impl StatsFormat {
+    /// Sink writing tables in the format to a directory, if the format is written as tables.
+    pub fn sink(&self, dir: &Path) -> Option<Box<dyn StatsSink>> {
+        let dir = dir.to_path_buf();
+        match self {
+            StatsFormat::Pickle => None,
+            StatsFormat::Csv => Some(Box::new(CsvSink { dir })),
+            StatsFormat::Jsonl => Some(Box::new(JsonLinesSink { dir })),
+            StatsFormat::Parquet => Some(Box::new(ParquetSink { dir })),
+            StatsFormat::Arrow => Some(Box::new(ArrowSink { dir })),
+        }
+    }
+}
+
+/// Destination of tables of statistics.
+pub trait StatsSink {
+    fn write(&mut self, table: &Table) -> io::Result<()>;
+}
+
+pub struct CsvSink {
+    dir: PathBuf,
+}
+
+impl StatsSink for CsvSink {
+    fn write(&mut self, table: &Table) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(self.dir.join(format!("{}.csv", table.name)))?);
+        let header = table.columns.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        writeln!(w, "{}", header.join(","))?;
+        for row in 0..table.rows() {
+            let cells = table.columns.iter().map(|(_, x)| x.to_csv(row)).collect::<Vec<_>>();
+            writeln!(w, "{}", cells.join(","))?;
+        }
+        w.flush()
+    }
+}
+
+pub struct JsonLinesSink {
+    dir: PathBuf,
+}
+
+impl StatsSink for JsonLinesSink {
+    fn write(&mut self, table: &Table) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(self.dir.join(format!("{}.jsonl", table.name)))?);
+        for row in 0..table.rows() {
+            let obj = table.columns.iter()
+                .map(|(name, x)| (name.to_string(), x.to_json(row)))
+                .collect::<serde_json::Map<_, _>>();
+            serde_json::to_writer(&mut w, &obj)?;
+            writeln!(w)?;
+        }
+        w.flush()
+    }
+}
+
+pub struct ParquetSink {
+    dir: PathBuf,
+}
+
+impl StatsSink for ParquetSink {
+    fn write(&mut self, table: &Table) -> io::Result<()> {
+        let batch = table.to_arrow()?;
+        let file = File::create(self.dir.join(format!("{}.parquet", table.name)))?;
+        let mut w = ArrowWriter::try_new(file, batch.schema(), None).map_err(io::Error::other)?;
+        w.write(&batch).map_err(io::Error::other)?;
+        w.close().map_err(io::Error::other)?;
+        Ok(())
+    }
+}
+
+pub struct ArrowSink {
+    dir: PathBuf,
+}
+
+impl StatsSink for ArrowSink {
+    fn write(&mut self, table: &Table) -> io::Result<()> {
+        let batch = table.to_arrow()?;
+        let file = File::create(self.dir.join(format!("{}.arrow", table.name)))?;
+        let mut w = arrow_ipc::writer::FileWriter::try_new(BufWriter::new(file), &batch.schema())
+            .map_err(io::Error::other)?;
+        w.write(&batch).map_err(io::Error::other)?;
+        w.finish().map_err(io::Error::other)
+    }
+}
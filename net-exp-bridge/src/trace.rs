@@ -0,0 +1,144 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// State a frame goes into in the bridge.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum FrameState {
+    /// Arrived at the bridge.
+    Arrived,
+    /// Held in the waiting list, its destination unknown.
+    Held,
+    /// Made the bridge query segments for its destination.
+    Queried,
+    /// Released from the waiting list, its destination found or not.
+    Released,
+    /// Dispatched to the segment of its destination, or forwarded to segments of its group.
+    Dispatched,
+    /// Discarded.
+    Discarded,
+}
+
+impl Display for FrameState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FrameState::Arrived => "arrived",
+            FrameState::Held => "held",
+            FrameState::Queried => "queried",
+            FrameState::Released => "released",
+            FrameState::Dispatched => "dispatched",
+            FrameState::Discarded => "discarded",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Transition of a frame into a state, at some time since the simulation starts.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    pub id: u64,
+    pub state: FrameState,
+    pub time: Duration,
+}
+
+/// Writer of transitions of frames, doing nothing if tracing is off.
+///
+/// Writing stops at the first error, which is kept to be returned when finishing, rather than
+/// failing every record of the simulation.
+pub struct Tracer {
+    w: Option<BufWriter<File>>,
+    err: Option<io::Error>,
+}
+
+impl Tracer {
+    /// Tracer writing to a file, or doing nothing if no file is given.
+    pub fn new(path: Option<&Path>) -> io::Result<Self> {
+        let w = match path {
+            Some(path) => Some(BufWriter::new(File::create(path)?)),
+            None => None,
+        };
+        Ok(Tracer { w, err: None })
+    }
+
+    /// Tracer going on with a file from a length written before, dropping whatever follows, or
+    /// doing nothing if no file is given. Fails if the file is missing or shorter, having lost
+    /// records written before.
+    pub fn resume(path: Option<&Path>, len: u64) -> io::Result<Self> {
+        let w = match path {
+            Some(path) => {
+                let context = |err: io::Error| io::Error::new(err.kind(), format!("trace {}: {}", path.display(), err));
+                let mut file = OpenOptions::new().write(true).open(path).map_err(context)?;
+                let found = file.metadata()?.len();
+                if found < len {
+                    return Err(context(io::Error::new(io::ErrorKind::InvalidData,
+                        format!("{} bytes long, short of the {} bytes traced before", found, len))));
+                }
+                file.set_len(len)?;
+                file.seek(SeekFrom::End(0))?;
+                Some(BufWriter::new(file))
+            }
+            None => None,
+        };
+        Ok(Tracer { w, err: None })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.w.is_some()
+    }
+
+    pub fn rec(&mut self, id: u64, state: FrameState, time: Duration) {
+        if let (Some(w), None) = (&mut self.w, &self.err) {
+            if let Err(err) = rmp_serde::encode::write(w, &TraceRecord { id, state, time }) {
+                self.err = Some(io::Error::other(err));
+            }
+        }
+    }
+
+    /// Flush records written, or fail with the first error of writing them.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if let Some(err) = self.err.take() {
+            return Err(err);
+        }
+        match &mut self.w {
+            Some(w) => w.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Flush and tell the length of the file written so far, 0 if tracing is off.
+    pub fn written(&mut self) -> io::Result<u64> {
+        if let Some(err) = &self.err {
+            return Err(io::Error::new(err.kind(), err.to_string()));
+        }
+        match &mut self.w {
+            Some(w) => {
+                w.flush()?;
+                w.get_mut().stream_position()
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+/// Read all transitions traced in a file, in the order they are written.
+///
+/// Reading ends at the end of the file, or with an error at a record cut short or corrupt.
+pub fn read_trace(path: &Path) -> io::Result<impl Iterator<Item = io::Result<TraceRecord>>> {
+    let mut r = BufReader::new(File::open(path)?);
+    let mut failed = false;
+    Ok(std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        let rec = match r.fill_buf() {
+            Ok([]) => return None,
+            Ok(_) => rmp_serde::decode::from_read(&mut r).map_err(io::Error::other),
+            Err(err) => Err(err),
+        };
+        failed = rec.is_err();
+        Some(rec)
+    }))
+}
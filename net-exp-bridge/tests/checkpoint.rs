@@ -0,0 +1,84 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use common::{run, work_dir};
+
+/// Files written by the generator.
+const INPUTS: [&str; 6] = ["addr_seg.rmp", "inv_addr.rmp", "frame.rmp", "generate.json", "addr_seg.txt", "inv_addr.txt"];
+/// Files written by the simulator that differ between runs, the report recording the options.
+const VARYING: [&str; 2] = ["checkpoint.rmp", "report.html"];
+
+fn simulate(dir: &Path, extra: &[&str]) -> Output {
+    let mut args = vec!["--deterministic", "--stats-format", "pickle,csv,jsonl", "--trace", "--no-plot",
+                        "--max-addr", "4", "--violation", "restrict", "--table-size", "400", "--aging", "3000"];
+    args.extend(extra);
+    run(dir, env!("CARGO_BIN_EXE_simulate"), &args)
+}
+
+/// Directory of generated frames, with spoofed and flooded ones to exercise port security and the
+/// table, and a copy of it for another run.
+fn generate(name: &str) -> (PathBuf, PathBuf) {
+    let dir = work_dir(name);
+    let output = run(&dir, env!("CARGO_BIN_EXE_generate"), &[
+        "--seed", "7", "--valid-addr", "500", "--seg", "10", "--valid-frame", "20000", "--invalid-frame", "500",
+        "--spoofed-frame", "200", "--flood-frame", "1000",
+    ]);
+    assert!(output.status.success());
+    let copy = work_dir(&format!("{}-copy", name));
+    for x in INPUTS {
+        fs::copy(dir.join(x), copy.join(x)).unwrap();
+    }
+    (dir, copy)
+}
+
+#[test]
+fn resumed_run_same_as_uninterrupted() {
+    let (whole, resumed) = generate("checkpoint-resume");
+    // the last checkpoint is taken at 9000 ms
+    assert!(simulate(&whole, &["--checkpoint", "--checkpoint-every", "3000"]).status.success());
+    fs::copy(whole.join("checkpoint.rmp"), resumed.join("checkpoint.rmp")).unwrap();
+    // the trace written up to the checkpoint, and some more to be cut off on resumption
+    let mut trace = fs::read(whole.join("trace.rmp")).unwrap();
+    trace.extend([0xc0; 64]);
+    fs::write(resumed.join("trace.rmp"), trace).unwrap();
+    assert!(simulate(&resumed, &["--resume", "checkpoint.rmp"]).status.success());
+    let mut compared = 0;
+    for x in fs::read_dir(&whole).unwrap() {
+        let name = x.unwrap().file_name().into_string().unwrap();
+        if INPUTS.contains(&name.as_str()) || VARYING.contains(&name.as_str()) {
+            continue;
+        }
+        let (a, b) = (fs::read(whole.join(&name)).unwrap(), fs::read(resumed.join(&name)).unwrap());
+        assert!(a == b, "{} differs after resumption", name);
+        compared += 1;
+    }
+    assert!(compared >= 10, "only {} files of statistics written", compared);
+    fs::remove_dir_all(whole).unwrap();
+    fs::remove_dir_all(resumed).unwrap();
+}
+
+#[test]
+fn bad_checkpoint_reported() {
+    let (dir, other) = generate("checkpoint-bad");
+    assert!(simulate(&dir, &["--checkpoint", "--checkpoint-every", "5000"]).status.success());
+    let checkpoint = fs::read(dir.join("checkpoint.rmp")).unwrap();
+    fs::write(dir.join("truncated.rmp"), &checkpoint[..checkpoint.len() / 2]).unwrap();
+    fs::write(dir.join("garbage.rmp"), b"not a checkpoint").unwrap();
+    for x in ["truncated.rmp", "garbage.rmp", "missing.rmp"] {
+        let output = simulate(&dir, &["--resume", x]);
+        assert_eq!(output.status.code(), Some(1), "resuming from {}", x);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Cannot resume from"));
+    }
+
+    // a checkpoint of other frames
+    let output = run(&other, env!("CARGO_BIN_EXE_generate"), &["--seed", "7", "--valid-frame", "100", "--invalid-frame", "0"]);
+    assert!(output.status.success());
+    fs::copy(dir.join("checkpoint.rmp"), other.join("checkpoint.rmp")).unwrap();
+    let output = simulate(&other, &["--resume", "checkpoint.rmp"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not of the 100 frames loaded"));
+    fs::remove_dir_all(dir).unwrap();
+    fs::remove_dir_all(other).unwrap();
+}
@@ -0,0 +1,22 @@
+//! Helpers shared by tests running the binaries.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Empty directory of a test, named uniquely among all tests.
+pub fn work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("net-exp-bridge-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run a binary in a directory, which must not panic whether it succeeds or not.
+pub fn run(dir: &Path, bin: &str, args: &[&str]) -> Output {
+    let output = Command::new(bin).args(args).current_dir(dir).output().unwrap();
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("panicked"),
+            "{} panicked: {}", bin, String::from_utf8_lossy(&output.stderr));
+    output
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+use common::{run, work_dir};
+
+/// Write a run discarding some frames with some p99 latency.
+fn write_run(dir: &Path, discard: usize, p99: f64) {
+    fs::create_dir_all(dir).unwrap();
+    let summary = format!(
+        r#"{{"dispatch": 1000, "discard": {}, "latency": {{"count": 1000, "min": 1.0, "mean": 2.0, "p50": 2.0, "p90": 3.0, "p99": {}, "p999": 5.0, "max": 6.0}}}}"#,
+        discard, p99);
+    fs::write(dir.join("summary.json"), summary).unwrap();
+}
+
+fn compare(dir: &Path, args: &[&str]) -> Output {
+    let args = [args, &["--out", "charts"]].concat();
+    run(dir, env!("CARGO_BIN_EXE_compare"), &args)
+}
+
+#[test]
+fn regression_fails() {
+    let dir = work_dir("compare-regression");
+    for (i, p99) in [10.0, 10.5, 9.5].into_iter().enumerate() {
+        write_run(&dir.join("base").join(i.to_string()), 10, p99);
+        write_run(&dir.join("same").join(i.to_string()), 10, p99 + 0.1);
+        write_run(&dir.join("worse").join(i.to_string()), 10, p99 * 2.0);
+    }
+    assert_eq!(compare(&dir, &["base", "same", "--fail-on", "p99"]).status.code(), Some(0));
+    let output = compare(&dir, &["base", "worse", "--fail-on", "p99,discard-rate"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("regression: p99/us worse by 100.00%"), "{}", stdout);
+    assert!(!stdout.contains("regression: discard rate"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn bad_runs_reported() {
+    let dir = work_dir("compare-bad");
+    write_run(&dir.join("base"), 10, 4.0);
+    fs::create_dir_all(dir.join("empty")).unwrap();
+    fs::create_dir_all(dir.join("corrupt")).unwrap();
+    fs::write(dir.join("corrupt/summary.json"), "{\"dispatch\": ").unwrap();
+    write_run(&dir.join("bad_latency"), 10, 4.0);
+    fs::write(dir.join("bad_latency/latency.json"), "[]").unwrap();
+    for (candidate, message) in [("empty", "no runs found"), ("missing", "missing"), ("corrupt", "summary.json"),
+                                 ("bad_latency", "latency.json")] {
+        let output = compare(&dir, &["base", candidate]);
+        assert_eq!(output.status.code(), Some(2), "comparing against {}", candidate);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("Cannot load runs") && stderr.contains(message), "{}", stderr);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn throughput_needs_windows() {
+    let dir = work_dir("compare-throughput");
+    write_run(&dir.join("base"), 10, 4.0);
+    write_run(&dir.join("candidate"), 10, 4.0);
+    let output = compare(&dir, &["base", "candidate"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("throughput"));
+    assert_eq!(compare(&dir, &["base", "candidate", "--fail-on", "throughput"]).status.code(), Some(2));
+
+    // the candidate lasting twice as long
+    let percentiles = r#"{"count": 10, "min": 1.0, "mean": 2.0, "p50": 2.0, "p90": 3.0, "p99": 4.0, "p999": 5.0, "max": 6.0}"#;
+    let windows = |seconds: usize| format!(r#"{{"windows": [{}]}}"#,
+        (0..seconds).map(|x| format!(r#"{{"second": {}, "all": {}}}"#, x, percentiles)).collect::<Vec<_>>().join(", "));
+    fs::write(dir.join("base/latency.json"), windows(2)).unwrap();
+    fs::write(dir.join("candidate/latency.json"), windows(4)).unwrap();
+    let output = compare(&dir, &["base", "candidate", "--fail-on", "throughput"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("regression: throughput/s worse by 50.00%"), "{}", stdout);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn chart_failure_reported() {
+    let dir = work_dir("compare-chart");
+    write_run(&dir.join("base"), 10, 4.0);
+    // charts put in a directory that is a file
+    fs::write(dir.join("charts"), "").unwrap();
+    let output = compare(&dir, &["base", "base"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Cannot render charts"));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,299 @@
+use std::fs;
+use std::net::Ipv4Addr;
+use proptest::prelude::*;
+use net_exp_bridge::dissect::*;
+use net_exp_checksum::{capture, checksum, packet};
+
+fn sample(name: &str) -> Vec<u8> {
+    fs::read(format!("{}/../net-exp-wireshark/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+}
+
+/// Frames of a sample capture.
+fn frames(name: &str) -> Vec<Vec<u8>> {
+    let file = sample(name);
+    capture::parse(&file).unwrap().iter().map(|x| x.data.to_vec()).collect()
+}
+
+/// Ethernet frame carrying an IPv4 packet with a valid header checksum.
+fn ipv4_frame(protocol: u8, flags_offset: u16, options: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut frame = packet::parse_hex("ffffffffffff02000000000108004500").unwrap();
+    frame[14] = 0x45 + options.len() as u8 / 4;
+    let total_len = 20 + options.len() + payload.len();
+    frame.extend((total_len as u16).to_be_bytes());
+    frame.extend([0x12, 0x34]);
+    frame.extend(flags_offset.to_be_bytes());
+    frame.extend([64, protocol, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2]);
+    frame.extend(options);
+    let sum = packet::ipv4_header(&frame[14..]);
+    frame[24..26].copy_from_slice(&sum.to_be_bytes());
+    frame.extend(payload);
+    frame
+}
+
+/// ICMP message with a valid checksum.
+fn icmp(kind: u8, code: u8, rest: [u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut message = vec![kind, code, 0, 0];
+    message.extend(rest);
+    message.extend(data);
+    let sum = checksum(&message);
+    message[2..4].copy_from_slice(&sum.to_be_bytes());
+    message
+}
+
+#[test]
+fn arp_from_capture() {
+    let frames = frames("ip-icmp-arp.pcapng");
+    let x = dissect(&frames[2]).unwrap();
+    assert_eq!(x.ethernet.src().to_string(), "f6:e2:65:d8:00:63");
+    assert_eq!(x.ethernet.ether_type(), ETHER_TYPE_ARP);
+    let Network::Arp(arp) = x.network else { panic!("not ARP: {:?}", x.network) };
+    assert_eq!(arp.operation(), ArpOperation::Request);
+    assert_eq!(arp.sender_ip(), Ipv4Addr::new(192, 168, 110, 217));
+    assert_eq!(arp.target_ip(), Ipv4Addr::new(192, 168, 110, 199));
+    assert_eq!(arp.target_mac(), MacAddress([0; 6]));
+    assert!(x.transport.is_none());
+
+    let Network::Arp(arp) = dissect(&frames[3]).unwrap().network else { panic!() };
+    assert_eq!(arp.operation(), ArpOperation::Reply);
+    assert_eq!(arp.sender_mac().to_string(), "c0:3c:59:4e:eb:39");
+}
+
+#[test]
+fn capture_dissected() {
+    let (mut arp, mut udp, mut tcp, mut other) = (0, 0, 0, 0);
+    for frame in frames("ip-icmp-arp.pcapng") {
+        let x = dissect(&frame).unwrap();
+        match (x.network, x.transport) {
+            (Network::Arp(_), None) => arp += 1,
+            (Network::Ipv4(ip), Some(Transport::Udp(x))) => {
+                assert!(ip.checksum_valid());
+                assert!(x.checksum_valid(&ip).is_some());
+                udp += 1;
+            }
+            (Network::Ipv4(ip), Some(Transport::Tcp(_))) => {
+                assert!(ip.checksum_valid());
+                tcp += 1;
+            }
+            (Network::Other(0x86dd, _), None) => other += 1,
+            x => panic!("unexpected {:?}", x),
+        }
+    }
+    assert_eq!((arp, udp, tcp, other), (4, 24, 14, 22));
+}
+
+#[test]
+fn tcp_handshake_options() {
+    let frames = frames("www-baidu-filtered.pcapng");
+    let x = dissect(&frames[0]).unwrap();
+    let (Network::Ipv4(ip), Some(Transport::Tcp(tcp))) = (x.network, x.transport) else { panic!() };
+    assert_eq!(ip.src(), Ipv4Addr::new(192, 168, 1, 161));
+    assert!(ip.dont_fragment() && !ip.is_fragment());
+    assert_eq!((tcp.src_port(), tcp.dst_port()), (51136, 443));
+    assert_eq!(tcp.flags().to_string(), "SYN");
+    assert_eq!(tcp.header_len(), 40);
+    assert!(tcp.payload().is_empty());
+    assert_eq!(tcp.checksum_valid(&ip), Some(true));
+    assert_eq!(tcp.options().collect::<Vec<_>>(), [
+        TcpOption::MaxSegmentSize(1460),
+        TcpOption::SackPermitted,
+        TcpOption::Timestamps { value: 0xf369_b0b5, echo: 0 },
+        TcpOption::WindowScale(7),
+    ]);
+
+    let reply = frames.iter()
+        .filter_map(|x| match dissect(x).unwrap().transport {
+            Some(Transport::Tcp(tcp)) if tcp.flags().contains(TcpFlags::SYN | TcpFlags::ACK) => Some(tcp.ack()),
+            _ => None,
+        })
+        .next()
+        .unwrap();
+    assert_eq!(reply, tcp.seq() + 1);
+}
+
+#[test]
+fn icmp_messages() {
+    let echo = icmp(8, 0, [0, 1, 0, 7], b"abcdefgh");
+    let frame = ipv4_frame(packet::ICMP, 0, &[], &echo);
+    let x = dissect(&frame).unwrap();
+    let (Network::Ipv4(ip), Some(Transport::Icmp(icmp_echo))) = (x.network, x.transport) else { panic!() };
+    assert_eq!(icmp_echo.checksum_valid(&ip), Some(true));
+    assert_eq!(icmp_echo.message(), IcmpMessage::EchoRequest { id: 1, seq: 7, data: b"abcdefgh" });
+    assert!(icmp_echo.original().is_none());
+
+    // a datagram dropped at TTL zero, quoted by its header and first 8 bytes
+    let dropped = ipv4_frame(packet::UDP, 0, &[], &packet::parse_hex("d43100350010abcd0102030405060708").unwrap());
+    let quoted = &dropped[14..14 + 28];
+    let exceeded = icmp(11, 0, [0; 4], quoted);
+    let frame = ipv4_frame(packet::ICMP, 0, &[], &exceeded);
+    let x = dissect(&frame).unwrap();
+    let Some(Transport::Icmp(message)) = x.transport else { panic!() };
+    assert_eq!(message.message(), IcmpMessage::TimeExceeded { code: 0, original: quoted });
+    let original = message.original().unwrap().unwrap();
+    assert_eq!((original.protocol(), original.total_len(), original.payload().len()), (packet::UDP, 36, 8));
+    assert!(original.checksum_valid());
+
+    let unreachable = icmp(3, 4, [0, 0, 0x05, 0xdc], quoted);
+    let frame = ipv4_frame(packet::ICMP, 0, &[], &unreachable);
+    let x = dissect(&frame).unwrap();
+    let Some(Transport::Icmp(message)) = x.transport else { panic!() };
+    assert_eq!(message.message(), IcmpMessage::DestinationUnreachable { code: 4, next_hop_mtu: 1500, original: quoted });
+}
+
+#[test]
+fn ipv4_options_and_fragments() {
+    // record route with room for one address, then end of list
+    let options = [7, 7, 4, 0, 0, 0, 0, 0];
+    let udp = packet::parse_hex("d4310035000a00006869").unwrap();
+    let frame = ipv4_frame(packet::UDP, 0, &options, &udp);
+    let x = dissect(&frame).unwrap();
+    let Network::Ipv4(ip) = x.network else { panic!() };
+    assert_eq!(ip.header_len(), 28);
+    assert!(ip.checksum_valid());
+    assert_eq!(ip.options().collect::<Vec<_>>(), [RawOption { kind: 7, data: &[4, 0, 0, 0, 0] }]);
+    let Some(Transport::Udp(udp)) = x.transport else { panic!() };
+    assert_eq!((udp.dst_port(), udp.payload()), (53, &b"hi"[..]));
+    assert_eq!(udp.checksum_valid(&ip), None);
+
+    let frame = ipv4_frame(packet::UDP, 0x2000 | 185, &[], b"fragment");
+    let x = dissect(&frame).unwrap();
+    let Network::Ipv4(ip) = x.network else { panic!() };
+    assert!(ip.more_fragments() && ip.is_fragment());
+    assert_eq!(ip.fragment_offset(), 1480);
+    assert_eq!(x.transport, Some(Transport::Fragment(b"fragment")));
+
+    // the first fragment carries the header of a datagram longer than itself
+    let frame = ipv4_frame(packet::UDP, 0x2000, &[], &packet::parse_hex("d4310035ffff12346869").unwrap());
+    let x = dissect(&frame).unwrap();
+    let (Network::Ipv4(ip), Some(Transport::Udp(udp))) = (x.network, x.transport) else { panic!() };
+    assert!(ip.is_fragment() && ip.checksum_valid());
+    assert_eq!((udp.dst_port(), udp.len(), udp.payload()), (53, 0xffff, &b"hi"[..]));
+    assert_eq!(udp.checksum_valid(&ip), None);
+
+    let mut segment = packet::parse_hex("c7c001bb00000001000000005010ffff00000000").unwrap();
+    segment.extend(b"GET");
+    let frame = ipv4_frame(packet::TCP, 0x2000, &[], &segment);
+    let x = dissect(&frame).unwrap();
+    let (Network::Ipv4(ip), Some(Transport::Tcp(tcp))) = (x.network, x.transport) else { panic!() };
+    assert_eq!((tcp.dst_port(), tcp.payload()), (443, &b"GET"[..]));
+    assert_eq!(tcp.checksum_valid(&ip), None);
+
+    let echo = icmp(8, 0, [0, 1, 0, 7], b"abcdefgh");
+    let frame = ipv4_frame(packet::ICMP, 0x2000, &[], &echo[..12]);
+    let x = dissect(&frame).unwrap();
+    let (Network::Ipv4(ip), Some(Transport::Icmp(icmp))) = (x.network, x.transport) else { panic!() };
+    assert_eq!(icmp.message(), IcmpMessage::EchoRequest { id: 1, seq: 7, data: b"abcd" });
+    assert_eq!(icmp.checksum_valid(&ip), None);
+}
+
+#[test]
+fn padding_and_vlan() {
+    // a short frame padded to 60 bytes, behind an 802.1Q tag
+    let mut frame = ipv4_frame(253, 0, &[], b"x");
+    frame.splice(12..12, [0x81, 0x00, 0xa0, 0x64]);
+    frame.resize(60, 0);
+    let x = dissect(&frame).unwrap();
+    assert_eq!(x.ethernet.vlans().collect::<Vec<_>>(), [Vlan { tpid: 0x8100, pcp: 5, dei: false, id: 100 }]);
+    assert_eq!(x.ethernet.header_len(), 18);
+    assert_eq!(x.transport, Some(Transport::Other(253, b"x")));
+}
+
+#[test]
+fn errors_structured() {
+    let echo = icmp(8, 0, [0; 4], &[]);
+    let frame = ipv4_frame(packet::ICMP, 0, &[], &echo);
+    assert_eq!(dissect(&frame[..10]), Err(DissectError::Truncated { layer: Layer::Ethernet, needed: 14, len: 10 }));
+    assert_eq!(dissect(&frame[..30]), Err(DissectError::Truncated { layer: Layer::Ipv4, needed: 20, len: 16 }));
+    assert_eq!(dissect(&frame[..40]), Err(DissectError::Truncated { layer: Layer::Ipv4, needed: 28, len: 26 }));
+
+    let mut bad = frame.clone();
+    bad[14] = 0x65;
+    assert_eq!(dissect(&bad), Err(DissectError::Malformed { layer: Layer::Ipv4, reason: "version not 4" }));
+    bad[14] = 0x44;
+    assert!(matches!(dissect(&bad), Err(DissectError::Malformed { layer: Layer::Ipv4, .. })));
+
+    let frame = ipv4_frame(packet::ICMP, 0, &[], &echo[..6]);
+    assert_eq!(dissect(&frame), Err(DissectError::Truncated { layer: Layer::Icmp, needed: 8, len: 6 }));
+    // option longer than the header
+    let frame = ipv4_frame(packet::ICMP, 0, &[7, 9, 4, 0], &echo);
+    assert!(matches!(dissect(&frame), Err(DissectError::Malformed { layer: Layer::Ipv4, .. })));
+    // data offset past the segment
+    let mut segment = [0; 20];
+    segment[12] = 0xf0;
+    let frame = ipv4_frame(packet::TCP, 0, &[], &segment);
+    assert_eq!(dissect(&frame), Err(DissectError::Truncated { layer: Layer::Tcp, needed: 60, len: 20 }));
+    let frame = ipv4_frame(packet::UDP, 0, &[], &packet::parse_hex("0001000200040000").unwrap());
+    assert!(matches!(dissect(&frame), Err(DissectError::Malformed { layer: Layer::Udp, .. })));
+
+    let mut frame = frames("ip-icmp-arp.pcapng").remove(2);
+    frame[19] = 6;
+    assert!(matches!(dissect(&frame), Err(DissectError::Malformed { layer: Layer::Arp, .. })));
+    assert!(matches!(dissect(&frame[..30]), Err(DissectError::Malformed { layer: Layer::Arp, .. })));
+}
+
+/// Use every accessor of a dissection, which must never panic.
+fn walk(x: &Dissection) -> usize {
+    let mut n = x.ethernet.vlans().count() + x.ethernet.payload().len();
+    match x.network {
+        Network::Arp(arp) => n += arp.sender_ip().octets()[0] as usize + arp.target_mac().0[0] as usize,
+        Network::Ipv4(ip) => {
+            n += ip.options().map(|x| x.data.len()).sum::<usize>() + ip.payload().len() + ip.fragment_offset();
+            n += ip.checksum_valid() as usize;
+            match x.transport {
+                Some(Transport::Icmp(icmp)) => {
+                    n += icmp.checksum_valid(&ip).is_some() as usize;
+                    n += matches!(icmp.message(), IcmpMessage::Other { .. }) as usize;
+                    if let Some(Ok(original)) = icmp.original() {
+                        n += original.payload().len() + original.options().count();
+                    }
+                }
+                Some(Transport::Tcp(tcp)) => {
+                    n += tcp.options().count() + tcp.payload().len() + tcp.checksum_valid(&ip).is_some() as usize;
+                    n += tcp.flags().to_string().len();
+                }
+                Some(Transport::Udp(udp)) => n += udp.payload().len() + udp.checksum_valid(&ip).is_some() as usize,
+                _ => {}
+            }
+        }
+        Network::Other(_, data) => n += data.len(),
+    }
+    n
+}
+
+proptest! {
+    #[test]
+    fn fuzz_random_bytes(data in prop::collection::vec(any::<u8>(), 0..256)) {
+        if let Ok(x) = dissect(&data) {
+            walk(&x);
+        }
+    }
+
+    #[test]
+    fn fuzz_ipv4_headers(ihl in 0u8..16, tail in prop::collection::vec(any::<u8>(), 0..120), protocol in prop::sample::select(vec![1u8, 6, 17, 99])) {
+        // frames that get past Ethernet and the first bytes of IPv4 to reach deeper checks
+        let mut frame = packet::parse_hex("ffffffffffff0200000000010800").unwrap();
+        frame.push(0x40 | ihl);
+        frame.push(0);
+        frame.extend((20 + tail.len() as u16).to_be_bytes());
+        frame.extend([0, 0, 0, 0, 64, protocol, 0, 0, 10, 0, 0, 1, 10, 0, 0, 2]);
+        frame.extend(&tail);
+        if let Ok(x) = dissect(&frame) {
+            walk(&x);
+        }
+    }
+
+    #[test]
+    fn fuzz_mutated_captures(index in any::<prop::sample::Index>(),
+                             flips in prop::collection::vec((any::<prop::sample::Index>(), any::<u8>()), 1..8),
+                             cut in any::<prop::sample::Index>()) {
+        let frames = frames("ip-icmp-arp.pcapng");
+        let mut frame = index.get(&frames).clone();
+        for (i, x) in flips {
+            let i = i.index(frame.len());
+            frame[i] ^= x;
+        }
+        let len = cut.index(frame.len() + 1);
+        if let Ok(x) = dissect(&frame[..len]) {
+            walk(&x);
+        }
+    }
+}
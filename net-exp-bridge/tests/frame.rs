@@ -0,0 +1,39 @@
+use net_exp_bridge::{Address, Frame, Segment};
+
+fn frame() -> Frame {
+    Frame {
+        id: 42,
+        src: Address { data: [0x02, 0, 0x1f, 0xa0] },
+        src_seg: Segment { data: [0, 0x0c] },
+        dst: Address { data: [0x01, 0x5e, 0, 0x01] },
+        data: [0x0a, 0xff, 0, 0x7b],
+    }
+}
+
+#[test]
+fn parse_displayed() {
+    let x = frame();
+    let text = x.to_string();
+    assert_eq!(text, "#42 02:00:1f:a0 00:0c 01:5e:00:01 [0a, ff, 00, 7b]");
+    assert_eq!(Frame::try_from(text.as_str()), Ok(x.clone()));
+    assert_eq!(Frame::try_from(format!("  {}\n", text).as_str()), Ok(x));
+}
+
+#[test]
+fn malformed_rejected() {
+    for text in [
+        "",
+        "42 02:00:1f:a0 00:0c 01:5e:00:01 [0a, ff, 00, 7b]",
+        "#42 02:00:1f:a0 00:0c 01:5e:00:01",
+        "#42 02:00:1f:a0 00:0c 01:5e:00:01 0aff007b",
+        "#42 02:00:1f:a0 00:0c 01:5e:00:01 [0a, ff, 00]",
+        "#42 02:00:1f:a0 00:0c 01:5e:00:01 [0a, ff, 00, 7b, 01]",
+        "#42 02:00:1f:a0 00:0c 01:5e:00:01 [0a, ff, 00, 7g]",
+        "#42 02:00:1f:a0 00:0c 01:5e:00:01 [0a, ff, 00, +7]",
+        "#x 02:00:1f:a0 00:0c 01:5e:00:01 [0a, ff, 00, 7b]",
+        "#42 02:00:1f:a0 0:0c 01:5e:00:01 [0a, ff, 00, 7b]",
+        "#42 02:00:1f:a0 00:0c 01:5e:00:0é [0a, ff, 00, 7b]",
+    ] {
+        assert_eq!(Frame::try_from(text), Err(()), "{:?}", text);
+    }
+}
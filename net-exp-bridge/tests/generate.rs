@@ -0,0 +1,44 @@
+mod common;
+
+use std::fs;
+use net_exp_bridge::Frame;
+use common::{run, work_dir};
+
+/// Arguments keeping generation short.
+const SMALL: [&str; 8] = ["--valid-frame", "2000", "--invalid-frame", "100", "--group-frame", "100", "--seed", "3"];
+
+fn generate(name: &str, args: &[&str]) -> std::process::Output {
+    let dir = work_dir(&format!("generate-{}", name));
+    let output = run(&dir, env!("CARGO_BIN_EXE_generate"), &[&SMALL[..], args].concat());
+    fs::remove_dir_all(dir).unwrap();
+    output
+}
+
+#[test]
+fn bad_counts_rejected() {
+    for args in [
+        &["--seg", "0"][..],
+        &["--valid-addr", "0"],
+        &["--valid-addr", "1"],
+        &["--group-member", "0"],
+        &["--invalid-addr", "0"],
+        &["--spoofed-frame", "10", "--spoof-seg", "0"],
+    ] {
+        let output = generate("bad", args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+    }
+    assert!(generate("fewest", &["--valid-addr", "2", "--seg", "1", "--group-member", "1"]).status.success());
+}
+
+#[test]
+fn zipf_with_vanishing_weights_ends() {
+    // the first address takes all the weight, yet frames still go from one address to another
+    let dir = work_dir("generate-zipf");
+    let args = [&SMALL[..], &["--zipf", "1e6", "--valid-addr", "3"]].concat();
+    let output = run(&dir, env!("CARGO_BIN_EXE_generate"), &args);
+    assert!(output.status.success());
+    let frames: Vec<Frame> = rmp_serde::from_slice(&fs::read(dir.join("frame.rmp")).unwrap()).unwrap();
+    assert!(frames.len() >= 2000);
+    assert!(frames.iter().all(|x| x.src != x.dst));
+    fs::remove_dir_all(dir).unwrap();
+}
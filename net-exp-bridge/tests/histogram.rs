@@ -0,0 +1,135 @@
+use proptest::prelude::*;
+use net_exp_bridge::histogram::Histogram;
+
+/// Relative error of values told by a histogram, the width of a bucket over its lowest value.
+const ERROR: f64 = 1.0 / 128.0;
+
+fn histogram(values: impl IntoIterator<Item = u64>) -> Histogram {
+    let mut h = Histogram::new();
+    values.into_iter().for_each(|x| h.record(x));
+    h
+}
+
+/// Highest value of the bucket a value falls into, as the lowest quantile of a histogram holding
+/// it and a larger one.
+fn bucket_top(value: u64) -> u64 {
+    histogram([value, u64::MAX]).quantile(0.0)
+}
+
+#[test]
+fn small_values_exact() {
+    let h = histogram(0..256);
+    for q in [0.0, 0.01, 0.5, 0.9, 0.99, 1.0] {
+        let rank = ((q * 256.0_f64).ceil() as u64).max(1);
+        assert_eq!(h.quantile(q), rank - 1, "quantile {}", q);
+    }
+    assert_eq!((h.min(), h.max(), h.count()), (0, 255, 256));
+    assert_eq!(h.mean(), 127.5);
+}
+
+#[test]
+fn bucket_edges() {
+    // every value under 256 has a bucket of its own, then buckets double in width every power of two
+    assert_eq!(bucket_top(0), 0);
+    assert_eq!(bucket_top(255), 255);
+    assert_eq!(bucket_top(256), 257);
+    assert_eq!(bucket_top(257), 257);
+    assert_eq!(bucket_top(258), 259);
+    assert_eq!(bucket_top(511), 511);
+    assert_eq!(bucket_top(512), 515);
+    assert_eq!(bucket_top(515), 515);
+    assert_eq!(bucket_top(516), 519);
+    assert_eq!(bucket_top(1 << 40), (1 << 40) + (1 << 33) - 1);
+    assert_eq!(bucket_top((1 << 63) - 1), (1 << 63) - 1);
+    assert_eq!(bucket_top(1 << 63), (1 << 63) + (1 << 56) - 1);
+    assert_eq!(bucket_top(u64::MAX - (1 << 56)), u64::MAX - (1 << 56));
+    assert_eq!(bucket_top(u64::MAX - (1 << 56) + 1), u64::MAX);
+}
+
+#[test]
+fn very_large_values() {
+    let h = histogram([u64::MAX, u64::MAX - 1, 1 << 63, 3]);
+    assert_eq!(h.quantile(0.0), 3);
+    assert_eq!(h.quantile(0.5), (1 << 63) + (1 << 56) - 1);
+    assert_eq!(h.quantile(0.75), u64::MAX);
+    assert_eq!(h.quantile(1.0), u64::MAX);
+    assert_eq!(h.max(), u64::MAX);
+    assert_eq!(h.mean(), (u64::MAX as f64 * 2.0 + (1u64 << 63) as f64 + 2.0) / 4.0);
+}
+
+#[test]
+fn uniform_percentiles() {
+    // 1 to 1 000 000, the q-quantile being q * 1 000 000
+    let h = histogram(1..=1_000_000);
+    let x = h.percentiles(1.0);
+    assert_eq!((x.count, x.min, x.max), (1_000_000, 1.0, 1_000_000.0));
+    assert_eq!(x.mean, 500_000.5);
+    for (value, exact) in [(x.p50, 500_000.0), (x.p90, 900_000.0), (x.p99, 990_000.0), (x.p999, 999_000.0)] {
+        assert!(value >= exact && value <= exact * (1.0 + ERROR), "{} against {}", value, exact);
+    }
+    // divided by a unit, as latencies in nanoseconds shown in microseconds
+    let y = h.percentiles(1000.0);
+    assert_eq!((y.p50, y.max), (x.p50 / 1000.0, 1000.0));
+}
+
+#[test]
+fn geometric_percentiles() {
+    // 2^k recorded 2^(20 - k) times, half of the values being 1
+    let h = histogram((0..20).flat_map(|k| std::iter::repeat_n(1u64 << k, 1 << (20 - k))));
+    assert_eq!(h.quantile(0.5), 1);
+    assert_eq!(h.quantile(0.75), 2);
+    assert_eq!(h.quantile(0.9), 8);
+    assert_eq!(h.quantile(0.99), 64);
+    // 512, told as the top of its bucket
+    assert_eq!(h.quantile(0.999), 515);
+    assert_eq!(h.quantile(1.0), 1 << 19);
+}
+
+#[test]
+fn merge_same_as_recording() {
+    let a = histogram((0..5000).map(|x| x * 37));
+    let b = histogram((0..3000).map(|x| x * x));
+    let mut merged = Histogram::new();
+    merged.merge(&Histogram::new());
+    merged.merge(&a);
+    merged.merge(&b);
+    let all = histogram((0..5000).map(|x| x * 37).chain((0..3000).map(|x| x * x)));
+    assert_eq!((merged.count(), merged.min(), merged.max()), (all.count(), all.min(), all.max()));
+    assert_eq!(merged.mean(), all.mean());
+    for q in [0.0, 0.1, 0.5, 0.9, 0.99, 0.999, 1.0] {
+        assert_eq!(merged.quantile(q), all.quantile(q));
+    }
+}
+
+#[test]
+fn empty() {
+    let h = Histogram::new();
+    assert!(h.is_empty());
+    assert_eq!((h.quantile(0.5), h.mean()), (0, 0.0));
+    assert_eq!(h.percentiles(1.0).max, 0.0);
+}
+
+proptest! {
+    #[test]
+    fn relative_error_bounded(value in any::<u64>()) {
+        let top = bucket_top(value);
+        prop_assert!(top >= value);
+        if value < 256 {
+            prop_assert_eq!(top, value);
+        } else {
+            prop_assert!(((top - value) as f64) < value as f64 * ERROR);
+        }
+    }
+
+    #[test]
+    fn quantiles_within_error(values in prop::collection::vec(any::<u64>(), 1..200), q in 0.0..=1.0_f64) {
+        let h = histogram(values.iter().copied());
+        let mut sorted = values.clone();
+        sorted.sort();
+        let rank = ((q * values.len() as f64).ceil() as usize).clamp(1, values.len());
+        let exact = sorted[rank - 1];
+        let x = h.quantile(q);
+        prop_assert!(x >= exact && x as f64 <= exact as f64 * (1.0 + ERROR), "{} against {}", x, exact);
+        prop_assert!(x <= h.max());
+    }
+}
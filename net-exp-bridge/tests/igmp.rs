@@ -0,0 +1,116 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+use net_exp_bridge::{Address, Frame, Segment};
+use net_exp_bridge::igmp::*;
+
+const TIMEOUT: Duration = Duration::from_millis(2000);
+
+fn host(x: u8) -> Address {
+    Address { data: [0x02, 0, 0, x] }
+}
+
+fn seg(x: u8) -> Segment {
+    Segment { data: [0, x] }
+}
+
+fn ms(x: u64) -> Duration {
+    Duration::from_millis(x)
+}
+
+fn segs(x: &[u8]) -> BTreeSet<Segment> {
+    x.iter().map(|x| seg(*x)).collect()
+}
+
+#[test]
+fn messages_round_trip() {
+    for msg in [IgmpMessage::Query, IgmpMessage::Report(group(0x100)), IgmpMessage::Leave(group(0xffff))] {
+        let frame = msg.frame(host(1), seg(1));
+        assert_eq!(IgmpMessage::parse(&frame), Some(msg));
+        assert!(frame.dst.is_group());
+    }
+    assert_eq!(group(0x1234).to_string(), "01:5e:12:34");
+    // a frame to a group that is not IGMP
+    let frame = IgmpMessage::Report(group(0x100)).frame(host(1), seg(1));
+    let multicast = Frame { dst: group(0x100), ..frame };
+    assert_eq!(IgmpMessage::parse(&multicast), None);
+}
+
+#[test]
+fn members_forwarded_to() {
+    let mut table = SnoopingTable::new(TIMEOUT);
+    let g = group(0x100);
+    assert!(table.is_empty());
+    table.handle(IgmpMessage::Report(g), host(1), seg(1), ms(0));
+    table.handle(IgmpMessage::Report(g), host(2), seg(2), ms(0));
+    table.handle(IgmpMessage::Report(g), host(3), seg(2), ms(0));
+    table.handle(IgmpMessage::Report(group(0x101)), host(4), seg(4), ms(0));
+    assert_eq!(table.len(), 2);
+    assert_eq!(table.group_segments(&g), segs(&[1, 2]));
+    assert_eq!(table.group_segments(&group(0x102)), segs(&[]));
+}
+
+#[test]
+fn leave_removes_member() {
+    let mut table = SnoopingTable::new(TIMEOUT);
+    let g = group(0x100);
+    table.handle(IgmpMessage::Report(g), host(1), seg(1), ms(0));
+    table.handle(IgmpMessage::Report(g), host(2), seg(2), ms(0));
+    table.handle(IgmpMessage::Report(g), host(3), seg(2), ms(0));
+    table.handle(IgmpMessage::Leave(g), host(2), seg(2), ms(10));
+    // another member stays on the segment
+    assert_eq!(table.group_segments(&g), segs(&[1, 2]));
+    table.handle(IgmpMessage::Leave(g), host(3), seg(2), ms(10));
+    assert_eq!(table.group_segments(&g), segs(&[1]));
+    // leaving twice or leaving a group never joined does nothing
+    table.handle(IgmpMessage::Leave(g), host(3), seg(2), ms(10));
+    table.handle(IgmpMessage::Leave(group(0x200)), host(1), seg(1), ms(10));
+    assert_eq!(table.group_segments(&g), segs(&[1]));
+    table.handle(IgmpMessage::Leave(g), host(1), seg(1), ms(10));
+    assert!(table.group_segments(&g).is_empty());
+    // the group is dropped once purged empty
+    table.purge(ms(20));
+    assert!(table.is_empty());
+}
+
+#[test]
+fn members_time_out() {
+    let mut table = SnoopingTable::new(TIMEOUT);
+    let g = group(0x100);
+    table.handle(IgmpMessage::Report(g), host(1), seg(1), ms(0));
+    table.handle(IgmpMessage::Report(g), host(2), seg(2), ms(0));
+    // a report refreshes membership
+    table.handle(IgmpMessage::Report(g), host(2), seg(2), ms(1500));
+    table.purge(ms(2000));
+    assert_eq!(table.group_segments(&g), segs(&[1, 2]));
+    table.purge(ms(2001));
+    assert_eq!(table.group_segments(&g), segs(&[2]));
+    table.purge(ms(3500));
+    assert_eq!(table.len(), 1);
+    table.purge(ms(3501));
+    assert!(table.is_empty());
+    assert!(table.group_segments(&g).is_empty());
+}
+
+#[test]
+fn querier_elected_by_lowest_address() {
+    let mut table = SnoopingTable::new(TIMEOUT);
+    let g = group(0x100);
+    assert_eq!(table.querier(), None);
+    table.handle(IgmpMessage::Query, host(9), seg(9), ms(0));
+    assert_eq!(table.querier(), Some((host(9), seg(9))));
+    table.handle(IgmpMessage::Query, host(3), seg(3), ms(500));
+    assert_eq!(table.querier(), Some((host(3), seg(3))));
+    // group frames go towards the querier as well as to members
+    table.handle(IgmpMessage::Report(g), host(1), seg(1), ms(500));
+    assert_eq!(table.router_segments(), segs(&[3]));
+    assert_eq!(table.group_segments(&g), segs(&[1, 3]));
+    assert_eq!(table.group_segments(&group(0x101)), segs(&[3]));
+
+    // the elected querier going silent hands over to the next lowest still querying
+    table.handle(IgmpMessage::Query, host(9), seg(9), ms(2000));
+    table.purge(ms(2501));
+    assert_eq!(table.querier(), Some((host(9), seg(9))));
+    table.purge(ms(4001));
+    assert_eq!(table.querier(), None);
+    assert!(table.router_segments().is_empty());
+}
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+use net_exp_bridge::{Address, Frame, Segment};
+use net_exp_bridge::lacp::*;
+
+const STEP: u64 = 10;
+
+fn addr(x: u32) -> Address {
+    Address { data: x.to_be_bytes() }
+}
+
+fn frame(src: u32, dst: u32) -> Frame {
+    Frame { id: 0, src: addr(src), src_seg: Segment { data: [0, 1] }, dst: addr(dst), data: [0; 4] }
+}
+
+/// Two aggregators of bridges connected by parallel links.
+struct Pair {
+    a: Aggregator,
+    b: Aggregator,
+    now: u64,
+}
+
+impl Pair {
+    fn new(member: usize, policy: HashPolicy) -> Self {
+        let a = Aggregator::new(addr(0x0200_000a), 1, member, policy);
+        let b = Aggregator::new(addr(0x0200_000b), 1, member, policy);
+        Pair { a, b, now: 0 }
+    }
+
+    /// Run for a duration, with LACPDUs going through the links that carry them.
+    fn run(&mut self, millis: u64, carries: impl Fn(usize) -> bool) {
+        let end = self.now + millis;
+        while self.now < end {
+            for (member, pdu) in self.a.tick(self.now) {
+                if carries(member) {
+                    self.b.receive(member, &pdu, self.now);
+                }
+            }
+            for (member, pdu) in self.b.tick(self.now) {
+                if carries(member) {
+                    self.a.receive(member, &pdu, self.now);
+                }
+            }
+            self.now += STEP;
+        }
+    }
+}
+
+#[test]
+fn members_distribute_after_wait() {
+    let mut pair = Pair::new(3, HashPolicy::SrcMac);
+    assert!(pair.a.distributing().is_empty());
+    pair.run(AGGREGATE_WAIT_TIME / 2, |_| true);
+    assert!(pair.a.distributing().is_empty());
+    assert!(pair.a.ports().iter().all(|x| x.mux() == MuxState::Waiting && x.rx() == RxState::Current));
+    pair.run(AGGREGATE_WAIT_TIME, |_| true);
+    assert_eq!(pair.a.distributing(), [0, 1, 2]);
+    assert_eq!(pair.b.distributing(), [0, 1, 2]);
+    let port = &pair.a.ports()[1];
+    assert_eq!(port.partner().map(|x| (x.system, x.port)), Some((addr(0x0200_000b), 2)));
+    assert!(port.actor().state.has(PortState::COLLECTING | PortState::DISTRIBUTING | PortState::SYNCHRONIZATION));
+}
+
+#[test]
+fn member_drops_and_recovers() {
+    let mut pair = Pair::new(3, HashPolicy::SrcMac);
+    pair.run(5000, |_| true);
+    pair.a.set_link(1, false);
+    pair.b.set_link(1, false);
+    pair.run(STEP, |x| x != 1);
+    // a link going down detaches the member at once
+    assert_eq!(pair.a.distributing(), [0, 2]);
+    assert_eq!(pair.a.ports()[1].rx(), RxState::Disabled);
+    assert_eq!(pair.a.ports()[1].mux(), MuxState::Detached);
+    assert!(pair.a.ports()[1].partner().is_none());
+    pair.run(5000, |x| x != 1);
+    assert_eq!(pair.a.distributing(), [0, 2]);
+
+    pair.a.set_link(1, true);
+    pair.b.set_link(1, true);
+    pair.run(AGGREGATE_WAIT_TIME - STEP, |_| true);
+    assert_eq!(pair.a.distributing(), [0, 2]);
+    pair.run(2 * STEP + FAST_PERIODIC_TIME, |_| true);
+    assert_eq!(pair.a.distributing(), [0, 1, 2]);
+    assert_eq!(pair.b.distributing(), [0, 1, 2]);
+}
+
+#[test]
+fn silent_member_times_out() {
+    let mut pair = Pair::new(2, HashPolicy::SrcMac);
+    pair.run(5000, |_| true);
+    // the link stays up but carries nothing, so only the partner going silent tells
+    pair.run(FAST_PERIODIC_TIME, |x| x != 0);
+    assert_eq!(pair.a.distributing(), [0, 1]);
+    pair.run(SHORT_TIMEOUT_TIME, |x| x != 0);
+    assert_eq!(pair.a.ports()[0].rx(), RxState::Expired);
+    assert!(pair.a.ports()[0].actor().state.has(PortState::EXPIRED));
+    assert_eq!(pair.a.distributing(), [1]);
+    pair.run(SHORT_TIMEOUT_TIME, |x| x != 0);
+    assert_eq!(pair.a.ports()[0].rx(), RxState::Defaulted);
+    assert!(pair.a.ports()[0].actor().state.has(PortState::DEFAULTED));
+    assert_eq!(pair.a.ports()[0].mux(), MuxState::Detached);
+    assert_eq!(pair.a.distributing(), [1]);
+
+    pair.run(AGGREGATE_WAIT_TIME + 2 * FAST_PERIODIC_TIME, |_| true);
+    assert_eq!(pair.a.distributing(), [0, 1]);
+    assert_eq!(pair.a.ports()[0].rx(), RxState::Current);
+}
+
+#[test]
+fn no_member_without_partner() {
+    let mut pair = Pair::new(2, HashPolicy::DstMac);
+    pair.run(10_000, |_| false);
+    assert!(pair.a.distributing().is_empty());
+    assert_eq!(pair.a.select(&frame(1, 2)), None);
+}
+
+#[test]
+fn hash_folds_addresses() {
+    let x = frame(0x0102_0304, 0x0a0b_0c0d);
+    // 0x01020304 ^ 0x0102 = 0x01020206, then ^ 0x010202 = 0x01030004
+    assert_eq!(HashPolicy::SrcMac.hash(&x), 0x0103_0004);
+    assert_eq!(HashPolicy::DstMac.hash(&x), 0x0a01_0d00);
+    assert_eq!(HashPolicy::SrcDstHash.hash(&x), HashPolicy::SrcDstHash.hash(&frame(0x0a0b_0c0d, 0x0102_0304)));
+}
+
+/// Members frames from sources to destinations go through.
+fn members(pair: &Pair, frames: impl Iterator<Item = Frame>) -> Vec<usize> {
+    frames.map(|x| pair.a.select(&x).unwrap()).collect()
+}
+
+#[test]
+fn policies_choose_members() {
+    let mut src_mac = Pair::new(4, HashPolicy::SrcMac);
+    let mut dst_mac = Pair::new(4, HashPolicy::DstMac);
+    let mut src_dst = Pair::new(4, HashPolicy::SrcDstHash);
+    for pair in [&mut src_mac, &mut dst_mac, &mut src_dst] {
+        pair.run(5000, |_| true);
+    }
+    let from_one = || (0..64).map(|x| frame(0x0200_0001, 0x0200_0100 + x));
+    let to_one = || (0..64).map(|x| frame(0x0200_0100 + x, 0x0200_0001));
+
+    // frames of a source keep to one member under the source policy, and spread otherwise
+    assert_eq!(members(&src_mac, from_one()).iter().collect::<HashSet<_>>().len(), 1);
+    assert_eq!(members(&src_mac, to_one()).iter().collect::<HashSet<_>>().len(), 4);
+    assert_eq!(members(&dst_mac, to_one()).iter().collect::<HashSet<_>>().len(), 1);
+    assert_eq!(members(&dst_mac, from_one()).iter().collect::<HashSet<_>>().len(), 4);
+    assert_eq!(members(&src_dst, from_one()).iter().collect::<HashSet<_>>().len(), 4);
+    assert_eq!(members(&src_dst, to_one()).iter().collect::<HashSet<_>>().len(), 4);
+
+    // a conversation takes one member both ways under the source and destination policy
+    for x in from_one() {
+        let back = frame(u32::from_be_bytes(x.dst.data), u32::from_be_bytes(x.src.data));
+        assert_eq!(src_dst.a.select(&x), src_dst.a.select(&back));
+    }
+}
+
+#[test]
+fn policy_spreads_over_remaining_members() {
+    let mut pair = Pair::new(4, HashPolicy::SrcDstHash);
+    pair.run(5000, |_| true);
+    pair.a.set_link(2, false);
+    pair.b.set_link(2, false);
+    pair.run(STEP, |x| x != 2);
+    let used = members(&pair, (0..256).map(|x| frame(0x0200_0000 + x, 0x0200_1000 + x * 7)));
+    assert!(!used.contains(&2));
+    assert_eq!(used.iter().collect::<HashSet<_>>().len(), 3);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use net_exp_bridge::live::{LiveMetrics, LATENCY_BOUNDS};
+use net_exp_bridge::openmetrics::{render, serve};
+use common::{run, work_dir};
+
+fn metrics() -> LiveMetrics {
+    let metrics = LiveMetrics::new();
+    metrics.sent.store(120, Ordering::Relaxed);
+    metrics.dispatches.store(100, Ordering::Relaxed);
+    metrics.table.store(42, Ordering::Relaxed);
+    metrics.event_depth.store(3, Ordering::Relaxed);
+    metrics.latency[0].observe(Duration::from_micros(1));
+    metrics.latency[0].observe(Duration::from_micros(3));
+    metrics.latency[0].observe(Duration::from_secs(1));
+    metrics.latency[2].observe(Duration::from_millis(2));
+    metrics
+}
+
+#[test]
+fn render_format() {
+    let body = render(&metrics());
+    let lines = body.lines().collect::<Vec<_>>();
+    // every family declares its type and help before its samples
+    let at = |x: &str| lines.iter().position(|y| *y == x).unwrap_or_else(|| panic!("no line {:?}", x));
+    assert!(at("# TYPE orchestrator_frames counter") < at("# HELP orchestrator_frames Frames sent to the bridge."));
+    assert!(at("# HELP orchestrator_frames Frames sent to the bridge.") < at("orchestrator_frames_total 120"));
+    at("bridge_dispatches_total 100");
+    at("bridge_discards_total 0");
+    at("# TYPE bridge_table_size gauge");
+    at("bridge_table_size 42");
+    at("channel_depth{channel=\"event\"} 3");
+    at("channel_depth{channel=\"command\"} 0");
+    at("# TYPE bridge_latency_seconds histogram");
+
+    // buckets are cumulative and end with +Inf, equal to the count
+    at("bridge_latency_seconds_bucket{outcome=\"direct\",le=\"0.000001\"} 1");
+    at("bridge_latency_seconds_bucket{outcome=\"direct\",le=\"0.0000025\"} 1");
+    at("bridge_latency_seconds_bucket{outcome=\"direct\",le=\"0.000005\"} 2");
+    at("bridge_latency_seconds_bucket{outcome=\"direct\",le=\"0.05\"} 2");
+    at("bridge_latency_seconds_bucket{outcome=\"direct\",le=\"+Inf\"} 3");
+    at("bridge_latency_seconds_count{outcome=\"direct\"} 3");
+    at("bridge_latency_seconds_sum{outcome=\"direct\"} 1.000004");
+    at("bridge_latency_seconds_bucket{outcome=\"held\",le=\"+Inf\"} 0");
+    at("bridge_latency_seconds_bucket{outcome=\"discarded\",le=\"0.001\"} 0");
+    at("bridge_latency_seconds_bucket{outcome=\"discarded\",le=\"0.0025\"} 1");
+    assert_eq!(lines.iter().filter(|x| x.starts_with("bridge_latency_seconds_bucket")).count(),
+               3 * (LATENCY_BOUNDS.len() + 1));
+
+    // one family per name, and nothing after the end
+    let families = lines.iter().filter(|x| x.starts_with("# TYPE")).count();
+    assert_eq!(families, 7 + 2 + 1 + 1);
+    assert_eq!(lines.last(), Some(&"# EOF"));
+    assert!(body.ends_with("# EOF\n"));
+}
+
+fn get(addr: SocketAddr, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn serve_with_idle_client() {
+    let addr = serve(Arc::new(metrics()), 0).unwrap();
+    // a client that connects and sends nothing holds up no other
+    let _idle = TcpStream::connect(addr).unwrap();
+    let begin = Instant::now();
+    let response = get(addr, "/metrics");
+    assert!(begin.elapsed() < Duration::from_secs(2));
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Type: application/openmetrics-text; version=1.0.0; charset=utf-8\r\n"));
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.contains(&format!("Content-Length: {}", body.len())));
+    assert!(body.contains("orchestrator_frames_total 120\n"));
+    assert!(get(addr, "/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
+
+#[test]
+fn taken_port_reported() {
+    let dir = work_dir("metrics-taken");
+    let output = run(&dir, env!("CARGO_BIN_EXE_generate"), &["--seed", "1", "--valid-frame", "100", "--invalid-frame", "0"]);
+    assert!(output.status.success());
+    let taken = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let port = taken.local_addr().unwrap().port().to_string();
+    let output = run(&dir, env!("CARGO_BIN_EXE_simulate"), &["--deterministic", "--no-plot", "--metrics-port", &port]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Cannot serve metrics"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn latency_restored() {
+    let (metrics, restored) = (metrics(), metrics());
+    for x in restored.latency.iter() {
+        x.restore(Default::default());
+    }
+    assert!(render(&restored).contains("bridge_latency_seconds_bucket{outcome=\"direct\",le=\"+Inf\"} 0"));
+    for (x, y) in restored.latency.iter().zip(metrics.latency.iter()) {
+        x.restore(y.counts());
+    }
+    assert_eq!(render(&restored), render(&metrics));
+}
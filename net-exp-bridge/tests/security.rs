@@ -0,0 +1,103 @@
+use net_exp_bridge::{Address, Segment};
+use net_exp_bridge::security::*;
+
+fn addr(x: u8) -> Address {
+    Address { data: [0x02, 0, 0, x] }
+}
+
+fn seg(x: u8) -> Segment {
+    Segment { data: [0, x] }
+}
+
+fn security(max_addr: usize, sticky: bool, action: ViolationAction) -> PortSecurity {
+    PortSecurity::new(PortSecurityConfig { max_addr, sticky, action })
+}
+
+#[test]
+fn learns_up_to_limit() {
+    let mut sec = security(2, false, ViolationAction::Protect);
+    assert_eq!(sec.admit(addr(1), seg(1)), Verdict::Admit);
+    assert_eq!(sec.admit(addr(2), seg(1)), Verdict::Admit);
+    // addresses already secure go on, however full the segment is
+    assert_eq!(sec.admit(addr(1), seg(1)), Verdict::Admit);
+    assert_eq!(sec.admit(addr(3), seg(1)), Verdict::Drop);
+    // the limit is per segment
+    assert_eq!(sec.admit(addr(3), seg(2)), Verdict::Admit);
+    let port = &sec.ports()[&seg(1)];
+    assert_eq!(port.secure.iter().copied().collect::<Vec<_>>(), [addr(1), addr(2)]);
+    assert_eq!((port.dropped, port.violations), (1, 0));
+}
+
+#[test]
+fn address_secure_on_one_segment() {
+    let mut sec = security(10, false, ViolationAction::Restrict);
+    assert_eq!(sec.admit(addr(1), seg(1)), Verdict::Admit);
+    // moving to another segment violates, even with room there
+    assert_eq!(sec.admit(addr(1), seg(2)), Verdict::Drop);
+    assert_eq!(sec.admit(addr(1), seg(1)), Verdict::Admit);
+    assert!(sec.ports()[&seg(2)].secure.is_empty());
+    assert_eq!((sec.dropped(), sec.violations()), (1, 1));
+}
+
+#[test]
+fn protect_drops_silently() {
+    let mut sec = security(1, false, ViolationAction::Protect);
+    sec.admit(addr(1), seg(1));
+    for x in 2..5 {
+        assert_eq!(sec.admit(addr(x), seg(1)), Verdict::Drop);
+    }
+    assert_eq!(sec.admit(addr(1), seg(1)), Verdict::Admit);
+    assert_eq!((sec.dropped(), sec.violations(), sec.shutdowns()), (3, 0, 0));
+}
+
+#[test]
+fn restrict_counts_violations() {
+    let mut sec = security(1, false, ViolationAction::Restrict);
+    sec.admit(addr(1), seg(1));
+    for x in 2..5 {
+        assert_eq!(sec.admit(addr(x), seg(1)), Verdict::Drop);
+    }
+    assert_eq!(sec.admit(addr(1), seg(1)), Verdict::Admit);
+    assert_eq!((sec.dropped(), sec.violations(), sec.shutdowns()), (3, 3, 0));
+}
+
+#[test]
+fn shutdown_disables_segment() {
+    let mut sec = security(1, false, ViolationAction::Shutdown);
+    sec.admit(addr(1), seg(1));
+    sec.admit(addr(5), seg(2));
+    assert_eq!(sec.admit(addr(2), seg(1)), Verdict::Shutdown);
+    // everything is dropped afterwards, secure addresses too, without further violations
+    assert_eq!(sec.admit(addr(1), seg(1)), Verdict::Drop);
+    assert_eq!(sec.admit(addr(2), seg(1)), Verdict::Drop);
+    assert_eq!(sec.admit(addr(5), seg(2)), Verdict::Admit);
+    let port = &sec.ports()[&seg(1)];
+    assert!(port.shutdown);
+    assert_eq!((port.dropped, port.violations), (3, 1));
+    assert_eq!(sec.shutdowns(), 1);
+}
+
+#[test]
+fn sticky_kept_across_runs() {
+    let mut sec = security(1, true, ViolationAction::Restrict);
+    sec.admit(addr(1), seg(1));
+    sec.admit(addr(2), seg(2));
+    sec.admit(addr(3), seg(2));
+    let sticky = sec.sticky();
+    assert_eq!(sticky, [(addr(1), seg(1)), (addr(2), seg(2))]);
+
+    // the next run knows the addresses before any frame
+    let mut next = security(1, true, ViolationAction::Restrict);
+    next.load_sticky(&sticky);
+    assert_eq!(next.admit(addr(3), seg(2)), Verdict::Drop);
+    assert_eq!(next.admit(addr(2), seg(1)), Verdict::Drop);
+    assert_eq!(next.admit(addr(2), seg(2)), Verdict::Admit);
+    assert_eq!(next.violations(), 2);
+}
+
+#[test]
+fn nothing_sticky_unless_configured() {
+    let mut sec = security(1, false, ViolationAction::Protect);
+    sec.admit(addr(1), seg(1));
+    assert!(sec.sticky().is_empty());
+}
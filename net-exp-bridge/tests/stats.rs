@@ -0,0 +1,98 @@
+use net_exp_bridge::stats::*;
+
+fn close(x: f64, y: f64, tol: f64) -> bool {
+    (x - y).abs() <= tol * y.abs().max(1.0)
+}
+
+/// Two groups of samples with (t, df, p) of their test.
+type Case = (&'static [f64], &'static [f64], (f64, f64, f64));
+
+/// Examples of Welch's t-test on Wikipedia, with values of `scipy.stats.ttest_ind(a, b,
+/// equal_var=False)` as (t, df, p).
+const WELCH: [Case; 3] = [
+    (&[27.5, 21.0, 19.0, 23.6, 17.0, 17.9, 16.9, 20.1, 21.9, 22.6, 23.1, 19.6, 19.0, 21.7, 21.4],
+     &[27.1, 22.0, 20.8, 23.4, 23.4, 23.5, 25.8, 22.0, 24.8, 20.2, 21.9, 22.1, 22.9, 20.5, 24.4],
+     (-2.455356398286, 24.9885292902314, 0.0213780014628671)),
+    (&[17.2, 20.9, 22.6, 18.1, 21.7, 21.4, 23.5, 24.2, 14.7, 21.8],
+     &[21.5, 22.8, 21.0, 23.0, 21.6, 23.6, 22.5, 20.7, 23.4, 21.8, 20.7, 21.7, 21.5, 22.5, 23.6, 21.5, 22.5, 23.5, 21.5, 21.8],
+     (-1.56543352359851, 9.90474124865083, 0.148841696605328)),
+    (&[19.8, 20.4, 19.6, 17.8, 18.5, 18.9, 18.3, 18.9, 19.5, 22.0],
+     &[28.2, 26.6, 20.1, 23.3, 25.2, 22.1, 17.7, 27.6, 20.6, 13.7, 23.2, 17.5, 20.6, 18.0, 23.9, 21.6, 24.3, 20.4, 23.9, 13.3],
+     (-2.22551203996985, 24.5246349442573, 0.0354845308300102)),
+];
+
+#[test]
+fn welch_matches_scipy() {
+    for (a, b, (t, df, p)) in WELCH {
+        let x = welch_t_test(a, b).unwrap();
+        assert!(close(x.t, t, 1e-10), "t {} against {}", x.t, t);
+        assert!(close(x.df, df, 1e-10), "df {} against {}", x.df, df);
+        assert!(close(x.p, p, 1e-8), "p {} against {}", x.p, p);
+        // the test is symmetric
+        let y = welch_t_test(b, a).unwrap();
+        assert!(close(y.t, -t, 1e-10) && close(y.p, x.p, 1e-12));
+    }
+}
+
+#[test]
+fn welch_extreme_p() {
+    // few samples and a small difference
+    let x = welch_t_test(&[1.0, 2.0], &[1.5, 2.5, 3.0]).unwrap();
+    assert!(close(x.t, -1.25, 1e-12));
+    assert!(close(x.df, 2.42654028436019, 1e-10));
+    assert!(close(x.p, 0.318310943220256, 1e-8));
+    // far apart, the p-value stays accurate rather than rounding to zero
+    let x = welch_t_test(&[100.0, 101.0, 102.0, 103.0], &[1.0, 2.0, 3.0, 4.0]).unwrap();
+    assert!(close(x.df, 6.0, 1e-12));
+    assert!(close(x.p, 4.14350079631137e-11, 1e-6), "p {}", x.p);
+}
+
+#[test]
+fn welch_without_variance() {
+    // se is zero: identical constants are the same, different constants certainly differ
+    let x = welch_t_test(&[3.0, 3.0, 3.0], &[3.0, 3.0]).unwrap();
+    assert_eq!((x.t, x.df, x.p), (0.0, 3.0, 1.0));
+    let x = welch_t_test(&[3.0, 3.0, 3.0], &[4.0, 4.0]).unwrap();
+    assert_eq!(x.p, 0.0);
+    // a constant against samples that vary is tested as usual
+    let x = welch_t_test(&[3.0, 3.0, 3.0], &[2.0, 4.0, 3.5]).unwrap();
+    assert!(x.p > 0.0 && x.p < 1.0);
+}
+
+#[test]
+fn welch_needs_two_samples() {
+    assert!(welch_t_test(&[1.0], &[1.0, 2.0]).is_none());
+    assert!(welch_t_test(&[1.0, 2.0], &[]).is_none());
+}
+
+#[test]
+fn mean_and_variance() {
+    assert_eq!(mean(&[]), 0.0);
+    assert_eq!(mean(&[1.0, 2.0, 6.0]), 3.0);
+    assert_eq!(variance(&[5.0]), 0.0);
+    assert_eq!(variance(&[1.0, 2.0, 6.0]), 7.0);
+}
+
+#[test]
+fn ln_gamma_known() {
+    // values of mpmath.loggamma
+    for (x, y) in [(0.5, 0.5723649429247001), (1.0, 0.0), (2.0, 0.0), (3.5, 1.2009736023470742),
+                   (10.0, 12.80182748008147), (100.5, 361.4355404677776)] {
+        assert!((ln_gamma(x) - y).abs() < 1e-9, "ln_gamma({}) = {} against {}", x, ln_gamma(x), y);
+    }
+}
+
+#[test]
+fn incomplete_beta_known() {
+    // values of mpmath.betainc(a, b, 0, x, regularized=True)
+    for (a, b, x, y) in [(0.5, 0.5, 0.3, 0.3690101195655454), (2.0, 3.0, 0.4, 0.5248), (10.0, 0.5, 0.9, 0.15164090963471),
+                         (50.0, 0.5, 0.99, 0.3173043978741974), (0.5, 0.5, 0.999, 0.9798649583666225)] {
+        let z = incomplete_beta(a, b, x);
+        assert!(close(z, y, 1e-9), "I_{}({}, {}) = {} against {}", x, a, b, z, y);
+    }
+    // I_x(a, 1) = x^a, and the bounds
+    assert!(close(incomplete_beta(3.0, 1.0, 0.7), 0.343, 1e-10));
+    assert_eq!(incomplete_beta(2.0, 2.0, 0.0), 0.0);
+    assert_eq!(incomplete_beta(2.0, 2.0, 1.0), 1.0);
+    assert!(close(incomplete_beta(7.0, 7.0, 0.5), 0.5, 1e-9));
+}
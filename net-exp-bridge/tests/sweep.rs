@@ -0,0 +1,13 @@
+mod common;
+
+use std::fs;
+use common::{run, work_dir};
+
+#[test]
+fn no_jobs_rejected() {
+    let dir = work_dir("sweep-jobs");
+    let output = run(&dir, env!("CARGO_BIN_EXE_sweep"), &["--deterministic", "--jobs", "0"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(fs::read_dir(&dir).unwrap().next().is_none(), "runs started without jobs");
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use net_exp_bridge::trace::*;
+
+fn trace_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("net-exp-bridge-trace-{}-{}.rmp", name, std::process::id()))
+}
+
+/// Trace some transitions of frames to a file.
+fn write_trace(name: &str) -> (PathBuf, Vec<TraceRecord>) {
+    let path = trace_file(name);
+    let mut tracer = Tracer::new(Some(&path)).unwrap();
+    let records = (0..100)
+        .map(|x| TraceRecord { id: x / 3, state: FrameState::Arrived, time: Duration::from_micros(x * 250) })
+        .collect::<Vec<_>>();
+    for x in &records {
+        tracer.rec(x.id, x.state, x.time);
+    }
+    tracer.finish().unwrap();
+    (path, records)
+}
+
+#[test]
+fn read_back() {
+    let (path, records) = write_trace("read-back");
+    let read = read_trace(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(read, records);
+    fs::write(&path, []).unwrap();
+    assert_eq!(read_trace(&path).unwrap().count(), 0);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn truncated_trace_fails() {
+    let (path, records) = write_trace("truncated");
+    let data = fs::read(&path).unwrap();
+    fs::write(&path, &data[..data.len() - 3]).unwrap();
+    let read = read_trace(&path).unwrap().collect::<Vec<_>>();
+    // every record before the one cut short, then an error and nothing after
+    assert_eq!(read.len(), records.len());
+    assert!(read[..records.len() - 1].iter().zip(&records).all(|(x, y)| x.as_ref().unwrap() == y));
+    assert!(read.last().unwrap().is_err());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn corrupt_trace_fails() {
+    let (path, _) = write_trace("corrupt");
+    let mut data = fs::read(&path).unwrap();
+    let middle = data.len() / 2;
+    data[middle..middle + 8].fill(0xc1);
+    fs::write(&path, &data).unwrap();
+    let read = read_trace(&path).unwrap().collect::<Vec<_>>();
+    assert!(read.len() < 100);
+    assert!(read.last().unwrap().is_err());
+    assert!(read[..read.len() - 1].iter().all(|x| x.is_ok()));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn resume_cuts_and_continues() {
+    let (path, records) = write_trace("resume");
+    let len = fs::metadata(&path).unwrap().len();
+    let mut data = fs::read(&path).unwrap();
+    data.extend([0xc0; 16]);
+    fs::write(&path, data).unwrap();
+    let mut tracer = Tracer::resume(Some(&path), len).unwrap();
+    let last = TraceRecord { id: 100, state: FrameState::Arrived, time: Duration::from_secs(1) };
+    tracer.rec(last.id, last.state, last.time);
+    tracer.finish().unwrap();
+    let read = read_trace(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(read[..records.len()], records);
+    assert_eq!(read[records.len()..], [last]);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn resume_of_lost_trace_fails() {
+    let (path, _) = write_trace("resume-lost");
+    let len = fs::metadata(&path).unwrap().len();
+    let data = fs::read(&path).unwrap();
+    fs::write(&path, &data[..data.len() / 2]).unwrap();
+    assert_eq!(Tracer::resume(Some(&path), len).err().unwrap().kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(fs::metadata(&path).unwrap().len(), data.len() as u64 / 2);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(Tracer::resume(Some(&path), len).err().unwrap().kind(), std::io::ErrorKind::NotFound);
+    assert!(!path.exists());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn full_disk_kept_for_finish() {
+    let mut tracer = Tracer::new(Some("/dev/full".as_ref())).unwrap();
+    for x in 0..10000 {
+        tracer.rec(x, FrameState::Arrived, Duration::from_micros(x));
+    }
+    assert!(tracer.written().is_err());
+    assert!(tracer.finish().is_err());
+}
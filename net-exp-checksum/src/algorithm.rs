@@ -0,0 +1,348 @@
+//! Checksums and CRCs compared with the Internet checksum, behind a common trait.
+
+use std::fmt::{Display, Formatter};
+use std::io::{self, Read};
+use crate::{read_blocks, InternetChecksum, Kernel};
+
+/// Incremental hasher of a checksum or CRC.
+pub trait Checksum {
+    /// Width of checksums, in bits.
+    fn width(&self) -> u32;
+
+    /// Add data following what is added before.
+    fn update(&mut self, data: &[u8]);
+
+    /// Checksum of data added so far.
+    fn checksum(&self) -> u64;
+
+    /// Add all data of a reader, read in large blocks, returning the count of bytes read.
+    fn read_from(&mut self, r: &mut dyn Read) -> io::Result<u64> {
+        read_blocks(r, |x| self.update(x))
+    }
+}
+
+impl Checksum for InternetChecksum {
+    fn width(&self) -> u32 {
+        16
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        InternetChecksum::update(self, data)
+    }
+
+    fn checksum(&self) -> u64 {
+        self.finish() as u64
+    }
+}
+
+/// Table of a CRC of 32 bits, reflected, for a polynomial in reversed bit order.
+const fn crc32_table(poly: u32) -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ poly } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Table of a CRC of 16 bits, not reflected, for a polynomial in normal bit order.
+const fn crc16_table(poly: u16) -> [u16; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ poly } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+static CRC32_IEEE: [u32; 256] = crc32_table(0xedb8_8320);
+static CRC32_CASTAGNOLI: [u32; 256] = crc32_table(0x82f6_3b78);
+static CRC16_CCITT: [u16; 256] = crc16_table(0x1021);
+
+/// CRC of 32 bits, reflected, starting from and finishing with all ones.
+#[derive(Debug, Copy, Clone)]
+pub struct Crc32 {
+    crc: u32,
+    table: &'static [u32; 256],
+}
+
+impl Crc32 {
+    /// CRC-32 of IEEE 802.3, as in the frame check sequence of Ethernet.
+    pub fn ieee() -> Self {
+        Crc32 { crc: !0, table: &CRC32_IEEE }
+    }
+
+    /// CRC-32C of Castagnoli, as in iSCSI and SCTP.
+    pub fn castagnoli() -> Self {
+        Crc32 { crc: !0, table: &CRC32_CASTAGNOLI }
+    }
+}
+
+impl Checksum for Crc32 {
+    fn width(&self) -> u32 {
+        32
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for x in data {
+            self.crc = (self.crc >> 8) ^ self.table[((self.crc ^ *x as u32) & 0xff) as usize];
+        }
+    }
+
+    fn checksum(&self) -> u64 {
+        !self.crc as u64
+    }
+}
+
+/// CRC-16-CCITT as commonly implemented, with polynomial `0x1021` starting from all ones, not
+/// reflected and not finished with a xor, also known as CRC-16/CCITT-FALSE.
+#[derive(Debug, Copy, Clone)]
+pub struct Crc16Ccitt {
+    crc: u16,
+}
+
+impl Crc16Ccitt {
+    pub fn new() -> Self {
+        Crc16Ccitt { crc: !0 }
+    }
+}
+
+impl Default for Crc16Ccitt {
+    fn default() -> Self {
+        Crc16Ccitt::new()
+    }
+}
+
+impl Checksum for Crc16Ccitt {
+    fn width(&self) -> u32 {
+        16
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for x in data {
+            self.crc = (self.crc << 8) ^ CRC16_CCITT[((self.crc >> 8) ^ *x as u16) as usize];
+        }
+    }
+
+    fn checksum(&self) -> u64 {
+        self.crc as u64
+    }
+}
+
+/// Fletcher-16, two sums modulo 255 of bytes and of the first sums.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Fletcher16 {
+    sum1: u32,
+    sum2: u32,
+}
+
+impl Fletcher16 {
+    pub fn new() -> Self {
+        Fletcher16::default()
+    }
+}
+
+impl Checksum for Fletcher16 {
+    fn width(&self) -> u32 {
+        16
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        // sums stay within 32 bits for this many bytes before they are reduced
+        for block in data.chunks(5802) {
+            for x in block {
+                self.sum1 += *x as u32;
+                self.sum2 += self.sum1;
+            }
+            self.sum1 %= 255;
+            self.sum2 %= 255;
+        }
+    }
+
+    fn checksum(&self) -> u64 {
+        (self.sum2 << 8 | self.sum1) as u64
+    }
+}
+
+/// Fletcher-32, two sums modulo 65535 of little-endian 16-bit words and of the first sums, padded
+/// with a zero byte if the length is odd.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Fletcher32 {
+    sum1: u64,
+    sum2: u64,
+    /// Byte of a word split between updates, the low half of the word.
+    odd: Option<u8>,
+}
+
+impl Fletcher32 {
+    pub fn new() -> Self {
+        Fletcher32::default()
+    }
+
+    fn add(&mut self, word: u16) {
+        self.sum1 += word as u64;
+        self.sum2 += self.sum1;
+    }
+}
+
+impl Checksum for Fletcher32 {
+    fn width(&self) -> u32 {
+        32
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        if let Some(low) = self.odd.take() {
+            self.add(u16::from_le_bytes([low, data[0]]));
+            data = &data[1..];
+        }
+        let mut words = data.chunks_exact(2);
+        for (i, word) in (&mut words).enumerate() {
+            self.add(u16::from_le_bytes([word[0], word[1]]));
+            if i % 4096 == 4095 {
+                self.sum1 %= 65535;
+                self.sum2 %= 65535;
+            }
+        }
+        self.sum1 %= 65535;
+        self.sum2 %= 65535;
+        if let [low] = words.remainder() {
+            self.odd = Some(*low);
+        }
+    }
+
+    fn checksum(&self) -> u64 {
+        let mut x = *self;
+        if let Some(low) = x.odd.take() {
+            x.add(low as u16);
+        }
+        ((x.sum2 % 65535) << 16) | (x.sum1 % 65535)
+    }
+}
+
+/// Largest prime below 2^16, the modulus of Adler-32.
+const ADLER_MOD: u32 = 65521;
+
+/// Adler-32 of zlib, Fletcher's sums of bytes modulo a prime, the first starting from one.
+#[derive(Debug, Copy, Clone)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    pub fn new() -> Self {
+        Adler32 { a: 1, b: 0 }
+    }
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Adler32::new()
+    }
+}
+
+impl Checksum for Adler32 {
+    fn width(&self) -> u32 {
+        32
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        // sums stay within 32 bits for this many bytes before they are reduced, as in zlib
+        for block in data.chunks(5552) {
+            for x in block {
+                self.a += *x as u32;
+                self.b += self.a;
+            }
+            self.a %= ADLER_MOD;
+            self.b %= ADLER_MOD;
+        }
+    }
+
+    fn checksum(&self) -> u64 {
+        (self.b << 16 | self.a) as u64
+    }
+}
+
+/// Algorithm of checksums.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Algorithm {
+    /// Internet checksum of RFC 1071
+    Internet,
+    /// CRC-32 of IEEE 802.3, as in the Ethernet FCS
+    Crc32,
+    /// CRC-32C of Castagnoli
+    Crc32c,
+    /// CRC-16-CCITT, starting from all ones
+    Crc16Ccitt,
+    /// Fletcher-16
+    Fletcher16,
+    /// Fletcher-32, of little-endian words
+    Fletcher32,
+    /// Adler-32 of zlib
+    Adler32,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 7] = [
+        Algorithm::Internet, Algorithm::Crc32, Algorithm::Crc32c, Algorithm::Crc16Ccitt,
+        Algorithm::Fletcher16, Algorithm::Fletcher32, Algorithm::Adler32,
+    ];
+
+    /// New hasher of the algorithm, summing with a kernel if it is the Internet checksum.
+    pub fn hasher(&self, kernel: Kernel) -> Box<dyn Checksum + Send> {
+        match self {
+            Algorithm::Internet => Box::new(InternetChecksum::with_kernel(kernel)),
+            Algorithm::Crc32 => Box::new(Crc32::ieee()),
+            Algorithm::Crc32c => Box::new(Crc32::castagnoli()),
+            Algorithm::Crc16Ccitt => Box::new(Crc16Ccitt::new()),
+            Algorithm::Fletcher16 => Box::new(Fletcher16::new()),
+            Algorithm::Fletcher32 => Box::new(Fletcher32::new()),
+            Algorithm::Adler32 => Box::new(Adler32::new()),
+        }
+    }
+
+    /// Checksum of data.
+    pub fn checksum(&self, data: &[u8]) -> u64 {
+        let mut hasher = self.hasher(Kernel::default());
+        hasher.update(data);
+        hasher.checksum()
+    }
+
+    /// Width of checksums, in bits.
+    pub fn width(&self) -> u32 {
+        self.hasher(Kernel::Scalar).width()
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Algorithm::Internet => "internet",
+            Algorithm::Crc32 => "crc32",
+            Algorithm::Crc32c => "crc32c",
+            Algorithm::Crc16Ccitt => "crc16-ccitt",
+            Algorithm::Fletcher16 => "fletcher16",
+            Algorithm::Fletcher32 => "fletcher32",
+            Algorithm::Adler32 => "adler32",
+        };
+        f.write_str(name)
+    }
+}
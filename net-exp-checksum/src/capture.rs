@@ -0,0 +1,177 @@
+//! Reader of packets captured in pcap and pcapng files, borrowing packet data from the file.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+pub const LINKTYPE_ETHERNET: u32 = 1;
+pub const LINKTYPE_RAW: u32 = 101;
+pub const LINKTYPE_LINUX_SLL: u32 = 113;
+pub const LINKTYPE_IPV4: u32 = 228;
+pub const LINKTYPE_IPV6: u32 = 229;
+
+const PCAP_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_NANOS: u32 = 0xa1b2_3c4d;
+const PCAPNG_SECTION: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER: u32 = 0x1a2b_3c4d;
+const PCAPNG_INTERFACE: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+
+/// Packet captured.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Packet<'a> {
+    /// Link-layer header type of the packet, one of the `LINKTYPE_` values.
+    pub link_type: u32,
+    /// Bytes captured, fewer than the packet if it is cut at the snapshot length.
+    pub data: &'a [u8],
+    /// Length of the packet on the wire.
+    pub orig_len: u32,
+}
+
+impl Packet<'_> {
+    /// Offset of the IP header in the packet, if the packet carries IPv4 or IPv6 over a link
+    /// type known.
+    pub fn ip_offset(&self) -> Option<usize> {
+        let ether_type = |offset: usize| self.data.get(offset..offset + 2).map(|x| u16::from_be_bytes([x[0], x[1]]));
+        let is_ip = |x| x == 0x0800 || x == 0x86dd;
+        match self.link_type {
+            LINKTYPE_ETHERNET => match ether_type(12)? {
+                // a single 802.1Q tag
+                0x8100 => is_ip(ether_type(16)?).then_some(18),
+                x => is_ip(x).then_some(14),
+            },
+            LINKTYPE_LINUX_SLL => is_ip(ether_type(14)?).then_some(16),
+            LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Some(0),
+            _ => None,
+        }
+    }
+}
+
+/// Error of a capture file that cannot be read.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CaptureError {
+    /// File starting with neither the magic of pcap nor a pcapng section header.
+    Magic(u32),
+    /// File ending within a header, block or packet starting at an offset.
+    Truncated(usize),
+    /// Block at an offset with a length out of range.
+    BlockLength { offset: usize, len: usize },
+    /// Packet block at an offset referring to an interface not described.
+    Interface { offset: usize, id: u32 },
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::Magic(x) => write!(f, "magic {:#010x} is neither pcap nor pcapng", x),
+            CaptureError::Truncated(x) => write!(f, "truncated at offset {}", x),
+            CaptureError::BlockLength { offset, len } => write!(f, "block at offset {} has bad length {}", offset, len),
+            CaptureError::Interface { offset, id } => write!(f, "block at offset {} refers to unknown interface {}", offset, id),
+        }
+    }
+}
+
+impl Error for CaptureError {}
+
+/// Reader of integers in the byte order of a file.
+#[derive(Copy, Clone)]
+struct Order {
+    big: bool,
+}
+
+impl Order {
+    fn u16(&self, data: &[u8], offset: usize) -> Result<u16, CaptureError> {
+        let x = data.get(offset..offset + 2).ok_or(CaptureError::Truncated(offset))?;
+        let x = [x[0], x[1]];
+        Ok(if self.big { u16::from_be_bytes(x) } else { u16::from_le_bytes(x) })
+    }
+
+    fn u32(&self, data: &[u8], offset: usize) -> Result<u32, CaptureError> {
+        let x = data.get(offset..offset + 4).ok_or(CaptureError::Truncated(offset))?;
+        let x = [x[0], x[1], x[2], x[3]];
+        Ok(if self.big { u32::from_be_bytes(x) } else { u32::from_le_bytes(x) })
+    }
+}
+
+fn bytes(data: &[u8], offset: usize, len: usize) -> Result<&[u8], CaptureError> {
+    data.get(offset..offset + len).ok_or(CaptureError::Truncated(offset))
+}
+
+/// Read all packets of a pcap or pcapng file.
+pub fn parse(file: &[u8]) -> Result<Vec<Packet<'_>>, CaptureError> {
+    let magic = Order { big: false }.u32(file, 0)?;
+    match magic {
+        PCAP_MICROS | PCAP_NANOS => parse_pcap(file, Order { big: false }),
+        _ if magic.swap_bytes() == PCAP_MICROS || magic.swap_bytes() == PCAP_NANOS => {
+            parse_pcap(file, Order { big: true })
+        }
+        PCAPNG_SECTION => parse_pcapng(file),
+        _ => Err(CaptureError::Magic(magic)),
+    }
+}
+
+fn parse_pcap(file: &[u8], order: Order) -> Result<Vec<Packet<'_>>, CaptureError> {
+    let link_type = order.u32(file, 20)?;
+    let mut packets = Vec::new();
+    let mut offset = 24;
+    while offset < file.len() {
+        let cap_len = order.u32(file, offset + 8)? as usize;
+        let orig_len = order.u32(file, offset + 12)?;
+        let data = bytes(file, offset + 16, cap_len)?;
+        packets.push(Packet { link_type, data, orig_len });
+        offset += 16 + cap_len;
+    }
+    Ok(packets)
+}
+
+fn parse_pcapng(file: &[u8]) -> Result<Vec<Packet<'_>>, CaptureError> {
+    let mut packets = Vec::new();
+    let mut order = Order { big: false };
+    // link types of interfaces of the current section
+    let mut interfaces = Vec::new();
+    let mut offset = 0;
+    while offset < file.len() {
+        let block_type = order.u32(file, offset)?;
+        if block_type == PCAPNG_SECTION {
+            // the byte order of a section is known only from its header
+            order = Order { big: bytes(file, offset + 8, 4)? == PCAPNG_BYTE_ORDER.to_be_bytes() };
+            interfaces.clear();
+        }
+        let len = order.u32(file, offset + 4)? as usize;
+        if len < 12 || !len.is_multiple_of(4) {
+            return Err(CaptureError::BlockLength { offset, len });
+        }
+        let block = bytes(file, offset, len)?;
+        let body = &block[8..len - 4];
+        let least = match block_type {
+            PCAPNG_INTERFACE => 8,
+            PCAPNG_ENHANCED_PACKET => 20,
+            PCAPNG_SIMPLE_PACKET => 4,
+            _ => 0,
+        };
+        if body.len() < least {
+            return Err(CaptureError::BlockLength { offset, len });
+        }
+        match block_type {
+            PCAPNG_INTERFACE => interfaces.push(order.u16(body, 0)? as u32),
+            PCAPNG_ENHANCED_PACKET => {
+                let id = order.u32(body, 0)?;
+                let link_type = *interfaces.get(id as usize).ok_or(CaptureError::Interface { offset, id })?;
+                let cap_len = order.u32(body, 12)? as usize;
+                let orig_len = order.u32(body, 16)?;
+                let data = bytes(body, 20, cap_len).map_err(|_| CaptureError::BlockLength { offset, len })?;
+                packets.push(Packet { link_type, data, orig_len });
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                let link_type = *interfaces.first().ok_or(CaptureError::Interface { offset, id: 0 })?;
+                let orig_len = order.u32(body, 0)?;
+                let data = &body[4..][..(orig_len as usize).min(body.len() - 4)];
+                packets.push(Packet { link_type, data, orig_len });
+            }
+            // statistics, name resolution and other blocks
+            _ => {}
+        }
+        offset += len;
+    }
+    Ok(packets)
+}
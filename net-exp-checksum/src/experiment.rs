@@ -0,0 +1,215 @@
+//! Experiment measuring how well checksums detect corruption, by injecting patterns of errors
+//! into payloads and counting corrupted payloads whose checksum is unchanged.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::Algorithm;
+
+/// Tries drawing an error that changes the payload before giving up on it.
+const TRIES: usize = 64;
+
+/// Pseudo-random generator by xorshift, enough to draw errors reproducibly.
+#[derive(Debug, Clone)]
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // a zero state would stay zero
+        XorShift(seed ^ 0x2545_f491_4f6c_dd1d)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Number in `0..n`, which must not be zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn fill(&mut self, data: &mut [u8]) {
+        for chunk in data.chunks_mut(8) {
+            let x = self.next_u64().to_ne_bytes();
+            chunk.copy_from_slice(&x[..chunk.len()]);
+        }
+    }
+}
+
+/// Pattern of errors injected into a payload.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Pattern {
+    /// One bit flipped.
+    Bit,
+    /// Bits flipped within a span of this many bits, the first and the last of them always and
+    /// those between at random.
+    Burst(usize),
+    /// Two different 16-bit words at even offsets swapped.
+    Swap,
+    /// This many distinct bits flipped anywhere.
+    Random(usize),
+}
+
+impl Pattern {
+    /// Patterns measured by default, showing where the Internet checksum falls behind CRCs.
+    pub const DEFAULT: [Pattern; 8] = [
+        Pattern::Bit, Pattern::Burst(8), Pattern::Burst(16), Pattern::Burst(17), Pattern::Burst(33),
+        Pattern::Swap, Pattern::Random(2), Pattern::Random(4),
+    ];
+
+    /// Inject errors of the pattern into data. Returns whether the data is changed, which fails
+    /// only when data is too short for the pattern or has no different words to swap.
+    pub fn inject(&self, data: &mut [u8], rng: &mut XorShift) -> bool {
+        let bits = data.len() * 8;
+        match *self {
+            Pattern::Bit => Pattern::Random(1).inject(data, rng),
+            Pattern::Burst(len) => {
+                if len == 0 || len > bits {
+                    return false;
+                }
+                let start = rng.below(bits - len + 1);
+                flip(data, start);
+                if len > 1 {
+                    flip(data, start + len - 1);
+                }
+                for i in start + 1..start + len - 1 {
+                    if rng.next_u64() & 1 == 1 {
+                        flip(data, i);
+                    }
+                }
+                true
+            }
+            Pattern::Swap => {
+                let words = data.len() / 2;
+                if words < 2 {
+                    return false;
+                }
+                for _ in 0..TRIES {
+                    let (i, j) = (rng.below(words) * 2, rng.below(words) * 2);
+                    if data[i..i + 2] != data[j..j + 2] {
+                        data.swap(i, j);
+                        data.swap(i + 1, j + 1);
+                        return true;
+                    }
+                }
+                false
+            }
+            Pattern::Random(count) => {
+                if count == 0 || count > bits {
+                    return false;
+                }
+                let mut flipped = Vec::with_capacity(count);
+                while flipped.len() < count {
+                    let i = rng.below(bits);
+                    if !flipped.contains(&i) {
+                        flip(data, i);
+                        flipped.push(i);
+                    }
+                }
+                true
+            }
+        }
+    }
+}
+
+/// Flip a bit of data, counted from the most significant bit of the first byte.
+fn flip(data: &mut [u8], bit: usize) {
+    data[bit / 8] ^= 0x80 >> (bit % 8);
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Bit => f.write_str("bit"),
+            Pattern::Burst(len) => write!(f, "burst:{}", len),
+            Pattern::Swap => f.write_str("swap"),
+            Pattern::Random(count) => write!(f, "random:{}", count),
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    /// Parse `bit`, `burst:<bits>`, `swap` or `random:<bits>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((name, arg)) => {
+                let arg = arg.parse::<usize>().map_err(|x| format!("{}: {}", s, x))?;
+                if arg == 0 {
+                    return Err(format!("{}: count of bits must not be zero", s));
+                }
+                (name, Some(arg))
+            }
+            None => (s, None),
+        };
+        match (name, arg) {
+            ("bit", None) => Ok(Pattern::Bit),
+            ("burst", Some(len)) => Ok(Pattern::Burst(len)),
+            ("swap", None) => Ok(Pattern::Swap),
+            ("random", Some(count)) => Ok(Pattern::Random(count)),
+            _ => Err(format!("{}: expected bit, burst:<bits>, swap or random:<bits>", s)),
+        }
+    }
+}
+
+/// Errors of a pattern injected and missed by an algorithm.
+#[derive(Debug, Copy, Clone)]
+pub struct Outcome {
+    pub pattern: Pattern,
+    pub algo: Algorithm,
+    /// Payloads changed by errors injected.
+    pub trials: u64,
+    /// Payloads changed whose checksum is unchanged.
+    pub undetected: u64,
+}
+
+impl Outcome {
+    /// Ratio of errors detected to errors injected.
+    pub fn detection_rate(&self) -> f64 {
+        if self.trials == 0 {
+            return f64::NAN;
+        }
+        1.0 - self.undetected as f64 / self.trials as f64
+    }
+}
+
+/// Inject errors of every pattern into copies of payloads in turn, some trials per pattern, and
+/// count errors every algorithm misses. The same errors are checked against every algorithm.
+pub fn run(payloads: &[Vec<u8>], patterns: &[Pattern], algos: &[Algorithm], trials: u64, seed: u64) -> Vec<Outcome> {
+    let originals = payloads.iter()
+        .map(|x| algos.iter().map(|algo| algo.checksum(x)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let mut rng = XorShift::new(seed);
+    let mut outcomes = Vec::new();
+    for pattern in patterns {
+        let mut counts = vec![(0, 0); algos.len()];
+        let mut buf = Vec::new();
+        for trial in 0..trials {
+            let i = trial as usize % payloads.len().max(1);
+            let Some(payload) = payloads.get(i) else { break };
+            buf.clear();
+            buf.extend_from_slice(payload);
+            if !pattern.inject(&mut buf, &mut rng) {
+                continue;
+            }
+            for (j, algo) in algos.iter().enumerate() {
+                counts[j].0 += 1;
+                if algo.checksum(&buf) == originals[i][j] {
+                    counts[j].1 += 1;
+                }
+            }
+        }
+        outcomes.extend(algos.iter().zip(counts).map(|(algo, (trials, undetected))| Outcome {
+            pattern: *pattern,
+            algo: *algo,
+            trials,
+            undetected,
+        }));
+    }
+    outcomes
+}
@@ -0,0 +1,151 @@
+//! Step-by-step computation of the Internet checksum, one word at a time with every end-around
+//! carry, rendered as a text table, Markdown or an SVG diagram for reports.
+
+use std::fmt::Write;
+
+/// Word of data added to the running sum.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Step {
+    /// Offset of the word in data.
+    pub offset: usize,
+    pub word: u16,
+    /// Whether the word is the last byte of data padded with a zero byte.
+    pub padded: bool,
+    /// Running sum before the word is added.
+    pub before: u16,
+    /// Whether adding the word carries out of 16 bits, the carry added back to the lowest bit.
+    pub carry: bool,
+    /// Running sum after the word and the carry are added.
+    pub after: u16,
+}
+
+/// Computation of the Internet checksum of some data.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Explanation {
+    pub steps: Vec<Step>,
+    /// One's complement sum of all words.
+    pub sum: u16,
+    /// One's complement of the sum.
+    pub checksum: u16,
+}
+
+/// Explain the checksum of data, adding one big-endian word at a time.
+pub fn explain(data: &[u8]) -> Explanation {
+    let mut steps = Vec::with_capacity(data.len().div_ceil(2));
+    let mut sum = 0_u16;
+    for (i, word) in data.chunks(2).enumerate() {
+        let padded = word.len() == 1;
+        let word = u16::from_be_bytes([word[0], word.get(1).copied().unwrap_or(0)]);
+        let (raw, carry) = sum.overflowing_add(word);
+        let after = raw + carry as u16;
+        steps.push(Step { offset: i * 2, word, padded, before: sum, carry, after });
+        sum = after;
+    }
+    Explanation { steps, sum, checksum: !sum }
+}
+
+/// 16 bits in binary, split into nibbles.
+fn binary(x: u16) -> String {
+    let bits = format!("{:016b}", x);
+    (0..4).map(|i| &bits[i * 4..i * 4 + 4]).collect::<Vec<_>>().join(" ")
+}
+
+impl Step {
+    /// Sum of the word before the carry is added back, of 17 bits.
+    pub fn raw(&self) -> u32 {
+        self.before as u32 + self.word as u32
+    }
+
+    fn word_label(&self) -> String {
+        if self.padded { format!("0x{:04x} (padded)", self.word) } else { format!("0x{:04x}", self.word) }
+    }
+}
+
+impl Explanation {
+    /// Render as a plain text table.
+    pub fn to_table(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "{:>4}  {:>6}  {:<15}  {:<19}  {:<7}  {:<5}  {:<6}  sum binary",
+                 "#", "offset", "word", "word binary", "sum+word", "carry", "sum").unwrap();
+        for (i, x) in self.steps.iter().enumerate() {
+            writeln!(out, "{:>4}  {:>6}  {:<15}  {:<19}  0x{:05x}  {:<5}  0x{:04x}  {}",
+                     i + 1, x.offset, x.word_label(), binary(x.word), x.raw(), if x.carry { "+1" } else { "" },
+                     x.after, binary(x.after)).unwrap();
+        }
+        writeln!(out, "sum       0x{:04x}  {}", self.sum, binary(self.sum)).unwrap();
+        writeln!(out, "checksum  0x{:04x}  {}  (one's complement of the sum)", self.checksum, binary(self.checksum))
+            .unwrap();
+        out
+    }
+
+    /// Render as a Markdown table.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "| # | offset | word | word binary | sum + word | carry | sum | sum binary |").unwrap();
+        writeln!(out, "|---:|---:|---|---|---|---|---|---|").unwrap();
+        for (i, x) in self.steps.iter().enumerate() {
+            writeln!(out, "| {} | {} | `{}` | `{}` | `0x{:05x}` | {} | `0x{:04x}` | `{}` |",
+                     i + 1, x.offset, x.word_label(), binary(x.word), x.raw(), if x.carry { "+1" } else { "" },
+                     x.after, binary(x.after)).unwrap();
+        }
+        writeln!(out).unwrap();
+        writeln!(out, "Sum: `0x{:04x}` (`{}`)", self.sum, binary(self.sum)).unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "Checksum, the one's complement of the sum: `0x{:04x}` (`{}`)", self.checksum, binary(self.checksum))
+            .unwrap();
+        out
+    }
+
+    /// Render as an SVG diagram of column addition in binary, each word added under the running
+    /// sum and every carry out of 16 bits drawn back into the lowest bit.
+    pub fn to_svg(&self) -> String {
+        const ROW: usize = 24;
+        const CHAR: usize = 10;
+        // left edge of the 17 bits of a sum, the first being the carry
+        const BITS: usize = 120;
+        let width = BITS + 22 * CHAR + 260;
+        let height = ROW * (3 * self.steps.len() + 4);
+        let mut out = String::new();
+        writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="16">"#,
+                 width, height).unwrap();
+        writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+        let text = |out: &mut String, x: usize, y: usize, fill: &str, s: &str| {
+            writeln!(out, r#"<text x="{}" y="{}" fill="{}" xml:space="preserve">{}</text>"#, x, y, fill, s).unwrap();
+        };
+        let mut y = ROW;
+        text(&mut out, 10, y, "black", "sum");
+        text(&mut out, BITS + CHAR, y, "black", &binary(0));
+        for x in &self.steps {
+            y += ROW;
+            let label = if x.padded { format!("+ @{} pad", x.offset) } else { format!("+ @{}", x.offset) };
+            text(&mut out, 10, y, "#1f77b4", &label);
+            text(&mut out, BITS + CHAR, y, "#1f77b4", &binary(x.word));
+            text(&mut out, BITS + 22 * CHAR, y, "#1f77b4", &format!("0x{:04x}", x.word));
+            // rule under the addition
+            writeln!(out, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="black"/>"#,
+                     BITS - CHAR, y + 6, BITS + 20 * CHAR, y + 6).unwrap();
+            if x.carry {
+                // the sum of 17 bits, whose carry wraps around into the lowest bit
+                y += ROW;
+                text(&mut out, BITS, y, "#d62728", "1");
+                text(&mut out, BITS + CHAR, y, "black", &binary(x.raw() as u16));
+                let (x1, x2) = (BITS + CHAR / 2, BITS + 19 * CHAR + CHAR / 2);
+                writeln!(out, r##"<path d="M {} {} C {} {}, {} {}, {} {}" fill="none" stroke="#d62728" marker-end="url(#arrow)"/>"##,
+                         x1, y + 4, x1, y + ROW, x2, y + ROW, x2, y + ROW / 2 + 4).unwrap();
+                text(&mut out, BITS + 22 * CHAR, y, "#d62728", "carry +1");
+            }
+            y += ROW;
+            text(&mut out, 10, y, "black", "sum");
+            text(&mut out, BITS + CHAR, y, "black", &binary(x.after));
+            text(&mut out, BITS + 22 * CHAR, y, "black", &format!("0x{:04x}", x.after));
+        }
+        y += 2 * ROW;
+        text(&mut out, 10, y, "black", "checksum");
+        text(&mut out, BITS + CHAR, y, "#2ca02c", &binary(self.checksum));
+        text(&mut out, BITS + 22 * CHAR, y, "#2ca02c", &format!("0x{:04x} = ~0x{:04x}", self.checksum, self.sum));
+        writeln!(out, r##"<defs><marker id="arrow" markerWidth="8" markerHeight="8" refX="4" refY="4" orient="auto"><path d="M 0 0 L 8 4 L 0 8 z" fill="#d62728"/></marker></defs>"##)
+            .unwrap();
+        writeln!(out, "</svg>").unwrap();
+        out
+    }
+}
@@ -0,0 +1,38 @@
+//! Incremental update of Internet checksums by RFC 1624, as done by routers decrementing the
+//! TTL or NAT rewriting addresses: `HC' = ~(~HC + ~m + m')` for every 16-bit word `m` changed to
+//! `m'`, in one's complement arithmetic.
+//!
+//! Eqn. 3 of RFC 1624 is used rather than Eqn. 2 of RFC 1141, which subtracts and may give
+//! `0xffff` (-0) where a full recomputation gives `0x0000` (+0). The result then matches a full
+//! recomputation whenever the data after the update is not all zeros, the only data whose sum is
+//! +0 and whose checksum is `0xffff`, but which no header carrying a checksum may be.
+
+use crate::{kernel, InternetChecksum};
+
+/// Checksum after a 16-bit word of data changes from `old` to `new`.
+pub fn update_word(checksum: u16, old: u16, new: u16) -> u16 {
+    let sum = kernel::add(kernel::add(!checksum as u64, !old as u64), new as u64);
+    !kernel::fold(sum)
+}
+
+/// Checksum after a 32-bit field of data at an even offset, such as an IPv4 address, changes.
+pub fn update_u32(checksum: u16, old: u32, new: u32) -> u16 {
+    let checksum = update_word(checksum, (old >> 16) as u16, (new >> 16) as u16);
+    update_word(checksum, old as u16, new as u16)
+}
+
+/// Sum of bytes at an offset in data, a byte at an odd offset being the low half of its word.
+fn sum_at(offset: usize, bytes: &[u8]) -> u16 {
+    let mut hasher = InternetChecksum::new();
+    if offset % 2 == 1 {
+        hasher.update(&[0]);
+    }
+    hasher.update(bytes);
+    hasher.sum()
+}
+
+/// Checksum after bytes of data at an offset change from `old` to `new`, which must be as long.
+pub fn update_bytes(checksum: u16, offset: usize, old: &[u8], new: &[u8]) -> u16 {
+    assert_eq!(old.len(), new.len(), "bytes changed to bytes of another length");
+    update_word(checksum, sum_at(offset, old), sum_at(offset, new))
+}
@@ -0,0 +1,185 @@
+//! Kernels summing words of data, from the plain loop to SIMD, chosen at runtime.
+//!
+//! Every kernel takes data starting at an even offset in the stream and returns a partial sum,
+//! a `u64` whose fold to 16 bits by end-around carry is the one's complement sum of the
+//! big-endian words of data, padded with a zero byte if its length is odd. Partial sums are
+//! combined by `add`.
+//!
+//! Faster kernels add words in native byte order, for the sum of byte-swapped words is the
+//! byte-swapped sum, and fold carries only once at the end, for carries out of 64 bits are worth
+//! one in one's complement as carries out of 16 bits are.
+
+use std::fmt::{Display, Formatter};
+
+/// Add partial sums, with end-around carry.
+pub fn add(x: u64, y: u64) -> u64 {
+    let (sum, carry) = x.overflowing_add(y);
+    sum + carry as u64
+}
+
+/// Fold a partial sum to 16 bits.
+pub fn fold(mut x: u64) -> u16 {
+    while x > 0xffff {
+        x = (x & 0xffff) + (x >> 16);
+    }
+    x as u16
+}
+
+/// Partial sum of words added in native byte order.
+fn from_native(x: u64) -> u64 {
+    u16::from_be(fold(x)) as u64
+}
+
+/// Kernel summing words of data.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Kernel {
+    /// One big-endian word at a time, folding every carry as it comes.
+    Scalar,
+    /// Eight bytes at a time in a 64-bit lane, folding carries at the end.
+    Lanes,
+    /// Sixteen bytes at a time with SSE2.
+    Sse2,
+    /// Thirty-two bytes at a time with AVX2.
+    Avx2,
+}
+
+impl Kernel {
+    pub const ALL: [Kernel; 4] = [Kernel::Scalar, Kernel::Lanes, Kernel::Sse2, Kernel::Avx2];
+
+    /// Whether the kernel runs on this CPU.
+    pub fn available(&self) -> bool {
+        match self {
+            Kernel::Scalar | Kernel::Lanes => true,
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Sse2 => is_x86_feature_detected!("sse2"),
+            #[cfg(target_arch = "x86_64")]
+            Kernel::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(not(target_arch = "x86_64"))]
+            Kernel::Sse2 | Kernel::Avx2 => false,
+        }
+    }
+
+    /// Fastest kernel running on this CPU.
+    pub fn detect() -> Kernel {
+        [Kernel::Avx2, Kernel::Sse2].into_iter()
+            .find(|x| x.available())
+            .unwrap_or(Kernel::Lanes)
+    }
+
+    /// Partial sum of data, with the kernel if it runs on this CPU, otherwise with 64-bit lanes.
+    pub fn sum(&self, data: &[u8]) -> u64 {
+        match self {
+            Kernel::Scalar => scalar(data),
+            Kernel::Lanes => lanes(data),
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: the CPU is checked to support the instructions
+            Kernel::Sse2 if self.available() => unsafe { x86::sse2(data) },
+            #[cfg(target_arch = "x86_64")]
+            // SAFETY: the CPU is checked to support the instructions
+            Kernel::Avx2 if self.available() => unsafe { x86::avx2(data) },
+            _ => lanes(data),
+        }
+    }
+}
+
+impl Default for Kernel {
+    fn default() -> Self {
+        Kernel::detect()
+    }
+}
+
+impl Display for Kernel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Kernel::Scalar => "scalar",
+            Kernel::Lanes => "lanes",
+            Kernel::Sse2 => "sse2",
+            Kernel::Avx2 => "avx2",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Sum of big-endian words, one at a time.
+fn scalar(data: &[u8]) -> u64 {
+    let mut sum = 0_u16;
+    for word in data.chunks(2) {
+        let val = u16::from_be_bytes([word[0], word.get(1).copied().unwrap_or(0)]);
+        let (ns, of) = sum.overflowing_add(val);
+        sum = ns + of as u16;
+    }
+    sum as u64
+}
+
+/// Sum of words in 64-bit lanes.
+fn lanes(data: &[u8]) -> u64 {
+    let mut sum = 0_u64;
+    let mut carry = 0_u64;
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let (ns, of) = sum.overflowing_add(u64::from_ne_bytes(chunk.try_into().unwrap()));
+        sum = ns;
+        carry += of as u64;
+    }
+    add(from_native(add(sum, carry)), scalar(chunks.remainder()))
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+    use super::{add, from_native, lanes};
+
+    /// Vectors summed into 32-bit lanes before they may overflow, each vector adding at most
+    /// twice `0xffff` to every lane.
+    const BLOCK: usize = 0x8000;
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn sse2(data: &[u8]) -> u64 {
+        let zero = _mm_setzero_si128();
+        let mut total = 0;
+        let mut chunks = data.chunks_exact(16);
+        loop {
+            let mut acc = _mm_setzero_si128();
+            let mut n = 0;
+            for chunk in chunks.by_ref().take(BLOCK) {
+                let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+                // widen words to 32 bits, adding the halves into the same lanes
+                acc = _mm_add_epi32(acc, _mm_unpacklo_epi16(v, zero));
+                acc = _mm_add_epi32(acc, _mm_unpackhi_epi16(v, zero));
+                n += 1;
+            }
+            let mut words = [0_u32; 4];
+            _mm_storeu_si128(words.as_mut_ptr() as *mut __m128i, acc);
+            total = words.iter().fold(total, |x, y| add(x, *y as u64));
+            if n < BLOCK {
+                break;
+            }
+        }
+        add(from_native(total), lanes(chunks.remainder()))
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn avx2(data: &[u8]) -> u64 {
+        let zero = _mm256_setzero_si256();
+        let mut total = 0;
+        let mut chunks = data.chunks_exact(32);
+        loop {
+            let mut acc = _mm256_setzero_si256();
+            let mut n = 0;
+            for chunk in chunks.by_ref().take(BLOCK) {
+                let v = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+                acc = _mm256_add_epi32(acc, _mm256_unpacklo_epi16(v, zero));
+                acc = _mm256_add_epi32(acc, _mm256_unpackhi_epi16(v, zero));
+                n += 1;
+            }
+            let mut words = [0_u32; 8];
+            _mm256_storeu_si256(words.as_mut_ptr() as *mut __m256i, acc);
+            total = words.iter().fold(total, |x, y| add(x, *y as u64));
+            if n < BLOCK {
+                break;
+            }
+        }
+        add(from_native(total), lanes(chunks.remainder()))
+    }
+}
@@ -0,0 +1,175 @@
+//! Internet checksum of RFC 1071, the 16-bit one's complement of the one's complement sum of
+//! data taken as big-endian 16-bit words, as carried by IPv4, UDP, TCP and ICMP headers.
+
+use std::fs::File;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+pub use algorithm::{Algorithm, Checksum};
+pub use kernel::Kernel;
+
+pub mod algorithm;
+pub mod capture;
+pub mod experiment;
+pub mod explain;
+pub mod incremental;
+pub mod kernel;
+pub mod packet;
+pub mod plot;
+
+/// Size of blocks data is read in.
+const BLOCK: usize = 64 * 1024;
+
+/// Incremental hasher of the Internet checksum.
+///
+/// Data may be fed in chunks of any length, a byte left over from an odd chunk being paired with
+/// the first byte of the next one, so the result never depends on how the data is split.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct InternetChecksum {
+    /// Partial sum of words so far, with carries out of 16 bits not folded back yet.
+    sum: u64,
+    /// Byte of a word split between updates, the high half of the word.
+    odd: Option<u8>,
+    kernel: Kernel,
+}
+
+impl InternetChecksum {
+    /// Hasher with the fastest kernel running on this CPU.
+    pub fn new() -> Self {
+        InternetChecksum::default()
+    }
+
+    pub fn with_kernel(kernel: Kernel) -> Self {
+        InternetChecksum { kernel, ..InternetChecksum::default() }
+    }
+
+    /// Add data following what is added before.
+    pub fn update(&mut self, mut data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        if let Some(high) = self.odd.take() {
+            self.sum = kernel::add(self.sum, u16::from_be_bytes([high, data[0]]) as u64);
+            data = &data[1..];
+        }
+        let even = data.len() & !1;
+        self.sum = kernel::add(self.sum, self.kernel.sum(&data[..even]));
+        if let [high] = data[even..] {
+            self.odd = Some(high);
+        }
+    }
+
+    /// Add data following what is added before, summed in parts on some threads at once.
+    pub fn update_parallel(&mut self, mut data: &[u8], threads: usize) {
+        if data.is_empty() {
+            return;
+        }
+        if self.odd.is_some() {
+            self.update(&data[..1]);
+            data = &data[1..];
+        }
+        let part = ((data.len() / threads.max(1) + 1) & !1).max(2);
+        let kernel = self.kernel;
+        let sums = thread::scope(|s| {
+            let handles = data.chunks(part)
+                .map(|x| s.spawn(move || {
+                    let mut hasher = InternetChecksum::with_kernel(kernel);
+                    hasher.update(x);
+                    hasher
+                }))
+                .collect::<Vec<_>>();
+            handles.into_iter().map(|x| x.join().unwrap()).collect::<Vec<_>>()
+        });
+        sums.iter().for_each(|x| self.combine(x));
+    }
+
+    /// Add the sum of data following what is added to this hasher, which must hold an even count
+    /// of bytes.
+    ///
+    /// One's complement addition is associative and commutative, so parts of data summed apart
+    /// combine into the sum of the whole, as long as every part starts at an even offset.
+    pub fn combine(&mut self, other: &InternetChecksum) {
+        assert!(self.odd.is_none(), "combined after an odd count of bytes");
+        self.sum = kernel::add(self.sum, other.sum);
+        self.odd = other.odd;
+    }
+
+    /// Add all data of a reader, read in large blocks, returning the count of bytes read.
+    ///
+    /// Reads may return any count of bytes, so a byte left over from one read is carried to the
+    /// next rather than padded. Interrupted reads are retried, and other errors are returned.
+    pub fn read_from(&mut self, r: impl Read) -> io::Result<u64> {
+        read_blocks(r, |x| self.update(x))
+    }
+
+    /// One's complement sum of data added so far, padded with a zero byte if its length is odd.
+    pub fn sum(&self) -> u16 {
+        kernel::fold(kernel::add(self.sum, self.odd.map_or(0, |x| (x as u64) << 8)))
+    }
+
+    /// Checksum of data added so far, the one's complement of the sum.
+    pub fn finish(&self) -> u16 {
+        !self.sum()
+    }
+}
+
+/// Read all data of a reader in large blocks, returning the count of bytes read. Interrupted
+/// reads are retried, and other errors are returned.
+pub fn read_blocks(mut r: impl Read, mut f: impl FnMut(&[u8])) -> io::Result<u64> {
+    let mut buf = vec![0; BLOCK];
+    let mut total = 0;
+    loop {
+        match r.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => {
+                f(&buf[..n]);
+                total += n as u64;
+            }
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Checksum of data.
+pub fn checksum(data: &[u8]) -> u16 {
+    let mut hasher = InternetChecksum::new();
+    hasher.update(data);
+    hasher.finish()
+}
+
+/// Check data carrying its checksum, whose sum must then be all ones.
+pub fn verify(data: &[u8]) -> bool {
+    let mut hasher = InternetChecksum::new();
+    hasher.update(data);
+    hasher.sum() == 0xffff
+}
+
+/// Checksum hasher of a file, read in parts on some threads at once if it is a regular file.
+/// Other files such as pipes and devices tell no length and may not seek, so they are streamed.
+pub fn read_file_parallel(path: &Path, threads: usize, kernel: Kernel) -> io::Result<InternetChecksum> {
+    let file = File::open(path)?;
+    let meta = file.metadata()?;
+    if !meta.is_file() {
+        let mut hasher = InternetChecksum::with_kernel(kernel);
+        hasher.read_from(file)?;
+        return Ok(hasher);
+    }
+    let len = meta.len();
+    let part = ((len / threads.max(1) as u64 + 1) & !1).max(2);
+    let parts = thread::scope(|s| {
+        let handles = (0..len.div_ceil(part))
+            .map(|i| s.spawn(move || -> io::Result<InternetChecksum> {
+                let mut file = File::open(path)?;
+                file.seek(SeekFrom::Start(i * part))?;
+                let mut hasher = InternetChecksum::with_kernel(kernel);
+                hasher.read_from(file.take(part))?;
+                Ok(hasher)
+            }))
+            .collect::<Vec<_>>();
+        handles.into_iter().map(|x| x.join().unwrap()).collect::<io::Result<Vec<_>>>()
+    })?;
+    let mut hasher = InternetChecksum::with_kernel(kernel);
+    parts.iter().for_each(|x| hasher.combine(x));
+    Ok(hasher)
+}
@@ -1,24 +1,625 @@
-use std::env::args;
-use std::fs::File;
-use std::io::{BufReader, Read};
-use std::time::Instant;
-
-fn main() {
-    let path = args().nth(1).unwrap();
-    let file = File::open(path).unwrap();
-    let mut file = BufReader::new(file);
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::hint::black_box;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+use clap::{Parser, Subcommand, ValueEnum};
+use memmap2::Mmap;
+use net_exp_checksum::{plot, Algorithm, Checksum, InternetChecksum, Kernel};
+use net_exp_checksum::experiment::{self, Outcome, Pattern, XorShift};
+use net_exp_checksum::{capture, explain};
+use net_exp_checksum::packet::{self, Offload, PacketError, Status};
+
+/// Size of files memory-mapped rather than streamed, in bytes.
+const MMAP_THRESHOLD: u64 = 1 << 20;
+
+/// Print or check checksums of files, one `checksum  file` line per file.
+///
+/// With no file, or when the file is `-`, read standard input.
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Files to checksum, or glob patterns matching them
+    files: Vec<String>,
+    /// Read checksums from the files and check them
+    #[arg(short, long)]
+    check: bool,
+    /// Don't print OK for every file checked successfully
+    #[arg(long, requires = "check")]
+    quiet: bool,
+    /// Algorithm of checksums
+    #[arg(long, value_enum, default_value_t = Algorithm::Internet)]
+    algo: Algorithm,
+    /// Kernel summing words of Internet checksums, the fastest running on this CPU if not specified
+    #[arg(long, value_enum)]
+    kernel: Option<Kernel>,
+    /// Count of threads summing parts of large files at once, for Internet checksums
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+    /// Print the time taken to standard error
+    #[arg(long)]
+    time: bool,
+    /// Compare the speed of kernels and of parallel summing instead
+    #[arg(long, conflicts_with_all = ["files", "check"])]
+    bench: bool,
+    /// Size of data summed in the benchmark, in MiB
+    #[arg(long, default_value_t = 256)]
+    bench_size: usize,
+    /// Print how Internet checksums are computed word by word instead, with every carry
+    #[arg(long, conflicts_with_all = ["check", "bench", "algo"])]
+    explain: bool,
+    /// Print explanations as Markdown tables
+    #[arg(long, requires = "explain")]
+    markdown: bool,
+    /// Directory SVG diagrams of explanations are written to, one `<file>.svg` per input, inputs
+    /// of the same file name in other directories failing
+    #[arg(long, requires = "explain")]
+    svg: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Measure how well checksums detect errors injected into payloads
+    ///
+    /// Every error is checked against every algorithm. Detection rates are printed as a table,
+    /// and written to `detection.csv`, `detection.md` and a chart of errors undetected.
+    Experiment(ExperimentArgs),
+    /// Check checksum fields of a raw IPv4 or IPv6 packet, such as one copied from Wireshark
+    ///
+    /// Every field is reported as valid or invalid along with the value expected. Exits with
+    /// failure if any is invalid.
+    VerifyPacket(VerifyPacketArgs),
+    /// Check checksum fields of every packet of a pcap or pcapng capture
+    ///
+    /// Mismatches are printed per packet, those looking like checksums offloaded to the network
+    /// card of the capturing host labeled so, and followed by a summary per protocol. Exits with
+    /// failure if any mismatch is not offloaded.
+    Capture(CaptureArgs),
+}
+
+#[derive(clap::Args)]
+struct CaptureArgs {
+    /// Capture file
+    file: PathBuf,
+    /// Don't print packets whose checksums look offloaded
+    #[arg(long)]
+    hide_offload: bool,
+}
+
+#[derive(clap::Args)]
+struct VerifyPacketArgs {
+    /// File holding the packet, standard input if `-`
+    #[arg(default_value = "-")]
+    input: String,
+    /// Encoding of the packet
+    #[arg(long, value_enum, default_value_t = Format::Auto)]
+    format: Format,
+    /// Take the packet as an Ethernet II frame carrying IP
+    #[arg(long)]
+    ethernet: bool,
+}
+
+/// Encoding of a packet read.
+#[derive(Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum Format {
+    /// Hex digits, optionally separated by whitespace or colons
+    Hex,
+    /// Raw bytes
+    Binary,
+    /// Hex if the input holds hex digits only, otherwise raw bytes
+    Auto,
+}
+
+#[derive(clap::Args)]
+struct ExperimentArgs {
+    /// Algorithms compared
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = Algorithm::ALL)]
+    algos: Vec<Algorithm>,
+    /// Patterns of errors injected: bit, burst:<bits>, swap of 16-bit words or random:<bits>
+    #[arg(long, value_delimiter = ',', default_values_t = Pattern::DEFAULT)]
+    patterns: Vec<Pattern>,
+    /// Errors injected per pattern
+    #[arg(long, default_value_t = 100000)]
+    trials: u64,
+    /// Files taken as payloads, instead of random ones
+    #[arg(long)]
+    payload: Vec<PathBuf>,
+    /// Count of random payloads
+    #[arg(long, default_value_t = 16, conflicts_with = "payload")]
+    payloads: usize,
+    /// Size of random payloads, in bytes
+    #[arg(long, default_value_t = 256, conflicts_with = "payload")]
+    size: usize,
+    /// Seed of random payloads and errors
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+    /// Directory tables and charts are written to
+    #[arg(long, default_value = ".")]
+    out: PathBuf,
+}
+
+/// Way inputs are summed.
+#[derive(Copy, Clone)]
+struct Options {
+    algo: Algorithm,
+    kernel: Kernel,
+    threads: usize,
+}
+
+/// Whether an argument is a glob pattern rather than a plain file name.
+fn is_pattern(arg: &str) -> bool {
+    arg.contains(['*', '?', '['])
+}
+
+/// Expand glob patterns into the files they match, keeping plain file names and `-` as they are.
+fn expand(args: &[String]) -> Vec<Result<String, String>> {
+    let mut inputs = Vec::new();
+    for arg in args {
+        if !is_pattern(arg) {
+            inputs.push(Ok(arg.clone()));
+            continue;
+        }
+        let paths = match glob::glob(arg) {
+            Ok(paths) => paths,
+            Err(err) => {
+                inputs.push(Err(format!("{}: {}", arg, err)));
+                continue;
+            }
+        };
+        let count = inputs.len();
+        for path in paths {
+            inputs.push(path.map(|x| x.display().to_string()).map_err(|x| x.to_string()));
+        }
+        if inputs.len() == count {
+            inputs.push(Err(format!("{}: no match", arg)));
+        }
+    }
+    inputs
+}
+
+/// Checksum of an input, memory-mapped if it is a large file and streamed otherwise.
+fn sum_input(name: &str, opts: Options) -> io::Result<Box<dyn Checksum + Send>> {
+    let mut hasher = opts.algo.hasher(opts.kernel);
+    if name == "-" {
+        hasher.read_from(&mut io::stdin().lock())?;
+        return Ok(hasher);
+    }
+    let mut file = File::open(name)?;
+    let meta = file.metadata()?;
+    if meta.is_file() && meta.len() >= MMAP_THRESHOLD {
+        // SAFETY: files are taken as not changing while they are summed, as for any checksum tool
+        let map = unsafe { Mmap::map(&file)? };
+        if opts.algo == Algorithm::Internet {
+            let mut hasher = InternetChecksum::with_kernel(opts.kernel);
+            hasher.update_parallel(&map, opts.threads);
+            return Ok(Box::new(hasher));
+        }
+        hasher.update(&map);
+    } else {
+        hasher.read_from(&mut file)?;
+    }
+    Ok(hasher)
+}
+
+/// Checksum in hex, as many digits as the width of the algorithm takes.
+fn format_sum(hasher: &dyn Checksum) -> String {
+    format!("{:0w$x}", hasher.checksum(), w = hasher.width() as usize / 4)
+}
+
+/// Print checksums of inputs. Returns whether all of them are read.
+fn print_sums(args: &[String], opts: Options) -> bool {
+    let mut ok = true;
+    for input in expand(args) {
+        let result = input.and_then(|name| match sum_input(&name, opts) {
+            Ok(hasher) => Ok((name, hasher)),
+            Err(err) => Err(format!("{}: {}", name, err)),
+        });
+        match result {
+            Ok((name, hasher)) => println!("{}  {}", format_sum(hasher.as_ref()), name),
+            Err(err) => {
+                eprintln!("net-exp-checksum: {}", err);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+/// Parse a line of a checksum list, `checksum  file` or `checksum *file`.
+fn parse_line(line: &str) -> Option<(u64, &str)> {
+    let (sum, name) = line.split_once(' ')?;
+    let name = name.strip_prefix([' ', '*'])?;
+    let sum = u64::from_str_radix(sum, 16).ok()?;
+    (!name.is_empty()).then_some((sum, name))
+}
+
+/// Check inputs against checksums listed in files. Returns whether all of them match.
+fn check_sums(lists: &[String], opts: Options, quiet: bool) -> bool {
+    let (mut mismatched, mut unread, mut malformed) = (0, 0, 0);
+    let mut ok = true;
+    for list in lists {
+        let r: Box<dyn BufRead> = if list == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            match File::open(list) {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(err) => {
+                    eprintln!("net-exp-checksum: {}: {}", list, err);
+                    ok = false;
+                    continue;
+                }
+            }
+        };
+        for line in r.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    eprintln!("net-exp-checksum: {}: {}", list, err);
+                    ok = false;
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (expected, name) = if let Some(val) = parse_line(&line) { val } else {
+                malformed += 1;
+                continue;
+            };
+            match sum_input(name, opts) {
+                Ok(hasher) if hasher.checksum() == expected => {
+                    if !quiet {
+                        println!("{}: OK", name);
+                    }
+                }
+                Ok(_) => {
+                    println!("{}: FAILED", name);
+                    mismatched += 1;
+                }
+                Err(err) => {
+                    eprintln!("net-exp-checksum: {}: {}", name, err);
+                    println!("{}: FAILED open or read", name);
+                    unread += 1;
+                }
+            }
+        }
+    }
+    if malformed > 0 {
+        eprintln!("net-exp-checksum: WARNING: {} lines are improperly formatted", malformed);
+    }
+    if unread > 0 {
+        eprintln!("net-exp-checksum: WARNING: {} listed files could not be read", unread);
+    }
+    if mismatched > 0 {
+        eprintln!("net-exp-checksum: WARNING: {} computed checksums did NOT match", mismatched);
+    }
+    ok && mismatched == 0 && unread == 0 && malformed == 0
+}
+
+/// Explain checksums of inputs, as tables and optionally diagrams. Returns whether all of them
+/// are read and written.
+fn explain_inputs(args: &[String], markdown: bool, svg: Option<&Path>) -> bool {
+    let mut ok = true;
+    // inputs diagrams are written for, by the path of the diagram
+    let mut written = BTreeMap::new();
+    for input in expand(args) {
+        let result = input.and_then(|name| {
+            let data = if name == "-" {
+                let mut buf = Vec::new();
+                io::stdin().lock().read_to_end(&mut buf).map(|_| buf)
+            } else {
+                fs::read(&name)
+            };
+            let explanation = explain::explain(&data.map_err(|x| format!("{}: {}", name, x))?);
+            if markdown {
+                println!("### {}\n", name);
+                println!("{}", explanation.to_markdown());
+            } else {
+                println!("{}", name);
+                println!("{}", explanation.to_table());
+            }
+            if let Some(dir) = svg {
+                let file_name = if name == "-" { "stdin".into() } else { Path::new(&name).file_name().unwrap().to_owned() };
+                let mut path = dir.join(file_name);
+                path.as_mut_os_string().push(".svg");
+                if let Some(other) = written.get(&path) {
+                    return Err(format!("{}: written for {} already, not overwritten for {}",
+                                       path.display(), other, name));
+                }
+                fs::create_dir_all(dir)
+                    .and_then(|_| fs::write(&path, explanation.to_svg()))
+                    .map_err(|x| format!("{}: {}", path.display(), x))?;
+                written.insert(path, name);
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            eprintln!("net-exp-checksum: {}", err);
+            ok = false;
+        }
+    }
+    ok
+}
+
+/// Data of some size for the benchmark, by xorshift.
+fn bench_data(size: usize) -> Vec<u8> {
+    let mut data = vec![0; size];
+    XorShift::new(0).fill(&mut data);
+    data
+}
+
+/// Best time of some runs of a function, along with its result.
+fn best_of(runs: usize, mut f: impl FnMut() -> u16) -> (Duration, u16) {
+    (0..runs)
+        .map(|_| {
+            let begin = Instant::now();
+            let sum = black_box(f());
+            (begin.elapsed(), sum)
+        })
+        .min_by_key(|(t, _)| *t)
+        .unwrap()
+}
+
+/// Print the speed of every kernel available, and of summing on all CPUs at once.
+fn bench(size: usize) -> bool {
+    let data = bench_data(size << 20);
+    let threads = thread::available_parallelism().map_or(1, |x| x.get());
+    let mut modes = Kernel::ALL.into_iter()
+        .filter(|x| x.available())
+        .map(|x| (x.to_string(), x, 1))
+        .collect::<Vec<_>>();
+    if threads > 1 {
+        modes.push((format!("{} x{} threads", Kernel::detect(), threads), Kernel::detect(), threads));
+    }
+
+    println!("{:<24}{:>10}{:>10}", "mode", "GB/s", "sum");
+    let mut sums = Vec::new();
+    for (name, kernel, threads) in modes {
+        let (time, sum) = best_of(5, || {
+            let mut hasher = InternetChecksum::with_kernel(kernel);
+            hasher.update_parallel(&data, threads);
+            hasher.sum()
+        });
+        println!("{:<24}{:>10.2}{:>10x}", name, data.len() as f64 / time.as_secs_f64() / 1e9, sum);
+        sums.push(sum);
+    }
+    if sums.windows(2).any(|x| x[0] != x[1]) {
+        eprintln!("Kernels disagree on the sum.");
+        return false;
+    }
+    true
+}
+
+/// Write a table of detection rates in Markdown, a row per pattern and a column per algorithm.
+fn write_markdown(mut w: impl Write, outcomes: &[Outcome], algos: &[Algorithm]) -> io::Result<()> {
+    write!(w, "| pattern |")?;
+    for algo in algos {
+        write!(w, " {} |", algo)?;
+    }
+    writeln!(w)?;
+    writeln!(w, "|---|{}", "---:|".repeat(algos.len()))?;
+    for row in outcomes.chunks(algos.len()) {
+        write!(w, "| {} |", row[0].pattern)?;
+        for x in row {
+            write!(w, " {:.4}% ({}/{}) |", x.detection_rate() * 100.0, x.undetected, x.trials)?;
+        }
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+/// Write outcomes as CSV, a row per pattern and algorithm.
+fn write_csv(path: &Path, outcomes: &[Outcome]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    writeln!(w, "pattern,algorithm,trials,undetected,detection_rate")?;
+    for x in outcomes {
+        writeln!(w, "{},{},{},{},{}", x.pattern, x.algo, x.trials, x.undetected, x.detection_rate())?;
+    }
+    w.flush()
+}
+
+/// Run the error-detection experiment and write its results.
+fn experiment(args: &ExperimentArgs) -> io::Result<()> {
+    let payloads = if args.payload.is_empty() {
+        let mut rng = XorShift::new(!args.seed);
+        (0..args.payloads)
+            .map(|_| {
+                let mut x = vec![0; args.size];
+                rng.fill(&mut x);
+                x
+            })
+            .collect::<Vec<_>>()
+    } else {
+        args.payload.iter().map(fs::read).collect::<io::Result<Vec<_>>>()?
+    };
+    let outcomes = experiment::run(&payloads, &args.patterns, &args.algos, args.trials, args.seed);
+    for x in outcomes.iter().filter(|x| x.trials < args.trials) {
+        eprintln!("net-exp-checksum: {}: only {} of {} errors change the payloads", x.pattern, x.trials, args.trials);
+    }
+
+    write_markdown(io::stdout().lock(), &outcomes, &args.algos)?;
+    fs::create_dir_all(&args.out)?;
+    write_markdown(BufWriter::new(File::create(args.out.join("detection.md"))?), &outcomes, &args.algos)?;
+    write_csv(&args.out.join("detection.csv"), &outcomes)?;
+
+    let groups = args.patterns.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    let names = args.algos.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    let missed = (0..args.algos.len())
+        .map(|i| outcomes.iter().skip(i).step_by(args.algos.len())
+            .map(|x| (1.0 - x.detection_rate()) * 100.0)
+            .collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let series = names.iter().zip(missed.iter()).map(|(x, y)| (x.as_str(), y.as_slice())).collect::<Vec<_>>();
+    let chart = args.out.join("detection");
+    plot::bars(&chart.display().to_string(), "undetected/%", &groups, &series)
+        .map_err(|x| io::Error::other(x.to_string()))
+}
+
+/// Check checksum fields of a packet and print them. Returns whether all of them are valid.
+fn verify_packet(args: &VerifyPacketArgs) -> Result<bool, String> {
+    let raw = if args.input == "-" {
+        let mut buf = Vec::new();
+        io::stdin().lock().read_to_end(&mut buf).map(|_| buf)
+    } else {
+        fs::read(&args.input)
+    };
+    let raw = raw.map_err(|x| format!("{}: {}", args.input, x))?;
+    let hex = || std::str::from_utf8(&raw).ok().and_then(packet::parse_hex);
+    let data = match args.format {
+        Format::Hex => hex().ok_or_else(|| format!("{}: not hex digits", args.input))?,
+        Format::Binary => raw.clone(),
+        Format::Auto => hex().unwrap_or_else(|| raw.clone()),
+    };
+    let checks = if args.ethernet { packet::verify_frame(&data) } else { packet::verify_packet(&data) }
+        .map_err(|x| format!("{}: {}", args.input, x))?;
+    if checks.is_empty() {
+        println!("no checksum field checked");
+    }
+    for x in &checks {
+        let expected = x.expected.map_or("-".to_string(), |x| format!("0x{:04x}", x));
+        println!("{:<8}offset {:<6}found 0x{:04x}  expected {:<8}{}", x.protocol, x.offset, x.found, expected, x.status);
+    }
+    Ok(checks.iter().all(|x| x.status != Status::Invalid))
+}
+
+/// Counts of checksum fields of a protocol checked in a capture.
+#[derive(Default)]
+struct FieldCounts {
+    valid: usize,
+    invalid: usize,
+    offload_zero: usize,
+    offload_partial: usize,
+    absent: usize,
+    fragment: usize,
+}
+
+/// Check checksum fields of all packets of a capture, printing mismatches and a summary. Returns
+/// whether no mismatch is left unexplained by offload.
+fn check_capture(args: &CaptureArgs) -> Result<bool, String> {
+    let file = fs::read(&args.file).map_err(|x| format!("{}: {}", args.file.display(), x))?;
+    let packets = capture::parse(&file).map_err(|x| format!("{}: {}", args.file.display(), x))?;
+    let mut counts = BTreeMap::<&str, FieldCounts>::new();
+    let (mut not_ip, mut cut, mut malformed) = (0, 0, 0);
+    for (i, x) in packets.iter().enumerate() {
+        // numbered from one, as in Wireshark
+        let number = i + 1;
+        let Some(offset) = x.ip_offset() else {
+            not_ip += 1;
+            continue;
+        };
+        let checks = match packet::verify_packet(&x.data[offset..]) {
+            Ok(checks) => checks,
+            Err(PacketError::Truncated { .. }) if x.data.len() < x.orig_len as usize => {
+                cut += 1;
+                continue;
+            }
+            Err(err) => {
+                println!("#{:<6}malformed: {}", number, err);
+                malformed += 1;
+                continue;
+            }
+        };
+        for check in checks {
+            let count = counts.entry(check.protocol).or_default();
+            let label = match (check.status, check.offload()) {
+                (Status::Valid, _) => {
+                    count.valid += 1;
+                    continue;
+                }
+                (Status::Absent, _) => {
+                    count.absent += 1;
+                    continue;
+                }
+                (Status::Fragment, _) => {
+                    count.fragment += 1;
+                    continue;
+                }
+                (Status::Invalid, Some(Offload::Zero)) => {
+                    count.offload_zero += 1;
+                    "offload (zero)"
+                }
+                (Status::Invalid, Some(Offload::Partial)) => {
+                    count.offload_partial += 1;
+                    "offload (pseudo-header sum)"
+                }
+                (Status::Invalid, None) => {
+                    count.invalid += 1;
+                    "MISMATCH"
+                }
+            };
+            if args.hide_offload && check.offload().is_some() {
+                continue;
+            }
+            let expected = check.expected.map_or("-".to_string(), |x| format!("0x{:04x}", x));
+            println!("#{:<6}{:<8}offset {:<6}found 0x{:04x}  expected {:<8}{}",
+                     number, check.protocol, offset + check.offset, check.found, expected, label);
+        }
+    }
+
+    println!();
+    println!("{:<8}{:>8}{:>8}{:>10}{:>14}{:>8}{:>10}{:>10}",
+             "", "valid", "invalid", "off zero", "off partial", "absent", "fragment", "total");
+    for (protocol, x) in &counts {
+        let total = x.valid + x.invalid + x.offload_zero + x.offload_partial + x.absent + x.fragment;
+        println!("{:<8}{:>8}{:>8}{:>10}{:>14}{:>8}{:>10}{:>10}",
+                 protocol, x.valid, x.invalid, x.offload_zero, x.offload_partial, x.absent, x.fragment, total);
+    }
+    println!("{} packets, {} not IP, {} cut at the snapshot length, {} malformed",
+             packets.len(), not_ip, cut, malformed);
+    Ok(malformed == 0 && counts.values().all(|x| x.invalid == 0))
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match &args.command {
+        Some(Command::Experiment(args)) => return match experiment(args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("net-exp-checksum: {}", err);
+                ExitCode::FAILURE
+            }
+        },
+        Some(Command::Capture(args)) => return match check_capture(args) {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::FAILURE,
+            Err(err) => {
+                eprintln!("net-exp-checksum: {}", err);
+                ExitCode::FAILURE
+            }
+        },
+        Some(Command::VerifyPacket(args)) => return match verify_packet(args) {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::FAILURE,
+            Err(err) => {
+                eprintln!("net-exp-checksum: {}", err);
+                ExitCode::FAILURE
+            }
+        },
+        None => {}
+    }
+    let opts = Options {
+        algo: args.algo,
+        kernel: args.kernel.unwrap_or_default(),
+        threads: args.threads,
+    };
+    let files = if args.files.is_empty() { vec!["-".to_string()] } else { args.files.clone() };
     let begin = Instant::now();
-    let mut buf = [0; 2];
-    let mut sum = 0_u16;
-    while let Ok(n) = file.read(&mut buf) {
-        if n == 0 { break; }
-        if n == 1 { buf[1] = 0; }
-        let val = u16::from_be_bytes(buf);
-        let (mut ns, of) = sum.overflowing_add(val);
-        if of { ns += 1; }
-        sum = ns;
-    }
-    let time = begin.elapsed();
-    println!("Elapse: {time:?}");
-    println!("Checksum: {sum:x}");
+    let ok = if args.bench {
+        bench(args.bench_size)
+    } else if args.explain {
+        explain_inputs(&files, args.markdown, args.svg.as_deref())
+    } else if args.check {
+        check_sums(&files, opts, args.quiet)
+    } else {
+        print_sums(&files, opts)
+    };
+    if args.time {
+        eprintln!("Elapse: {:?}", begin.elapsed());
+    }
+    if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
 }
@@ -0,0 +1,393 @@
+//! Internet checksums of protocol headers: the IPv4 header, and UDP, TCP, ICMP and ICMPv6
+//! messages, the transport ones covering a pseudo-header of the IP addresses as well.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use crate::InternetChecksum;
+
+pub const ICMP: u8 = 1;
+pub const TCP: u8 = 6;
+pub const UDP: u8 = 17;
+pub const ICMPV6: u8 = 58;
+
+/// Offset of the checksum field in an IPv4 header.
+pub const IPV4_FIELD: usize = 10;
+/// Offset of the checksum field in a TCP header.
+pub const TCP_FIELD: usize = 16;
+/// Offset of the checksum field in a UDP header.
+pub const UDP_FIELD: usize = 6;
+/// Offset of the checksum field in ICMP and ICMPv6 messages.
+pub const ICMP_FIELD: usize = 2;
+
+/// Hasher holding the sum of the IPv4 pseudo-header of RFC 793 and RFC 768.
+pub fn ipv4_pseudo_header(src: [u8; 4], dst: [u8; 4], protocol: u8, len: u16) -> InternetChecksum {
+    let mut hasher = InternetChecksum::new();
+    hasher.update(&src);
+    hasher.update(&dst);
+    hasher.update(&[0, protocol]);
+    hasher.update(&len.to_be_bytes());
+    hasher
+}
+
+/// Hasher holding the sum of the IPv6 pseudo-header of RFC 8200.
+pub fn ipv6_pseudo_header(src: [u8; 16], dst: [u8; 16], next_header: u8, len: u32) -> InternetChecksum {
+    let mut hasher = InternetChecksum::new();
+    hasher.update(&src);
+    hasher.update(&dst);
+    hasher.update(&len.to_be_bytes());
+    hasher.update(&[0, 0, 0, next_header]);
+    hasher
+}
+
+/// Checksum of data following what is added to a hasher, taking the checksum field at an offset
+/// as zero, which is the value the field must hold.
+fn with_field_zeroed(mut hasher: InternetChecksum, data: &[u8], field: usize) -> u16 {
+    hasher.update(&data[..field]);
+    hasher.update(&[0, 0]);
+    hasher.update(&data[field + 2..]);
+    hasher.finish()
+}
+
+/// Checksum of an IPv4 header, whose checksum field is skipped. Panics if the header is shorter
+/// than its checksum field, as do checksums of other headers.
+pub fn ipv4_header(header: &[u8]) -> u16 {
+    with_field_zeroed(InternetChecksum::new(), header, IPV4_FIELD)
+}
+
+/// Checksum of a TCP segment, following its pseudo-header.
+pub fn tcp(pseudo: InternetChecksum, segment: &[u8]) -> u16 {
+    with_field_zeroed(pseudo, segment, TCP_FIELD)
+}
+
+/// Checksum of a UDP datagram, following its pseudo-header. A checksum computed as zero is sent
+/// as all ones, for zero means no checksum.
+pub fn udp(pseudo: InternetChecksum, datagram: &[u8]) -> u16 {
+    match with_field_zeroed(pseudo, datagram, UDP_FIELD) {
+        0 => 0xffff,
+        x => x,
+    }
+}
+
+/// Checksum of an ICMP message, which has no pseudo-header.
+pub fn icmp(message: &[u8]) -> u16 {
+    with_field_zeroed(InternetChecksum::new(), message, ICMP_FIELD)
+}
+
+/// Checksum of an ICMPv6 message, following its pseudo-header.
+pub fn icmpv6(pseudo: InternetChecksum, message: &[u8]) -> u16 {
+    with_field_zeroed(pseudo, message, ICMP_FIELD)
+}
+
+/// Decode hex digits separated by whitespace or colons, with an optional `0x` prefix, as packets
+/// copied from Wireshark are.
+pub fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    let digits = text.strip_prefix("0x").unwrap_or(text)
+        .chars()
+        .filter(|x| !x.is_whitespace() && *x != ':')
+        .collect::<Vec<_>>();
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    digits.chunks(2)
+        .map(|x| Some((x[0].to_digit(16)? * 16 + x[1].to_digit(16)?) as u8))
+        .collect()
+}
+
+/// Error of a packet that cannot be checked.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PacketError {
+    /// Packet shorter than a header or a length field says, at a layer.
+    Truncated { layer: &'static str, needed: usize, len: usize },
+    /// Header length or total length of an IPv4 header out of range.
+    BadLength { layer: &'static str, len: usize },
+    /// Version of IP neither 4 nor 6.
+    Version(u8),
+    /// EtherType of an Ethernet frame carrying neither IPv4 nor IPv6.
+    EtherType(u16),
+}
+
+impl Display for PacketError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PacketError::Truncated { layer, needed, len } =>
+                write!(f, "{} truncated, {} bytes needed but {} left", layer, needed, len),
+            PacketError::BadLength { layer, len } => write!(f, "{} length {} out of range", layer, len),
+            PacketError::Version(x) => write!(f, "IP version {} is not 4 or 6", x),
+            PacketError::EtherType(x) => write!(f, "EtherType {:#06x} is not IPv4 or IPv6", x),
+        }
+    }
+}
+
+impl Error for PacketError {}
+
+/// Outcome of checking a checksum field.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Status {
+    Valid,
+    Invalid,
+    /// Zero in a UDP datagram over IPv4, sent without checksum.
+    Absent,
+    /// Checksum over a whole datagram, of which the packet is only a fragment.
+    Fragment,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Status::Valid => "valid",
+            Status::Invalid => "invalid",
+            Status::Absent => "absent",
+            Status::Fragment => "fragment",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Checksum field of a packet checked.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FieldCheck {
+    /// Protocol of the header holding the field: IPv4, TCP, UDP, ICMP or ICMPv6.
+    pub protocol: &'static str,
+    /// Offset of the field in the packet.
+    pub offset: usize,
+    /// Value of the field.
+    pub found: u16,
+    /// Value the field should hold, unless it cannot be computed from a fragment.
+    pub expected: Option<u16>,
+    pub status: Status,
+    /// Sum of the pseudo-header, for TCP, UDP and ICMPv6.
+    pub pseudo: Option<u16>,
+}
+
+/// Checksum left for the network card to compute, by a host capturing packets it sends.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Offload {
+    /// Field left zero.
+    Zero,
+    /// Field holding the sum of the pseudo-header only, not complemented, for the card to add
+    /// the rest to. The length in the pseudo-header is zero in segments the card splits (TSO),
+    /// whose IPv4 total length is zero too.
+    Partial,
+}
+
+impl FieldCheck {
+    /// How the checksum is left to the network card, if it is invalid in a way only a checksum
+    /// offloaded leaves it. IPv4 headers have no pseudo-header, so only zero is taken as offload.
+    pub fn offload(&self) -> Option<Offload> {
+        if self.status != Status::Invalid {
+            return None;
+        }
+        if self.found == 0 {
+            Some(Offload::Zero)
+        } else if Some(self.found) == self.pseudo {
+            Some(Offload::Partial)
+        } else {
+            None
+        }
+    }
+}
+
+/// Data shorter than a header or a length field of a layer says, the layer being named or
+/// typed as the parser taking it likes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Truncated<L> {
+    pub layer: L,
+    pub needed: usize,
+    pub len: usize,
+}
+
+impl From<Truncated<&'static str>> for PacketError {
+    fn from(x: Truncated<&'static str>) -> Self {
+        PacketError::Truncated { layer: x.layer, needed: x.needed, len: x.len }
+    }
+}
+
+/// Check data holds the bytes a layer needs, to be converted into the error of a parser by `?`.
+pub fn need<L>(layer: L, data: &[u8], needed: usize) -> Result<(), Truncated<L>> {
+    if data.len() < needed {
+        return Err(Truncated { layer, needed, len: data.len() });
+    }
+    Ok(())
+}
+
+/// Field of 16 bits in network byte order at an offset, which must be in the data.
+pub fn field(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+/// Check checksum fields of an Ethernet II frame carrying IPv4 or IPv6.
+pub fn verify_frame(frame: &[u8]) -> Result<Vec<FieldCheck>, PacketError> {
+    need("Ethernet", frame, 14)?;
+    let (offset, ether_type) = match field(frame, 12) {
+        // a single 802.1Q tag
+        0x8100 => {
+            need("802.1Q", frame, 18)?;
+            (18, field(frame, 16))
+        }
+        x => (14, x),
+    };
+    if ether_type != 0x0800 && ether_type != 0x86dd {
+        return Err(PacketError::EtherType(ether_type));
+    }
+    let mut checks = verify_packet(&frame[offset..])?;
+    checks.iter_mut().for_each(|x| x.offset += offset);
+    Ok(checks)
+}
+
+/// Check checksum fields of an IPv4 or IPv6 packet: the IPv4 header, and a TCP, UDP, ICMP or
+/// ICMPv6 message carried unless it is a fragment other than the first. Other protocols carried
+/// are not checked. An IPv4 total length of zero, left for the card to fill in by a host
+/// offloading segmentation, is taken as the length of the packet.
+pub fn verify_packet(packet: &[u8]) -> Result<Vec<FieldCheck>, PacketError> {
+    need("IP", packet, 1)?;
+    match packet[0] >> 4 {
+        4 => verify_ipv4(packet),
+        6 => verify_ipv6(packet),
+        x => Err(PacketError::Version(x)),
+    }
+}
+
+fn verify_ipv4(packet: &[u8]) -> Result<Vec<FieldCheck>, PacketError> {
+    need("IPv4", packet, 20)?;
+    let header_len = (packet[0] & 0x0f) as usize * 4;
+    // a segment too large to send whole, which the network card splits and fills lengths in
+    let segmented = field(packet, 2) == 0;
+    let total_len = if segmented { packet.len() } else { field(packet, 2) as usize };
+    if header_len < 20 {
+        return Err(PacketError::BadLength { layer: "IPv4 header", len: header_len });
+    }
+    if total_len < header_len {
+        return Err(PacketError::BadLength { layer: "IPv4 total", len: total_len });
+    }
+    need("IPv4", packet, total_len)?;
+    let header = &packet[..header_len];
+    let expected = ipv4_header(header);
+    let found = field(header, IPV4_FIELD);
+    let mut checks = vec![FieldCheck {
+        protocol: "IPv4",
+        offset: IPV4_FIELD,
+        found,
+        expected: Some(expected),
+        status: if found == expected { Status::Valid } else { Status::Invalid },
+        pseudo: None,
+    }];
+
+    let flags_offset = field(packet, 6);
+    let (more, offset) = (flags_offset & 0x2000 != 0, flags_offset & 0x1fff);
+    if offset != 0 {
+        return Ok(checks);
+    }
+    let payload = &packet[header_len..total_len];
+    let protocol = packet[9];
+    let pseudo = || ipv4_pseudo_header(
+        header[12..16].try_into().unwrap(),
+        header[16..20].try_into().unwrap(),
+        protocol,
+        payload.len() as u16,
+    );
+    if let Some(mut check) = verify_transport(protocol, payload, pseudo, true)? {
+        check.offset += header_len;
+        if segmented && check.pseudo.is_some() {
+            check.pseudo = Some(ipv4_pseudo_header(
+                header[12..16].try_into().unwrap(),
+                header[16..20].try_into().unwrap(),
+                protocol,
+                0,
+            ).sum());
+        }
+        if more {
+            check.expected = None;
+            check.status = Status::Fragment;
+        }
+        checks.push(check);
+    }
+    Ok(checks)
+}
+
+fn verify_ipv6(packet: &[u8]) -> Result<Vec<FieldCheck>, PacketError> {
+    need("IPv6", packet, 40)?;
+    let payload_len = field(packet, 4) as usize;
+    need("IPv6", packet, 40 + payload_len)?;
+    let (mut next, mut offset) = (packet[6], 40);
+    let mut fragment = false;
+    // skip extension headers up to the upper-layer header
+    loop {
+        match next {
+            // hop-by-hop options, routing and destination options
+            0 | 43 | 60 => {
+                need("IPv6 extension", &packet[offset..40 + payload_len], 8)?;
+                let len = (packet[offset + 1] as usize + 1) * 8;
+                need("IPv6 extension", &packet[offset..40 + payload_len], len)?;
+                next = packet[offset];
+                offset += len;
+            }
+            // fragment
+            44 => {
+                need("IPv6 fragment", &packet[offset..40 + payload_len], 8)?;
+                let flags_offset = field(packet, offset + 2);
+                if flags_offset & 0xfff8 != 0 {
+                    return Ok(Vec::new());
+                }
+                fragment = flags_offset & 1 != 0;
+                next = packet[offset];
+                offset += 8;
+            }
+            _ => break,
+        }
+    }
+    let payload = &packet[offset..40 + payload_len];
+    let pseudo = || ipv6_pseudo_header(
+        packet[8..24].try_into().unwrap(),
+        packet[24..40].try_into().unwrap(),
+        next,
+        payload.len() as u32,
+    );
+    let mut checks = Vec::new();
+    if let Some(mut check) = verify_transport(next, payload, pseudo, false)? {
+        check.offset += offset;
+        if fragment {
+            check.expected = None;
+            check.status = Status::Fragment;
+        }
+        checks.push(check);
+    }
+    Ok(checks)
+}
+
+/// Check the checksum field of a message carried by IP, with the offset relative to the message.
+fn verify_transport(
+    protocol: u8,
+    payload: &[u8],
+    pseudo: impl Fn() -> InternetChecksum,
+    ipv4: bool,
+) -> Result<Option<FieldCheck>, PacketError> {
+    let (name, offset, expected, pseudo) = match protocol {
+        TCP => {
+            need("TCP", payload, 20)?;
+            ("TCP", TCP_FIELD, tcp(pseudo(), payload), Some(pseudo().sum()))
+        }
+        UDP => {
+            need("UDP", payload, 8)?;
+            ("UDP", UDP_FIELD, udp(pseudo(), payload), Some(pseudo().sum()))
+        }
+        ICMP if ipv4 => {
+            need("ICMP", payload, 4)?;
+            ("ICMP", ICMP_FIELD, icmp(payload), None)
+        }
+        ICMPV6 if !ipv4 => {
+            need("ICMPv6", payload, 4)?;
+            ("ICMPv6", ICMP_FIELD, icmpv6(pseudo(), payload), Some(pseudo().sum()))
+        }
+        _ => return Ok(None),
+    };
+    let found = field(payload, offset);
+    let status = if found == expected {
+        Status::Valid
+    } else if protocol == UDP && ipv4 && found == 0 {
+        Status::Absent
+    } else {
+        Status::Invalid
+    };
+    Ok(Some(FieldCheck { protocol: name, offset, found, expected: Some(expected), status, pseudo }))
+}
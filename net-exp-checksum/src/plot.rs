@@ -0,0 +1,70 @@
+//! Charts of the command line tool, and the size, font and results shared with charts drawn by
+//! users of the library.
+
+use std::error::Error;
+#[cfg(feature = "cli")]
+use plotters::{coord::Shift, prelude::*};
+
+/// Size of charts rendered, in pixels.
+pub const SIZE: (u32, u32) = (1280, 960);
+/// Font of labels.
+pub const FONT: (&str, u32) = ("sans-serif", 24);
+
+pub type PlotResult<T = ()> = Result<T, Box<dyn Error>>;
+
+/// Render grouped bars to `<path>.svg` and `<path>.png`, one group per label and one bar of every
+/// labeled series in each group.
+#[cfg(feature = "cli")]
+pub fn bars(path: &str, y_label: &str, groups: &[String], series: &[(&str, &[f64])]) -> PlotResult {
+    draw_bars(SVGBackend::new(&format!("{}.svg", path), SIZE).into_drawing_area(), y_label, groups, series)?;
+    draw_bars(BitMapBackend::new(&format!("{}.png", path), SIZE).into_drawing_area(), y_label, groups, series)
+}
+
+#[cfg(feature = "cli")]
+fn draw_bars<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    y_label: &str,
+    groups: &[String],
+    series: &[(&str, &[f64])],
+) -> PlotResult where DB::ErrorType: 'static {
+    root.fill(&WHITE)?;
+    let y_max = series.iter()
+        .flat_map(|(_, x)| x.iter().copied())
+        .filter(|x| x.is_finite())
+        .fold(0.0, f64::max);
+    let y_max = if y_max > 0.0 { y_max * 1.1 } else { 1.0 };
+    let mut chart = ChartBuilder::on(&root)
+        .margin(20)
+        .x_label_area_size(60)
+        .y_label_area_size(100)
+        .build_cartesian_2d(0.0..groups.len() as f64, 0.0..y_max)?;
+    chart.configure_mesh()
+        .disable_x_mesh()
+        .x_labels(groups.len() * 2 + 1)
+        .x_label_formatter(&|x| {
+            // label only the middle of groups
+            let i = x.floor() as usize;
+            if (x - x.floor() - 0.5).abs() < 1e-6 { groups.get(i).cloned().unwrap_or_default() } else { String::new() }
+        })
+        .y_desc(y_label)
+        .label_style(FONT)
+        .draw()?;
+    let width = 0.8 / series.len().max(1) as f64;
+    for (i, (label, values)) in series.iter().enumerate() {
+        let color = Palette99::pick(i).to_rgba();
+        chart.draw_series(values.iter().enumerate().filter(|(_, y)| y.is_finite()).map(|(j, y)| {
+            let x = j as f64 + 0.1 + i as f64 * width;
+            Rectangle::new([(x, 0.0), (x + width, *y)], color.filled())
+        }))?
+            .label(*label)
+            .legend(move |(x, y)| Rectangle::new([(x, y - 8), (x + 16, y + 8)], color.filled()));
+    }
+    chart.configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .label_font(FONT)
+        .draw()?;
+    root.present()?;
+    Ok(())
+}
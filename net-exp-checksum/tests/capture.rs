@@ -0,0 +1,101 @@
+mod common;
+
+use net_exp_checksum::capture::{self, CaptureError, Packet, LINKTYPE_ETHERNET, LINKTYPE_RAW};
+use net_exp_checksum::packet::{self, Offload, Status};
+use common::{hex, sample, NTP_REPLY};
+
+/// Counts of checksum fields valid, offloaded and invalid otherwise over all packets.
+fn tally(packets: &[Packet]) -> (usize, usize, usize) {
+    let mut counts = (0, 0, 0);
+    for x in packets {
+        let Some(offset) = x.ip_offset() else { continue };
+        for check in packet::verify_packet(&x.data[offset..]).unwrap() {
+            match (check.status, check.offload()) {
+                (Status::Valid, _) => counts.0 += 1,
+                (_, Some(_)) => counts.1 += 1,
+                _ => counts.2 += 1,
+            }
+        }
+    }
+    counts
+}
+
+#[test]
+fn sample_captures_read() {
+    let file = sample("ip-icmp-arp.pcapng");
+    let packets = capture::parse(&file).unwrap();
+    assert_eq!(packets.len(), 64);
+    assert!(packets.iter().all(|x| x.link_type == LINKTYPE_ETHERNET));
+    assert_eq!(packets.iter().filter(|x| x.ip_offset().is_none()).count(), 4);
+    // sent TCP and UDP are offloaded, everything else valid
+    assert_eq!(tally(&packets), (79, 19, 0));
+
+    let file = sample("www-baidu-filtered.pcapng");
+    let packets = capture::parse(&file).unwrap();
+    assert_eq!(packets.len(), 211);
+    assert_eq!(tally(&packets), (422, 0, 0));
+}
+
+#[test]
+fn partial_sums_labeled() {
+    let file = sample("ip-icmp-arp.pcapng");
+    let packets = capture::parse(&file).unwrap();
+    let offloads = packets.iter()
+        .filter_map(|x| Some(packet::verify_packet(&x.data[x.ip_offset()?..]).unwrap()))
+        .flatten()
+        .filter_map(|x| x.offload())
+        .collect::<Vec<_>>();
+    assert!(!offloads.is_empty());
+    assert!(offloads.iter().all(|x| *x == Offload::Partial));
+}
+
+/// pcap file of raw IP packets, in big or little endian.
+fn pcap(packets: &[(&[u8], u32)], big: bool, nanos: bool) -> Vec<u8> {
+    let u32 = |x: u32| if big { x.to_be_bytes() } else { x.to_le_bytes() };
+    let u16 = |x: u16| if big { x.to_be_bytes() } else { x.to_le_bytes() };
+    let mut file = Vec::new();
+    file.extend(u32(if nanos { 0xa1b2_3c4d } else { 0xa1b2_c3d4 }));
+    file.extend(u16(2));
+    file.extend(u16(4));
+    file.extend(u32(0));
+    file.extend(u32(0));
+    file.extend(u32(65535));
+    file.extend(u32(LINKTYPE_RAW));
+    for (data, orig_len) in packets {
+        file.extend(u32(1));
+        file.extend(u32(2));
+        file.extend(u32(data.len() as u32));
+        file.extend(u32(*orig_len));
+        file.extend(*data);
+    }
+    file
+}
+
+#[test]
+fn pcap_either_byte_order() {
+    let data = hex(NTP_REPLY);
+    for (big, nanos) in [(false, false), (true, false), (false, true), (true, true)] {
+        let file = pcap(&[(&data, data.len() as u32), (&data[..20], data.len() as u32)], big, nanos);
+        let packets = capture::parse(&file).unwrap();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0], Packet { link_type: LINKTYPE_RAW, data: &data, orig_len: data.len() as u32 });
+        assert_eq!(packets[0].ip_offset(), Some(0));
+        assert_eq!(packets[1].data.len(), 20);
+    }
+}
+
+#[test]
+fn broken_files_rejected() {
+    let data = hex(NTP_REPLY);
+    let file = pcap(&[(&data, data.len() as u32)], false, false);
+    assert_eq!(capture::parse(&file[..file.len() - 1]), Err(CaptureError::Truncated(40)));
+    assert_eq!(capture::parse(b"GIF89a.."), Err(CaptureError::Magic(0x3846_4947)));
+    assert_eq!(capture::parse(&[0xd4, 0xc3]), Err(CaptureError::Truncated(0)));
+
+    let file = sample("www-baidu-filtered.pcapng");
+    assert!(matches!(capture::parse(&file[..file.len() - 2]), Err(CaptureError::Truncated(_))));
+    let mut bad = file.clone();
+    // length of the section header block
+    bad[4] = 13;
+    assert!(matches!(capture::parse(&bad), Err(CaptureError::BlockLength { offset: 0, .. })));
+}
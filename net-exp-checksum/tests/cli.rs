@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Directory of a test holding a few files to checksum.
+fn work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("net-exp-checksum-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("a.bin"), [0x45, 0x00, 0x00, 0x73]).unwrap();
+    fs::write(dir.join("b.bin"), b"hello, world").unwrap();
+    fs::write(dir.join("sub/c.txt"), b"odd").unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_net-exp-checksum")).args(args).current_dir(dir).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn globs_expanded() {
+    let dir = work_dir("glob");
+    let output = run(&dir, &["*.bin", "sub/c.txt"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    // 0x4500 + 0x0073, complemented
+    assert_eq!(stdout(&output), format!("ba8c  a.bin\n{}  b.bin\n{}  sub/c.txt\n",
+                                        &stdout(&run(&dir, &["b.bin"]))[..4], &stdout(&run(&dir, &["sub/c.txt"]))[..4]));
+    assert_eq!(stdout(&run(&dir, &["s?b/*.txt"])), stdout(&run(&dir, &["sub/c.txt"])));
+
+    // a pattern matching nothing or a missing file fails, others still being summed
+    let output = run(&dir, &["*.none", "a.bin", "missing.bin"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "ba8c  a.bin\n");
+    assert!(stderr(&output).contains("*.none: no match"), "{}", stderr(&output));
+    assert!(stderr(&output).contains("missing.bin: "), "{}", stderr(&output));
+    let output = run(&dir, &["[a.bin"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("[a.bin: "), "{}", stderr(&output));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn sums_checked() {
+    let dir = work_dir("check");
+    for algo in ["internet", "crc32"] {
+        let output = run(&dir, &["--algo", algo, "*.bin", "sub/*"]);
+        assert!(output.status.success());
+        fs::write(dir.join("sums"), stdout(&output)).unwrap();
+        let output = run(&dir, &["--algo", algo, "--check", "sums"]);
+        assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+        assert_eq!(stdout(&output), "a.bin: OK\nb.bin: OK\nsub/c.txt: OK\n");
+        assert!(stderr(&output).is_empty());
+        let output = run(&dir, &["--algo", algo, "--check", "--quiet", "sums"]);
+        assert_eq!(output.status.code(), Some(0));
+        assert!(stdout(&output).is_empty());
+    }
+
+    // binary mode, upper case digits and blank lines, as written by sha256sum
+    fs::write(dir.join("sums"), "BA8C *a.bin\n\n").unwrap();
+    let output = run(&dir, &["-c", "sums"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "a.bin: OK\n");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn check_failures_reported() {
+    let dir = work_dir("fail");
+    let cases = [
+        ("ba8d  a.bin\n", "a.bin: FAILED\n", "1 computed checksums did NOT match"),
+        ("ba8c  missing.bin\n", "missing.bin: FAILED open or read\n", "1 listed files could not be read"),
+        // one space only, a tab, no name, no sum, and a sum not in hex
+        ("ba8c a.bin\nba8c\ta.bin\nba8c  \n  a.bin\nxyz  a.bin\n", "", "5 lines are improperly formatted"),
+    ];
+    for (list, out, warning) in cases {
+        fs::write(dir.join("sums"), format!("ba8c  a.bin\n{}", list)).unwrap();
+        let output = run(&dir, &["--check", "sums"]);
+        assert_eq!(output.status.code(), Some(1), "{:?}", list);
+        assert_eq!(stdout(&output), format!("a.bin: OK\n{}", out));
+        assert!(stderr(&output).contains(warning), "{}", stderr(&output));
+    }
+
+    // a list missing fails, other lists still being checked
+    fs::write(dir.join("sums"), "ba8c  a.bin\n").unwrap();
+    let output = run(&dir, &["--check", "missing", "sums"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "a.bin: OK\n");
+    assert!(stderr(&output).contains("missing: "));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn explain_diagrams_not_overwritten() {
+    let dir = work_dir("explain");
+    fs::create_dir_all(dir.join("other")).unwrap();
+    fs::write(dir.join("other/a.bin"), b"other").unwrap();
+    let output = run(&dir, &["--explain", "--svg", "svg", "a.bin", "other/a.bin", "b.bin"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("written for a.bin already, not overwritten for other/a.bin"), "{}", stderr(&output));
+    assert!(dir.join("svg/b.bin.svg").exists());
+    // the diagram of the first input kept
+    assert!(run(&dir, &["--explain", "--svg", "first", "a.bin"]).status.success());
+    assert_eq!(fs::read(dir.join("svg/a.bin.svg")).unwrap(), fs::read(dir.join("first/a.bin.svg")).unwrap());
+
+    // only Internet checksums are explained
+    let output = run(&dir, &["--explain", "--algo", "crc32", "a.bin"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("--algo"), "{}", stderr(&output));
+    fs::remove_dir_all(dir).unwrap();
+}
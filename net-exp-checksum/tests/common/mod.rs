@@ -0,0 +1,19 @@
+//! Fixtures shared by tests of packets.
+#![allow(dead_code)]
+
+use std::fs;
+use net_exp_checksum::packet;
+
+/// NTP reply captured in net-exp-wireshark, received with checksums computed by its sender.
+pub const NTP_REPLY: &str = "4548004c32c8400027113523dbe5e018c0a86ec7007bebcb00380b14240200eb00000a4a0000003f6fcb06\
+0de8ec6656386444e7e8ec677a0c7fd944e8ec677a612401b9e8ec677a612a0fd6";
+
+/// Bytes of hex digits.
+pub fn hex(text: &str) -> Vec<u8> {
+    packet::parse_hex(text).unwrap()
+}
+
+/// File of a sample capture in net-exp-wireshark.
+pub fn sample(name: &str) -> Vec<u8> {
+    fs::read(format!("{}/../net-exp-wireshark/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap()
+}
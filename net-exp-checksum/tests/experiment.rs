@@ -0,0 +1,71 @@
+use net_exp_checksum::Algorithm;
+use net_exp_checksum::experiment::{run, Pattern, XorShift};
+
+fn payloads(count: usize, size: usize) -> Vec<Vec<u8>> {
+    let mut rng = XorShift::new(7);
+    (0..count)
+        .map(|_| {
+            let mut x = vec![0; size];
+            rng.fill(&mut x);
+            x
+        })
+        .collect()
+}
+
+#[test]
+fn swapped_words_missed_by_internet_only() {
+    let outcomes = run(&payloads(4, 64), &[Pattern::Swap], &[Algorithm::Internet, Algorithm::Crc32], 1000, 1);
+    assert_eq!(outcomes[0].trials, 1000);
+    assert_eq!(outcomes[0].undetected, 1000);
+    assert_eq!(outcomes[1].undetected, 0);
+}
+
+#[test]
+fn short_errors_always_detected() {
+    // every burst of up to 15 bits changes the sum of the words it spans
+    let patterns = [Pattern::Bit, Pattern::Burst(2), Pattern::Burst(15)];
+    for x in run(&payloads(4, 64), &patterns, &Algorithm::ALL, 2000, 2) {
+        assert_eq!(x.undetected, 0, "{} {}", x.pattern, x.algo);
+        assert_eq!(x.detection_rate(), 1.0);
+    }
+    for x in run(&payloads(4, 64), &[Pattern::Burst(32)], &[Algorithm::Crc32, Algorithm::Crc32c], 2000, 3) {
+        assert_eq!(x.undetected, 0, "{} {}", x.pattern, x.algo);
+    }
+}
+
+#[test]
+fn injected_errors_change_data() {
+    let mut rng = XorShift::new(3);
+    let original = payloads(1, 32).remove(0);
+    for pattern in [Pattern::Bit, Pattern::Burst(9), Pattern::Swap, Pattern::Random(5)] {
+        let mut data = original.clone();
+        assert!(pattern.inject(&mut data, &mut rng));
+        assert_ne!(data, original, "{}", pattern);
+    }
+    let flipped = |pattern: Pattern, rng: &mut XorShift| {
+        let mut data = original.clone();
+        pattern.inject(&mut data, rng);
+        data.iter().zip(&original).map(|(x, y)| (x ^ y).count_ones()).sum::<u32>()
+    };
+    assert_eq!(flipped(Pattern::Bit, &mut rng), 1);
+    assert_eq!(flipped(Pattern::Random(5), &mut rng), 5);
+}
+
+#[test]
+fn uncorruptible_payloads_skipped() {
+    let mut rng = XorShift::new(4);
+    assert!(!Pattern::Swap.inject(&mut [0; 16], &mut rng));
+    assert!(!Pattern::Burst(9).inject(&mut [0; 1], &mut rng));
+    let outcomes = run(&[vec![0; 16]], &[Pattern::Swap], &[Algorithm::Internet], 10, 5);
+    assert_eq!(outcomes[0].trials, 0);
+}
+
+#[test]
+fn patterns_parsed() {
+    for pattern in Pattern::DEFAULT {
+        assert_eq!(pattern.to_string().parse::<Pattern>(), Ok(pattern));
+    }
+    assert!("burst".parse::<Pattern>().is_err());
+    assert!("burst:0".parse::<Pattern>().is_err());
+    assert!("swap:2".parse::<Pattern>().is_err());
+}
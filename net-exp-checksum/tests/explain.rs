@@ -0,0 +1,56 @@
+use proptest::prelude::*;
+use net_exp_checksum::{checksum, InternetChecksum};
+use net_exp_checksum::explain::explain;
+
+#[test]
+fn rfc1071_example_steps() {
+    let x = explain(&[0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7]);
+    let sums = x.steps.iter().map(|x| (x.word, x.raw(), x.carry, x.after)).collect::<Vec<_>>();
+    assert_eq!(sums, [
+        (0x0001, 0x00001, false, 0x0001),
+        (0xf203, 0x0f204, false, 0xf204),
+        (0xf4f5, 0x1e6f9, true, 0xe6fa),
+        (0xf6f7, 0x1ddf1, true, 0xddf2),
+    ]);
+    assert_eq!((x.sum, x.checksum), (0xddf2, 0x220d));
+}
+
+#[test]
+fn odd_byte_padded() {
+    let x = explain(b"abc");
+    assert_eq!(x.steps.len(), 2);
+    assert!(!x.steps[0].padded);
+    assert!(x.steps[1].padded);
+    assert_eq!((x.steps[1].offset, x.steps[1].word), (2, 0x6300));
+    assert!(x.to_table().contains("0x6300 (padded)"));
+}
+
+#[test]
+fn renders_every_step() {
+    let x = explain(&[0xff; 7]);
+    let table = x.to_table();
+    assert_eq!(table.lines().count(), x.steps.len() + 3);
+    assert!(table.contains("1111 1111 1111 1111"));
+    let markdown = x.to_markdown();
+    assert!(markdown.contains("| 4 | 6 | `0xff00 (padded)`"));
+    assert!(markdown.contains(&format!("`0x{:04x}`", x.checksum)));
+    let svg = x.to_svg();
+    assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+    // one arrow for every carry wrapped around
+    let carries = x.steps.iter().filter(|x| x.carry).count();
+    assert_eq!(svg.matches("marker-end").count(), carries);
+}
+
+proptest! {
+    #[test]
+    fn agrees_with_checksum(data in prop::collection::vec(any::<u8>(), 0..512)) {
+        let x = explain(&data);
+        prop_assert_eq!(x.checksum, checksum(&data));
+        let mut hasher = InternetChecksum::new();
+        hasher.update(&data);
+        prop_assert_eq!(x.sum, hasher.sum());
+        for pair in x.steps.windows(2) {
+            prop_assert_eq!(pair[0].after, pair[1].before);
+        }
+    }
+}
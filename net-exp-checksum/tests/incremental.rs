@@ -0,0 +1,91 @@
+mod common;
+
+use proptest::prelude::*;
+use net_exp_checksum::{checksum, verify};
+use net_exp_checksum::incremental::{update_bytes, update_u32, update_word};
+use net_exp_checksum::packet;
+use common::hex;
+
+fn word(data: &[u8], i: usize) -> u16 {
+    u16::from_be_bytes([data[2 * i], data[2 * i + 1]])
+}
+
+#[test]
+fn rfc1624_example_gives_positive_zero() {
+    // Eqn. 2 of RFC 1141 gives 0xffff here, while recomputation gives 0x0000
+    assert_eq!(update_word(0xdd2f, 0x5555, 0x3285), 0x0000);
+}
+
+#[test]
+fn ttl_decremented() {
+    let mut header = hex("45000073000040004011b861c0a80001c0a800c7");
+    let old = word(&header, 4);
+    header[8] -= 1;
+    let sum = update_word(0xb861, old, word(&header, 4));
+    assert_eq!(sum, packet::ipv4_header(&header));
+    header[10..12].copy_from_slice(&sum.to_be_bytes());
+    assert!(verify(&header));
+}
+
+#[test]
+fn address_rewritten() {
+    let mut header = hex("45000073000040004011b861c0a80001c0a800c7");
+    let new = 0x0a00_0001_u32;
+    let sum = update_u32(0xb861, 0xc0a8_0001, new);
+    header[12..16].copy_from_slice(&new.to_be_bytes());
+    assert_eq!(sum, packet::ipv4_header(&header));
+}
+
+/// Data of at least a word. Data changed to all zeros is skipped, being the only data whose
+/// recomputed checksum is -0.
+fn data() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 2..256)
+}
+
+proptest! {
+    #[test]
+    fn word_update_matches_recomputation(mut data in data(), i in any::<prop::sample::Index>(), new in any::<u16>()) {
+        let i = i.index(data.len() / 2);
+        let before = checksum(&data);
+        let old = word(&data, i);
+        data[2 * i..2 * i + 2].copy_from_slice(&new.to_be_bytes());
+        prop_assume!(data.iter().any(|x| *x != 0));
+        prop_assert_eq!(update_word(before, old, new), checksum(&data));
+    }
+
+    #[test]
+    fn byte_update_matches_recomputation(mut data in data(),
+                                         start in any::<prop::sample::Index>(),
+                                         len in any::<prop::sample::Index>(),
+                                         seed in any::<u64>()) {
+        let start = start.index(data.len());
+        let len = len.index(data.len() - start + 1);
+        let before = checksum(&data);
+        let old = data[start..start + len].to_vec();
+        let new = (0..len).map(|x| (seed >> (x % 8 * 8)) as u8 ^ x as u8).collect::<Vec<_>>();
+        data[start..start + len].copy_from_slice(&new);
+        prop_assume!(data.iter().any(|x| *x != 0));
+        prop_assert_eq!(update_bytes(before, start, &old, &new), checksum(&data));
+    }
+
+    #[test]
+    fn successive_updates_match_recomputation(mut data in data(),
+                                              changes in prop::collection::vec((any::<prop::sample::Index>(), any::<u16>()), 1..32)) {
+        let mut sum = checksum(&data);
+        for (i, new) in changes {
+            let i = i.index(data.len() / 2);
+            sum = update_word(sum, word(&data, i), new);
+            data[2 * i..2 * i + 2].copy_from_slice(&new.to_be_bytes());
+        }
+        prop_assume!(data.iter().any(|x| *x != 0));
+        prop_assert_eq!(sum, checksum(&data));
+    }
+}
+
+#[test]
+fn all_zeros_negative_zero() {
+    // the only disagreement: data changed to all zeros recomputes to 0xffff, the same in one's
+    // complement as the 0x0000 of the update
+    assert_eq!(checksum(&[0, 0]), 0xffff);
+    assert_eq!(update_word(checksum(&[0, 1]), 1, 0), 0x0000);
+}
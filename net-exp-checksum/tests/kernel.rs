@@ -0,0 +1,80 @@
+use std::fs;
+use proptest::prelude::*;
+use net_exp_checksum::{read_file_parallel, InternetChecksum, Kernel};
+
+fn sum_with(kernel: Kernel, data: &[u8]) -> u16 {
+    let mut hasher = InternetChecksum::with_kernel(kernel);
+    hasher.update(data);
+    hasher.sum()
+}
+
+proptest! {
+    #[test]
+    fn kernels_agree(data in prop::collection::vec(any::<u8>(), 0..4096), skip in 0usize..64) {
+        // slices starting anywhere, so that loads are unaligned
+        let data = &data[skip.min(data.len())..];
+        let expected = sum_with(Kernel::Scalar, data);
+        for kernel in Kernel::ALL.into_iter().filter(|x| x.available()) {
+            prop_assert_eq!(sum_with(kernel, data), expected, "kernel {}", kernel);
+        }
+    }
+
+    #[test]
+    fn parallel_agrees(data in prop::collection::vec(any::<u8>(), 0..4096), head in 0usize..3,
+                       threads in 1usize..9) {
+        let head = head.min(data.len());
+        let expected = sum_with(Kernel::Scalar, &data);
+        let mut hasher = InternetChecksum::new();
+        hasher.update(&data[..head]);
+        hasher.update_parallel(&data[head..], threads);
+        prop_assert_eq!(hasher.sum(), expected);
+    }
+}
+
+#[test]
+fn carries_of_large_data() {
+    // enough all-ones words to overflow 32-bit lanes of SIMD kernels many times over
+    let data = vec![0xff; 1 << 24];
+    for kernel in Kernel::ALL.into_iter().filter(|x| x.available()) {
+        assert_eq!(sum_with(kernel, &data), 0xffff, "kernel {}", kernel);
+        assert_eq!(sum_with(kernel, &data[1..data.len() - 1]), 0xffff, "kernel {}", kernel);
+    }
+    let data = (0..1 << 24).map(|x| (x * 7 + x / 251) as u8).collect::<Vec<_>>();
+    let expected = sum_with(Kernel::Scalar, &data);
+    for kernel in Kernel::ALL.into_iter().filter(|x| x.available()) {
+        assert_eq!(sum_with(kernel, &data), expected, "kernel {}", kernel);
+    }
+}
+
+#[test]
+fn file_parallel_agrees() {
+    let path = std::env::temp_dir().join(format!("net-exp-checksum-{}.bin", std::process::id()));
+    for len in [0, 1, 2, 3, 1001, 65537] {
+        let data = (0..len).map(|x| (x * 31 + 7) as u8).collect::<Vec<_>>();
+        fs::write(&path, &data).unwrap();
+        let expected = sum_with(Kernel::Scalar, &data);
+        for threads in [1, 2, 3, 8] {
+            let hasher = read_file_parallel(&path, threads, Kernel::detect()).unwrap();
+            assert_eq!(hasher.sum(), expected, "{} bytes on {} threads", len, threads);
+        }
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn file_parallel_streams_fifo() {
+    // a pipe has no length to split by, and must be read through instead
+    let path = std::env::temp_dir().join(format!("net-exp-checksum-{}.fifo", std::process::id()));
+    let _ = fs::remove_file(&path);
+    assert!(std::process::Command::new("mkfifo").arg(&path).status().unwrap().success());
+    let data = (0..100_001).map(|x| (x * 31 + 7) as u8).collect::<Vec<_>>();
+    let writer = {
+        let (path, data) = (path.clone(), data.clone());
+        std::thread::spawn(move || fs::write(path, data).unwrap())
+    };
+    let hasher = read_file_parallel(&path, 4, Kernel::detect()).unwrap();
+    writer.join().unwrap();
+    assert_eq!(hasher.sum(), sum_with(Kernel::Scalar, &data));
+    fs::remove_file(&path).unwrap();
+}
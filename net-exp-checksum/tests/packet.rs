@@ -0,0 +1,150 @@
+mod common;
+
+use net_exp_checksum::packet::{self, FieldCheck, Offload, PacketError, Status};
+use net_exp_checksum::verify;
+use common::{hex, NTP_REPLY};
+
+// packets captured in net-exp-wireshark, received with checksums computed by their senders
+const TCP_ACK: &str = "45480034c7b640002e067b9bdcf6fcc3c0a86ec701bbed9850f427407803702b8010001634c8000001010\
+80adda242c4c9e6feaf";
+const ECHO_REPLY_V6: &str = "6040000000403a3120010da82032100600100000021300512409895a0c895a88f6f9fd83e1b62b438100\
+471e0001000107e9416500000000ac010d0000000000101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f30\
+31323334353637";
+// sent by the capturing host, whose NIC fills the TCP checksum in later
+const TCP_OFFLOADED: &str = "45000034f871400040063928c0a86ec7dcf6fcc3edca01bbc2cc63debb92f765801001f50951000001\
+01080ac9e77613dda1cb35";
+
+fn statuses(checks: &[FieldCheck]) -> Vec<(&str, Status)> {
+    checks.iter().map(|x| (x.protocol, x.status)).collect()
+}
+
+#[test]
+fn captured_packets_valid() {
+    let checks = packet::verify_packet(&hex(NTP_REPLY)).unwrap();
+    assert_eq!(statuses(&checks), [("IPv4", Status::Valid), ("UDP", Status::Valid)]);
+    assert_eq!((checks[1].offset, checks[1].found), (26, 0x0b14));
+
+    let checks = packet::verify_packet(&hex(TCP_ACK)).unwrap();
+    assert_eq!(statuses(&checks), [("IPv4", Status::Valid), ("TCP", Status::Valid)]);
+    assert_eq!(checks[1].offset, 36);
+
+    let checks = packet::verify_packet(&hex(ECHO_REPLY_V6)).unwrap();
+    assert_eq!(statuses(&checks), [("ICMPv6", Status::Valid)]);
+    assert_eq!((checks[0].offset, checks[0].found), (42, 0x471e));
+}
+
+#[test]
+fn offloaded_checksum_invalid_with_expected_value() {
+    let checks = packet::verify_packet(&hex(TCP_OFFLOADED)).unwrap();
+    assert_eq!(statuses(&checks), [("IPv4", Status::Valid), ("TCP", Status::Invalid)]);
+    assert_eq!(checks[1].found, 0x0951);
+    let expected = checks[1].expected.unwrap();
+
+    let mut fixed = hex(TCP_OFFLOADED);
+    fixed[36..38].copy_from_slice(&expected.to_be_bytes());
+    assert_eq!(statuses(&packet::verify_packet(&fixed).unwrap()), [("IPv4", Status::Valid), ("TCP", Status::Valid)]);
+}
+
+#[test]
+fn offloaded_ipv4_header_zero() {
+    let mut data = hex(TCP_OFFLOADED);
+    data[10..12].copy_from_slice(&[0, 0]);
+    let checks = packet::verify_packet(&data).unwrap();
+    assert_eq!(statuses(&checks), [("IPv4", Status::Invalid), ("TCP", Status::Invalid)]);
+    assert_eq!(checks.iter().map(|x| x.offload()).collect::<Vec<_>>(), [Some(Offload::Zero), Some(Offload::Partial)]);
+    // a header checksum otherwise wrong is not offloaded
+    data[10..12].copy_from_slice(&[0, 1]);
+    assert_eq!(packet::verify_packet(&data).unwrap()[0].offload(), None);
+}
+
+#[test]
+fn segmentation_offloaded() {
+    // a segment of several MSS, with lengths and checksums left for the card to fill in
+    let mut data = hex(TCP_OFFLOADED);
+    data.extend((0..4000).map(|x| x as u8));
+    data[2..4].copy_from_slice(&[0, 0]);
+    data[10..12].copy_from_slice(&[0, 0]);
+    let pseudo = packet::ipv4_pseudo_header(data[12..16].try_into().unwrap(), data[16..20].try_into().unwrap(),
+                                            packet::TCP, 0);
+    data[36..38].copy_from_slice(&pseudo.sum().to_be_bytes());
+    let checks = packet::verify_packet(&data).unwrap();
+    assert_eq!(statuses(&checks), [("IPv4", Status::Invalid), ("TCP", Status::Invalid)]);
+    assert_eq!(checks.iter().map(|x| x.offload()).collect::<Vec<_>>(), [Some(Offload::Zero), Some(Offload::Partial)]);
+
+    // the segment summed over all of it
+    let expected = checks[1].expected.unwrap();
+    data[36..38].copy_from_slice(&expected.to_be_bytes());
+    assert_eq!(packet::verify_packet(&data).unwrap()[1].status, Status::Valid);
+}
+
+#[test]
+fn ipv4_header_field_skipped() {
+    let header = hex("45000073000040004011b861c0a80001c0a800c7");
+    assert_eq!(packet::ipv4_header(&header), 0xb861);
+    assert!(verify(&header));
+}
+
+#[test]
+fn icmp_echo() {
+    let mut message = hex("0800000000010001616263646566");
+    let sum = packet::icmp(&message);
+    message[2..4].copy_from_slice(&sum.to_be_bytes());
+    assert!(verify(&message));
+    assert_eq!(packet::icmp(&message), sum);
+}
+
+#[test]
+fn udp_zero_sent_as_all_ones() {
+    // a datagram whose sum with the pseudo-header is all ones but for the checksum field
+    let pseudo = packet::ipv4_pseudo_header([0; 4], [0; 4], packet::UDP, 8);
+    let mut datagram = [0, 0, 0, 0, 0, 8, 0, 0];
+    // 0x0011 (protocol) + 0x0008 (pseudo length) + 0x0008 (length) + x = 0xffff
+    datagram[0..2].copy_from_slice(&(0xffff_u16 - 0x0011 - 0x0008 - 0x0008).to_be_bytes());
+    assert_eq!(packet::udp(pseudo, &datagram), 0xffff);
+}
+
+#[test]
+fn udp_without_checksum_absent() {
+    let mut data = hex(NTP_REPLY);
+    data[26..28].copy_from_slice(&[0, 0]);
+    let checks = packet::verify_packet(&data).unwrap();
+    assert_eq!(checks[1].status, Status::Absent);
+}
+
+#[test]
+fn ethernet_frame_offsets() {
+    let mut frame = hex("ffffffffffff0011223344550800");
+    frame.extend(hex(NTP_REPLY));
+    let checks = packet::verify_frame(&frame).unwrap();
+    assert_eq!(checks.iter().map(|x| x.offset).collect::<Vec<_>>(), [24, 40]);
+    frame[12..14].copy_from_slice(&[0x08, 0x06]);
+    assert_eq!(packet::verify_frame(&frame), Err(PacketError::EtherType(0x0806)));
+}
+
+#[test]
+fn fragments_not_checked_beyond_header() {
+    let mut data = hex(NTP_REPLY);
+    // more fragments
+    data[6] |= 0x20;
+    let sum = packet::ipv4_header(&data[..20]);
+    data[10..12].copy_from_slice(&sum.to_be_bytes());
+    let checks = packet::verify_packet(&data).unwrap();
+    assert_eq!(statuses(&checks), [("IPv4", Status::Valid), ("UDP", Status::Fragment)]);
+    // a later fragment
+    data[7] = 1;
+    let sum = packet::ipv4_header(&data[..20]);
+    data[10..12].copy_from_slice(&sum.to_be_bytes());
+    assert_eq!(statuses(&packet::verify_packet(&data).unwrap()), [("IPv4", Status::Valid)]);
+}
+
+#[test]
+fn malformed_packets_rejected() {
+    let data = hex(NTP_REPLY);
+    assert!(matches!(packet::verify_packet(&data[..30]), Err(PacketError::Truncated { .. })));
+    assert!(matches!(packet::verify_packet(&data[..10]), Err(PacketError::Truncated { .. })));
+    assert_eq!(packet::verify_packet(&[0x55]), Err(PacketError::Version(5)));
+    let mut bad = data.clone();
+    bad[0] = 0x44;
+    assert!(matches!(packet::verify_packet(&bad), Err(PacketError::BadLength { .. })));
+    assert!(matches!(packet::verify_packet(&hex(ECHO_REPLY_V6)[..50]), Err(PacketError::Truncated { .. })));
+}
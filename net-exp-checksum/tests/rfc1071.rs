@@ -0,0 +1,87 @@
+use net_exp_checksum::{checksum, verify, InternetChecksum};
+
+/// Bytes of the numerical example in section 3 of RFC 1071.
+const EXAMPLE: [u8; 8] = [0x00, 0x01, 0xf2, 0x03, 0xf4, 0xf5, 0xf6, 0xf7];
+
+#[test]
+fn example_sum() {
+    let mut hasher = InternetChecksum::new();
+    hasher.update(&EXAMPLE);
+    assert_eq!(hasher.sum(), 0xddf2);
+    assert_eq!(hasher.finish(), 0x220d);
+    assert_eq!(checksum(&EXAMPLE), 0x220d);
+}
+
+#[test]
+fn example_byte_order_independence() {
+    // the sum of byte-swapped words is the byte-swapped sum, section 2 (B)
+    let swapped = EXAMPLE.chunks(2).flat_map(|x| [x[1], x[0]]).collect::<Vec<_>>();
+    let mut hasher = InternetChecksum::new();
+    hasher.update(&swapped);
+    assert_eq!(hasher.sum(), 0xf2dd);
+}
+
+#[test]
+fn example_split_at_every_boundary() {
+    for i in 0..=EXAMPLE.len() {
+        for j in i..=EXAMPLE.len() {
+            let mut hasher = InternetChecksum::new();
+            hasher.update(&EXAMPLE[..i]);
+            hasher.update(&EXAMPLE[i..j]);
+            hasher.update(&EXAMPLE[j..]);
+            assert_eq!(hasher.sum(), 0xddf2, "split at {} and {}", i, j);
+        }
+    }
+}
+
+#[test]
+fn byte_by_byte() {
+    let mut hasher = InternetChecksum::new();
+    EXAMPLE.iter().for_each(|x| hasher.update(&[*x]));
+    assert_eq!(hasher.finish(), 0x220d);
+}
+
+#[test]
+fn odd_length_is_padded() {
+    assert_eq!(checksum(&[0xab]), !0xab00);
+    assert_eq!(checksum(&[0x00, 0x01, 0xf2]), !(0x0001 + 0xf200));
+    let mut hasher = InternetChecksum::new();
+    hasher.update(&[0x00]);
+    hasher.update(&[0x01, 0xf2]);
+    assert_eq!(hasher.finish(), !(0x0001 + 0xf200));
+}
+
+#[test]
+fn end_around_carry() {
+    // 0xffff + 0x0001 wraps to 0x0001 in one's complement
+    assert_eq!(checksum(&[0xff, 0xff, 0x00, 0x01]), !0x0001);
+    assert_eq!(checksum(&[0xff; 1024]), 0x0000);
+}
+
+#[test]
+fn empty() {
+    assert_eq!(InternetChecksum::new().sum(), 0);
+    assert_eq!(checksum(&[]), 0xffff);
+}
+
+#[test]
+fn verify_with_checksum_appended() {
+    let mut data = EXAMPLE.to_vec();
+    data.extend(checksum(&EXAMPLE).to_be_bytes());
+    assert!(verify(&data));
+    data[3] ^= 0x10;
+    assert!(!verify(&data));
+}
+
+#[test]
+fn verify_with_checksum_inside() {
+    // an IPv4 header, with its checksum at bytes 10 and 11
+    let mut header = [
+        0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00,
+        0xc0, 0xa8, 0x00, 0x01, 0xc0, 0xa8, 0x00, 0xc7,
+    ];
+    let sum = checksum(&header);
+    assert_eq!(sum, 0xb861);
+    header[10..12].copy_from_slice(&sum.to_be_bytes());
+    assert!(verify(&header));
+}
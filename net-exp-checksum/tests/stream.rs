@@ -0,0 +1,103 @@
+use std::io::{self, ErrorKind, Read};
+use proptest::prelude::*;
+use net_exp_checksum::{checksum, Algorithm, InternetChecksum};
+
+/// Reader returning data in chunks of given sizes in turn, failing with given errors before some.
+struct ChunkedReader {
+    data: Vec<u8>,
+    pos: usize,
+    sizes: Vec<usize>,
+    errors: Vec<(usize, ErrorKind)>,
+    call: usize,
+}
+
+impl ChunkedReader {
+    fn new(data: Vec<u8>, sizes: Vec<usize>) -> Self {
+        ChunkedReader { data, pos: 0, sizes, errors: Vec::new(), call: 0 }
+    }
+
+    fn fail_at(mut self, call: usize, kind: ErrorKind) -> Self {
+        self.errors.push((call, kind));
+        self
+    }
+}
+
+impl Read for ChunkedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let call = self.call;
+        self.call += 1;
+        if let Some(i) = self.errors.iter().position(|(x, _)| *x == call) {
+            let (_, kind) = self.errors.remove(i);
+            return Err(io::Error::new(kind, "injected"));
+        }
+        let size = self.sizes.get(call % self.sizes.len().max(1)).copied().unwrap_or(1).max(1);
+        let n = size.min(buf.len()).min(self.data.len() - self.pos);
+        buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+proptest! {
+    #[test]
+    fn updates_independent_of_split(data in prop::collection::vec(any::<u8>(), 0..2048),
+                                    cuts in prop::collection::vec(any::<prop::sample::Index>(), 0..16)) {
+        let mut cuts = cuts.iter().map(|x| x.index(data.len() + 1)).collect::<Vec<_>>();
+        cuts.sort();
+        for algo in Algorithm::ALL {
+            let mut hasher = algo.hasher(Default::default());
+            let mut last = 0;
+            for cut in &cuts {
+                hasher.update(&data[last..*cut]);
+                last = *cut;
+            }
+            hasher.update(&data[last..]);
+            prop_assert_eq!(hasher.checksum(), algo.checksum(&data), "{}", algo);
+        }
+    }
+
+    #[test]
+    fn reads_independent_of_chunking(data in prop::collection::vec(any::<u8>(), 0..4096),
+                                     sizes in prop::collection::vec(1usize..100, 1..16)) {
+        let len = data.len() as u64;
+        let expected = checksum(&data);
+        let mut hasher = InternetChecksum::new();
+        let read = hasher.read_from(ChunkedReader::new(data.clone(), sizes.clone())).unwrap();
+        prop_assert_eq!(read, len);
+        prop_assert_eq!(hasher.finish(), expected);
+        for algo in Algorithm::ALL {
+            let mut hasher = algo.hasher(Default::default());
+            let read = hasher.read_from(&mut ChunkedReader::new(data.clone(), sizes.clone())).unwrap();
+            prop_assert_eq!(read, len);
+            prop_assert_eq!(hasher.checksum(), algo.checksum(&data), "{}", algo);
+        }
+    }
+
+    #[test]
+    fn interrupted_reads_retried(data in prop::collection::vec(any::<u8>(), 1..1024),
+                                 sizes in prop::collection::vec(1usize..10, 1..8),
+                                 at in 0usize..20) {
+        let expected = checksum(&data);
+        let r = ChunkedReader::new(data, sizes).fail_at(at, ErrorKind::Interrupted);
+        let mut hasher = InternetChecksum::new();
+        hasher.read_from(r).unwrap();
+        prop_assert_eq!(hasher.finish(), expected);
+    }
+}
+
+#[test]
+fn single_byte_read_mid_stream_not_padded() {
+    // a short read of one byte must be paired with the next read, not padded
+    let data = vec![0x12, 0x34, 0x56, 0x78, 0x9a];
+    let mut hasher = InternetChecksum::new();
+    hasher.read_from(ChunkedReader::new(data, vec![1, 2, 2])).unwrap();
+    // 0x1234 + 0x5678 + 0x9a00 = 0x102ac, carried around to 0x02ad
+    assert_eq!(hasher.sum(), 0x02ad);
+}
+
+#[test]
+fn errors_reported() {
+    let r = ChunkedReader::new(vec![0; 100], vec![10]).fail_at(3, ErrorKind::BrokenPipe);
+    let err = InternetChecksum::new().read_from(r).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::BrokenPipe);
+}
@@ -0,0 +1,64 @@
+use net_exp_checksum::Algorithm;
+
+const CHECK: &[u8] = b"123456789";
+
+#[test]
+fn crc32() {
+    assert_eq!(Algorithm::Crc32.checksum(CHECK), 0xcbf4_3926);
+    assert_eq!(Algorithm::Crc32.checksum(b"The quick brown fox jumps over the lazy dog"), 0x414f_a339);
+    assert_eq!(Algorithm::Crc32.checksum(b""), 0);
+}
+
+#[test]
+fn crc32c() {
+    // check value and test vectors of RFC 3720 (iSCSI)
+    assert_eq!(Algorithm::Crc32c.checksum(CHECK), 0xe306_9283);
+    assert_eq!(Algorithm::Crc32c.checksum(&[0; 32]), 0x8a91_36aa);
+    assert_eq!(Algorithm::Crc32c.checksum(&[0xff; 32]), 0x62a8_ab43);
+}
+
+#[test]
+fn crc16_ccitt() {
+    assert_eq!(Algorithm::Crc16Ccitt.checksum(CHECK), 0x29b1);
+}
+
+#[test]
+fn fletcher16() {
+    assert_eq!(Algorithm::Fletcher16.checksum(b"abcde"), 0xc8f0);
+    assert_eq!(Algorithm::Fletcher16.checksum(b"abcdef"), 0x2057);
+    assert_eq!(Algorithm::Fletcher16.checksum(b"abcdefgh"), 0x0627);
+}
+
+#[test]
+fn fletcher32() {
+    assert_eq!(Algorithm::Fletcher32.checksum(b"abcde"), 0xf04f_c729);
+    assert_eq!(Algorithm::Fletcher32.checksum(b"abcdef"), 0x5650_2d2a);
+    assert_eq!(Algorithm::Fletcher32.checksum(b"abcdefgh"), 0xebe1_9591);
+}
+
+#[test]
+fn adler32() {
+    assert_eq!(Algorithm::Adler32.checksum(b"Wikipedia"), 0x11e6_0398);
+    assert_eq!(Algorithm::Adler32.checksum(b""), 1);
+}
+
+#[test]
+fn internet() {
+    assert_eq!(Algorithm::Internet.checksum(CHECK), 0xf62a);
+}
+
+#[test]
+fn long_inputs_reduced() {
+    // sums of all ones long enough to need reducing between blocks
+    let data = vec![0xff; 1 << 20];
+    assert_eq!(Algorithm::Fletcher16.checksum(&data), 0);
+    assert_eq!(Algorithm::Fletcher32.checksum(&data), 0);
+    // Adler-32 reduced after every byte
+    let mut a = 1_u64;
+    let mut b = 0_u64;
+    for _ in 0..data.len() {
+        a = (a + 0xff) % 65521;
+        b = (b + a) % 65521;
+    }
+    assert_eq!(Algorithm::Adler32.checksum(&data), b << 16 | a);
+}